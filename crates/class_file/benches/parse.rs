@@ -0,0 +1,97 @@
+//! Compares the owned parser with the borrowed one on the classes of `java.base`, read from the
+//! jimage of the JDK at `JAVA_HOME`.
+
+use std::{env, fs::File, io::Read, path::PathBuf};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use just_class_file::{borrowed, ClassFile};
+use just_jimage::Archive;
+use memmap::Mmap;
+
+fn java_base_classes() -> Vec<Vec<u8>> {
+    let modules_path = env::var("JAVA_HOME")
+        .map(|s| PathBuf::from(s).join("lib/modules"))
+        .expect("JAVA_HOME must be set");
+    let file = File::open(modules_path).unwrap();
+    let mmap = unsafe { Mmap::map(&file).unwrap() };
+    let archive = Archive::parse(mmap).unwrap();
+
+    let mut classes = vec![];
+    for resource in archive.resources() {
+        let name = resource.full_name();
+        if resource.extension() != "class" || !name.starts_with("/java.base/") {
+            continue;
+        }
+
+        let mut bytes = vec![];
+        archive
+            .by_name(&name)
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        classes.push(bytes);
+    }
+    classes
+}
+
+fn parse(c: &mut Criterion) {
+    let classes = java_base_classes();
+
+    let mut group = c.benchmark_group("parse java.base");
+    group.sample_size(10);
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            for bytes in &classes {
+                black_box(ClassFile::parse(std::io::Cursor::new(bytes)).unwrap());
+            }
+        })
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            for bytes in &classes {
+                black_box(borrowed::ClassFile::parse(bytes).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+/// Reads the names and code of every method, the kind of scan the borrowed parser is meant for.
+fn scan_methods(c: &mut Criterion) {
+    let classes = java_base_classes();
+
+    let mut group = c.benchmark_group("scan java.base methods");
+    group.sample_size(10);
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            for bytes in &classes {
+                let class_file = ClassFile::parse(std::io::Cursor::new(bytes)).unwrap();
+                for method in &class_file.methods {
+                    black_box(class_file.method_name(method).unwrap());
+                    black_box(
+                        method
+                            .attributes
+                            .code_attribute()
+                            .map(|code| code.code.len()),
+                    );
+                }
+            }
+        })
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            for bytes in &classes {
+                let class_file = borrowed::ClassFile::parse(bytes).unwrap();
+                for method in &class_file.methods {
+                    black_box(class_file.method_name(method).unwrap());
+                    let code = method.code(&class_file.constant_pool).unwrap();
+                    black_box(code.map(|code| code.code.len()));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, scan_methods);
+criterion_main!(benches);
@@ -1,10 +1,48 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.1-200-E.1
+//
+// Flags are parsed with `from_bits_retain`, so bits without a defined meaning are kept and
+// written back unchanged.
+
+use crate::{ClassFileError, ClassFileVersion, Result};
+
 bitflags::bitflags! {
-  pub struct AccessFlags: u16 {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct ClassAccessFlags: u16 {
+    const PUBLIC = 0x0001;
+    const FINAL = 0x0010;
+    const SUPER = 0x0020;
+    const INTERFACE = 0x0200;
+    const ABSTRACT = 0x0400;
+    const SYNTHETIC = 0x1000;
+    const ANNOTATION = 0x2000;
+    const ENUM = 0x4000;
+    const MODULE = 0x8000;
+  }
+}
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct FieldAccessFlags: u16 {
     const PUBLIC = 0x0001;
     const PRIVATE = 0x0002;
     const PROTECTED = 0x0004;
     const STATIC = 0x0008;
-    const FINAL	= 0x0010;
+    const FINAL = 0x0010;
+    const VOLATILE = 0x0040;
+    const TRANSIENT = 0x0080;
+    const SYNTHETIC = 0x1000;
+    const ENUM = 0x4000;
+  }
+}
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct MethodAccessFlags: u16 {
+    const PUBLIC = 0x0001;
+    const PRIVATE = 0x0002;
+    const PROTECTED = 0x0004;
+    const STATIC = 0x0008;
+    const FINAL = 0x0010;
     const SYNCHRONIZED = 0x0020;
     const BRIDGE = 0x0040;
     const VARARGS = 0x0080;
@@ -14,3 +52,285 @@ bitflags::bitflags! {
     const SYNTHETIC = 0x1000;
   }
 }
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct InnerClassAccessFlags: u16 {
+    const PUBLIC = 0x0001;
+    const PRIVATE = 0x0002;
+    const PROTECTED = 0x0004;
+    const STATIC = 0x0008;
+    const FINAL = 0x0010;
+    const INTERFACE = 0x0200;
+    const ABSTRACT = 0x0400;
+    const SYNTHETIC = 0x1000;
+    const ANNOTATION = 0x2000;
+    const ENUM = 0x4000;
+  }
+}
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct MethodParameterFlags: u16 {
+    const FINAL = 0x0010;
+    const SYNTHETIC = 0x1000;
+    const MANDATED = 0x8000;
+  }
+}
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct ModuleFlags: u16 {
+    const OPEN = 0x0020;
+    const SYNTHETIC = 0x1000;
+    const MANDATED = 0x8000;
+  }
+}
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct RequiresFlags: u16 {
+    const TRANSITIVE = 0x0020;
+    const STATIC_PHASE = 0x0040;
+    const SYNTHETIC = 0x1000;
+    const MANDATED = 0x8000;
+  }
+}
+
+bitflags::bitflags! {
+  /// The flags of both `exports` and `opens` directives.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct ExportsFlags: u16 {
+    const SYNTHETIC = 0x1000;
+    const MANDATED = 0x8000;
+  }
+}
+
+fn illegal(description: &'static str) -> Result<()> {
+    Err(ClassFileError::IllegalAccessFlags(description))
+}
+
+impl ClassAccessFlags {
+    /// Checks the combinations of flags that JVMS §4.1 forbids for a class or interface.
+    pub fn check(self) -> Result<()> {
+        if self.contains(Self::MODULE) {
+            if self != Self::MODULE {
+                return illegal("a module may not have other flags than ACC_MODULE");
+            }
+        } else if self.contains(Self::INTERFACE) {
+            if !self.contains(Self::ABSTRACT) {
+                return illegal("an interface must be ACC_ABSTRACT");
+            }
+            if self.intersects(Self::FINAL | Self::SUPER | Self::ENUM) {
+                return illegal("an interface may not be ACC_FINAL, ACC_SUPER or ACC_ENUM");
+            }
+        } else {
+            if self.contains(Self::ANNOTATION) {
+                return illegal("an annotation interface must be ACC_INTERFACE");
+            }
+            if self.contains(Self::FINAL | Self::ABSTRACT) {
+                return illegal("a class may not be both ACC_FINAL and ACC_ABSTRACT");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FieldAccessFlags {
+    /// Checks the combinations of flags that JVMS §4.5 forbids for a field of a class or
+    /// interface with `class_flags`.
+    pub fn check(self, class_flags: ClassAccessFlags) -> Result<()> {
+        if (self & (Self::PUBLIC | Self::PRIVATE | Self::PROTECTED))
+            .bits()
+            .count_ones()
+            > 1
+        {
+            return illegal(
+                "a field may only have one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED",
+            );
+        }
+        if self.contains(Self::FINAL | Self::VOLATILE) {
+            return illegal("a field may not be both ACC_FINAL and ACC_VOLATILE");
+        }
+        if class_flags.contains(ClassAccessFlags::INTERFACE)
+            && (!self.contains(Self::PUBLIC | Self::STATIC | Self::FINAL)
+                || !(Self::PUBLIC | Self::STATIC | Self::FINAL | Self::SYNTHETIC).contains(self))
+        {
+            return illegal("an interface field must be ACC_PUBLIC, ACC_STATIC and ACC_FINAL");
+        }
+
+        Ok(())
+    }
+}
+
+impl MethodAccessFlags {
+    /// Checks the combinations of flags that JVMS §4.6 forbids for a method of a class or
+    /// interface with `class_flags`. The additional rules for `<init>` depend on the name and are
+    /// not checked here, and `<clinit>` methods are exempt from these rules altogether.
+    pub fn check(self, class_flags: ClassAccessFlags, version: ClassFileVersion) -> Result<()> {
+        if (self & (Self::PUBLIC | Self::PRIVATE | Self::PROTECTED))
+            .bits()
+            .count_ones()
+            > 1
+        {
+            return illegal(
+                "a method may only have one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED",
+            );
+        }
+
+        if class_flags.contains(ClassAccessFlags::INTERFACE) {
+            if self.intersects(Self::PROTECTED | Self::FINAL | Self::SYNCHRONIZED | Self::NATIVE) {
+                return illegal(
+                    "an interface method may not be ACC_PROTECTED, ACC_FINAL, ACC_SYNCHRONIZED or ACC_NATIVE",
+                );
+            }
+            if version.major < 52 && !self.contains(Self::PUBLIC | Self::ABSTRACT) {
+                return illegal(
+                    "an interface method must be ACC_PUBLIC and ACC_ABSTRACT before Java 8",
+                );
+            }
+            if version.major >= 52 && !self.intersects(Self::PUBLIC | Self::PRIVATE) {
+                return illegal("an interface method must be ACC_PUBLIC or ACC_PRIVATE");
+            }
+        }
+
+        // Before Java 1.2 and from Java 17 ACC_STRICT has no meaning and may be combined freely.
+        let strict_is_meaningful = (46..61).contains(&version.major);
+        if self.contains(Self::ABSTRACT)
+            && (self.intersects(
+                Self::PRIVATE | Self::STATIC | Self::FINAL | Self::SYNCHRONIZED | Self::NATIVE,
+            ) || (strict_is_meaningful && self.contains(Self::STRICT)))
+        {
+            return illegal(
+                "an abstract method may not be ACC_PRIVATE, ACC_STATIC, ACC_FINAL, ACC_SYNCHRONIZED, ACC_NATIVE or ACC_STRICT",
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl InnerClassAccessFlags {
+    /// Checks the same combinations as [`ClassAccessFlags::check`], which apply to the flags of
+    /// an inner class as declared in source.
+    pub fn check(self) -> Result<()> {
+        if self.contains(Self::INTERFACE) {
+            if !self.contains(Self::ABSTRACT) {
+                return illegal("an interface must be ACC_ABSTRACT");
+            }
+            if self.intersects(Self::FINAL | Self::ENUM) {
+                return illegal("an interface may not be ACC_FINAL or ACC_ENUM");
+            }
+        } else {
+            if self.contains(Self::ANNOTATION) {
+                return illegal("an annotation interface must be ACC_INTERFACE");
+            }
+            if self.contains(Self::FINAL | Self::ABSTRACT) {
+                return illegal("a class may not be both ACC_FINAL and ACC_ABSTRACT");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod check_tests {
+    use super::*;
+
+    const JAVA_8: ClassFileVersion = ClassFileVersion::new(52, 0);
+
+    #[test]
+    fn it_should_accept_legal_class_flags() {
+        for flags in [
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            ClassAccessFlags::FINAL | ClassAccessFlags::SUPER | ClassAccessFlags::ENUM,
+            ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT | ClassAccessFlags::ANNOTATION,
+            ClassAccessFlags::MODULE,
+        ] {
+            assert!(flags.check().is_ok(), "{:?}", flags);
+        }
+    }
+
+    #[test]
+    fn it_should_reject_illegal_class_flags() {
+        for flags in [
+            ClassAccessFlags::ABSTRACT | ClassAccessFlags::FINAL,
+            ClassAccessFlags::INTERFACE,
+            ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT | ClassAccessFlags::FINAL,
+            ClassAccessFlags::ANNOTATION,
+            ClassAccessFlags::MODULE | ClassAccessFlags::PUBLIC,
+        ] {
+            assert!(
+                matches!(flags.check(), Err(ClassFileError::IllegalAccessFlags(_))),
+                "{:?}",
+                flags
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_check_field_flags() {
+        let interface = ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
+
+        assert!((FieldAccessFlags::PRIVATE | FieldAccessFlags::VOLATILE)
+            .check(ClassAccessFlags::SUPER)
+            .is_ok());
+        assert!(
+            (FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL)
+                .check(interface)
+                .is_ok()
+        );
+        assert!((FieldAccessFlags::PUBLIC | FieldAccessFlags::PRIVATE)
+            .check(ClassAccessFlags::SUPER)
+            .is_err());
+        assert!((FieldAccessFlags::FINAL | FieldAccessFlags::VOLATILE)
+            .check(ClassAccessFlags::SUPER)
+            .is_err());
+        assert!(FieldAccessFlags::PUBLIC.check(interface).is_err());
+    }
+
+    #[test]
+    fn it_should_check_method_flags() {
+        let interface = ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
+
+        assert!((MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT)
+            .check(interface, JAVA_8)
+            .is_ok());
+        assert!((MethodAccessFlags::PRIVATE | MethodAccessFlags::STATIC)
+            .check(interface, JAVA_8)
+            .is_ok());
+        assert!((MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC)
+            .check(interface, ClassFileVersion::new(51, 0))
+            .is_err());
+        assert!(
+            (MethodAccessFlags::PUBLIC | MethodAccessFlags::SYNCHRONIZED)
+                .check(interface, JAVA_8)
+                .is_err()
+        );
+        assert!((MethodAccessFlags::ABSTRACT | MethodAccessFlags::FINAL)
+            .check(ClassAccessFlags::ABSTRACT, JAVA_8)
+            .is_err());
+        assert!((MethodAccessFlags::ABSTRACT | MethodAccessFlags::STRICT)
+            .check(ClassAccessFlags::ABSTRACT, JAVA_8)
+            .is_err());
+        assert!((MethodAccessFlags::ABSTRACT | MethodAccessFlags::STRICT)
+            .check(ClassAccessFlags::ABSTRACT, ClassFileVersion::new(61, 0))
+            .is_ok());
+    }
+
+    #[test]
+    fn it_should_check_inner_class_flags() {
+        assert!(
+            (InnerClassAccessFlags::STATIC | InnerClassAccessFlags::FINAL)
+                .check()
+                .is_ok()
+        );
+        assert!(
+            (InnerClassAccessFlags::ABSTRACT | InnerClassAccessFlags::FINAL)
+                .check()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.16
+//
+// Annotation types are identified by their field descriptor, e.g. `Ljava/lang/Deprecated;`, as
+// they are in the class file. Both visible and invisible annotations are considered, since the
+// retention policy of an annotation type only decides which attribute it is stored in.
+
+use crate::{
+    attributes::{Annotation, ElementValue},
+    class_file::{FieldInfo, MethodInfo},
+    constant_pool::{CpIndex, CpInfo},
+    ClassFile, ClassFileError, Result,
+};
+
+/// An element value with its constants resolved from the constant pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationValue<'a> {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(&'a str),
+    Enum {
+        /// The field descriptor of the enum type.
+        type_name: &'a str,
+        const_name: &'a str,
+    },
+    /// The return descriptor of the class, e.g. `Ljava/lang/String;` or `V`.
+    Class(&'a str),
+    Annotation(&'a Annotation),
+    Array(Vec<AnnotationValue<'a>>),
+}
+
+impl ClassFile {
+    pub fn has_annotation(&self, descriptor: &str) -> bool {
+        self.find_annotation(self.attributes.annotations(), descriptor)
+            .is_some()
+    }
+
+    pub fn field_has_annotation(&self, field: &FieldInfo, descriptor: &str) -> bool {
+        self.find_annotation(field.attributes.annotations(), descriptor)
+            .is_some()
+    }
+
+    pub fn method_has_annotation(&self, method: &MethodInfo, descriptor: &str) -> bool {
+        self.find_annotation(method.attributes.annotations(), descriptor)
+            .is_some()
+    }
+
+    /// Returns the first of `annotations` whose type has the field descriptor `descriptor`.
+    pub fn find_annotation<'a>(
+        &self,
+        annotations: impl IntoIterator<Item = &'a Annotation>,
+        descriptor: &str,
+    ) -> Option<&'a Annotation> {
+        annotations
+            .into_iter()
+            .find(|annotation| self.annotation_type(annotation).ok() == Some(descriptor))
+    }
+
+    /// The field descriptor of the type of the annotation.
+    pub fn annotation_type(&self, annotation: &Annotation) -> Result<&str> {
+        self.constant_pool.utf8(CpIndex::new(annotation.type_index))
+    }
+
+    /// Returns the value of the element named `name`, or `None` if the annotation leaves it out,
+    /// in which case the element has the default value of the annotation type.
+    pub fn annotation_element<'a>(
+        &'a self,
+        annotation: &'a Annotation,
+        name: &str,
+    ) -> Result<Option<AnnotationValue<'a>>> {
+        for pair in &annotation.element_value_pairs {
+            if self
+                .constant_pool
+                .utf8(CpIndex::new(pair.element_name_index))?
+                == name
+            {
+                return self.element_value(&pair.value).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn element_value<'a>(&'a self, value: &'a ElementValue) -> Result<AnnotationValue<'a>> {
+        let constant_pool = &self.constant_pool;
+        let constant = |index: u16| {
+            constant_pool
+                .get(index)
+                .ok_or(ClassFileError::InvalidConstantPoolIndex(index))
+        };
+        let int = |index: u16| match constant(index)? {
+            CpInfo::Integer(value) => Ok(*value),
+            cp_info => Err(ClassFileError::UnexpectedConstantPoolEntry(
+                "Integer",
+                cp_info.clone(),
+            )),
+        };
+
+        // Byte, char, short and boolean constants are stored as CONSTANT_Integer entries, so the
+        // narrowing casts keep the values of a valid class file.
+        Ok(match value {
+            ElementValue::Byte { const_value_index } => {
+                AnnotationValue::Byte(int(*const_value_index)? as i8)
+            }
+            ElementValue::Char { const_value_index } => {
+                AnnotationValue::Char(int(*const_value_index)? as u16)
+            }
+            ElementValue::Short { const_value_index } => {
+                AnnotationValue::Short(int(*const_value_index)? as i16)
+            }
+            ElementValue::Boolean { const_value_index } => {
+                AnnotationValue::Boolean(int(*const_value_index)? != 0)
+            }
+            ElementValue::Int { const_value_index } => {
+                AnnotationValue::Int(int(*const_value_index)?)
+            }
+            ElementValue::Double { const_value_index } => match constant(*const_value_index)? {
+                CpInfo::Double(value) => AnnotationValue::Double(*value),
+                cp_info => {
+                    return Err(ClassFileError::UnexpectedConstantPoolEntry(
+                        "Double",
+                        cp_info.clone(),
+                    ))
+                }
+            },
+            ElementValue::Float { const_value_index } => match constant(*const_value_index)? {
+                CpInfo::Float(value) => AnnotationValue::Float(*value),
+                cp_info => {
+                    return Err(ClassFileError::UnexpectedConstantPoolEntry(
+                        "Float",
+                        cp_info.clone(),
+                    ))
+                }
+            },
+            ElementValue::Long { const_value_index } => match constant(*const_value_index)? {
+                CpInfo::Long(value) => AnnotationValue::Long(*value),
+                cp_info => {
+                    return Err(ClassFileError::UnexpectedConstantPoolEntry(
+                        "Long",
+                        cp_info.clone(),
+                    ))
+                }
+            },
+            ElementValue::String { const_value_index } => {
+                AnnotationValue::String(constant_pool.utf8(CpIndex::new(*const_value_index))?)
+            }
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => AnnotationValue::Enum {
+                type_name: constant_pool.utf8(CpIndex::new(*type_name_index))?,
+                const_name: constant_pool.utf8(CpIndex::new(*const_name_index))?,
+            },
+            ElementValue::Class { class_info_index } => {
+                AnnotationValue::Class(constant_pool.utf8(CpIndex::new(*class_info_index))?)
+            }
+            ElementValue::Annotation(annotation) => AnnotationValue::Annotation(annotation),
+            ElementValue::Array(values) => AnnotationValue::Array(
+                values
+                    .iter()
+                    .map(|value| self.element_value(value))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
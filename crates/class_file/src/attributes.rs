@@ -1,15 +1,16 @@
-use std::io::Cursor;
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7
 
-use crate::{constant_pool::CpInfo, Attribute};
+use crate::{
+    constant_pool::CpInfo, ConstantPool, ExportsFlags, InnerClassAccessFlags, MethodParameterFlags,
+    ModuleFlags, RequiresFlags,
+};
 
-use super::{parser::Parser, ConstantPool};
-
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Attributes(pub Vec<Attribute>);
 impl Attributes {
     pub fn find_by_name(&self, name: &str, constant_pool: &ConstantPool) -> Option<&Attribute> {
         for a in &self.0 {
-            let CpInfo::Utf8(ref s) = constant_pool[a.attribute_name_index] else {
+            let Some(CpInfo::Utf8(s)) = constant_pool.get(a.attribute_name_index) else {
                 continue;
             };
 
@@ -21,14 +22,186 @@ impl Attributes {
         None
     }
 
-    pub fn code_attribute(&self, constant_pool: &ConstantPool) -> Option<CodeAttribute> {
-        Parser::new(Cursor::new(&self.find_by_name("Code", constant_pool)?.info))
-            .parse_code_attribute()
-            .ok()
+    pub fn find_map<'a, T>(&'a self, f: impl FnMut(&'a AttributeInfo) -> Option<T>) -> Option<T> {
+        self.iter().map(|a| &a.info).find_map(f)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Attribute> {
+        self.0.iter()
+    }
+
+    pub fn code_attribute(&self) -> Option<&CodeAttribute> {
+        self.find_map(|info| match info {
+            AttributeInfo::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    pub fn stack_map_table(&self) -> Option<&[StackMapFrame]> {
+        self.find_map(|info| match info {
+            AttributeInfo::StackMapTable(frames) => Some(frames.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn signature_index(&self) -> Option<u16> {
+        self.find_map(|info| match info {
+            AttributeInfo::Signature { signature_index } => Some(*signature_index),
+            _ => None,
+        })
+    }
+
+    pub fn bootstrap_methods(&self) -> Option<&[BootstrapMethod]> {
+        self.find_map(|info| match info {
+            AttributeInfo::BootstrapMethods(methods) => Some(methods.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The annotations of the RuntimeVisibleAnnotations and RuntimeInvisibleAnnotations
+    /// attributes.
+    pub fn annotations(&self) -> impl Iterator<Item = &Annotation> {
+        self.iter().flat_map(|a| match &a.info {
+            AttributeInfo::RuntimeVisibleAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleAnnotations(annotations) => annotations.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// The visible and invisible annotations of the formal parameter at index `parameter` of a
+    /// method.
+    pub fn parameter_annotations(&self, parameter: usize) -> impl Iterator<Item = &Annotation> {
+        self.iter().flat_map(move |a| match &a.info {
+            AttributeInfo::RuntimeVisibleParameterAnnotations(parameters)
+            | AttributeInfo::RuntimeInvisibleParameterAnnotations(parameters) => parameters
+                .get(parameter)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            _ => &[],
+        })
+    }
+
+    /// The annotations of the RuntimeVisibleTypeAnnotations and
+    /// RuntimeInvisibleTypeAnnotations attributes.
+    pub fn type_annotations(&self) -> impl Iterator<Item = &TypeAnnotation> {
+        self.iter().flat_map(|a| match &a.info {
+            AttributeInfo::RuntimeVisibleTypeAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleTypeAnnotations(annotations) => annotations.as_slice(),
+            _ => &[],
+        })
+    }
+
+    pub fn annotation_default(&self) -> Option<&ElementValue> {
+        self.find_map(|info| match info {
+            AttributeInfo::AnnotationDefault(value) => Some(value),
+            _ => None,
+        })
     }
 }
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = &'a Attribute;
+    type IntoIter = std::slice::Iter<'a, Attribute>;
 
-#[derive(Debug)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub attribute_name_index: u16,
+    pub info: AttributeInfo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeInfo {
+    ConstantValue {
+        constantvalue_index: u16,
+    },
+    Code(CodeAttribute),
+    StackMapTable(Vec<StackMapFrame>),
+    Exceptions(Vec<u16>),
+    InnerClasses(Vec<InnerClass>),
+    EnclosingMethod(EnclosingMethodAttribute),
+    Synthetic,
+    Signature {
+        signature_index: u16,
+    },
+    SourceFile {
+        sourcefile_index: u16,
+    },
+    SourceDebugExtension(Vec<u8>),
+    LineNumberTable(Vec<LineNumberTableEntry>),
+    LocalVariableTable(Vec<LocalVariableTableEntry>),
+    LocalVariableTypeTable(Vec<LocalVariableTypeTableEntry>),
+    Deprecated,
+    RuntimeVisibleAnnotations(Vec<Annotation>),
+    RuntimeInvisibleAnnotations(Vec<Annotation>),
+    RuntimeVisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeInvisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeVisibleTypeAnnotations(Vec<TypeAnnotation>),
+    RuntimeInvisibleTypeAnnotations(Vec<TypeAnnotation>),
+    AnnotationDefault(ElementValue),
+    BootstrapMethods(Vec<BootstrapMethod>),
+    MethodParameters(Vec<MethodParameter>),
+    Module(ModuleAttribute),
+    ModulePackages(Vec<u16>),
+    ModuleMainClass {
+        main_class_index: u16,
+    },
+    NestHost {
+        host_class_index: u16,
+    },
+    NestMembers(Vec<u16>),
+    Record(Vec<RecordComponent>),
+    PermittedSubclasses(Vec<u16>),
+    /// An attribute not defined by the JVMS. The bytes are kept as-is so that nothing is lost.
+    Unknown(Vec<u8>),
+}
+impl AttributeInfo {
+    /// The name the attribute is stored under, or `None` for unknown attributes.
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            AttributeInfo::ConstantValue { .. } => "ConstantValue",
+            AttributeInfo::Code(_) => "Code",
+            AttributeInfo::StackMapTable(_) => "StackMapTable",
+            AttributeInfo::Exceptions(_) => "Exceptions",
+            AttributeInfo::InnerClasses(_) => "InnerClasses",
+            AttributeInfo::EnclosingMethod(_) => "EnclosingMethod",
+            AttributeInfo::Synthetic => "Synthetic",
+            AttributeInfo::Signature { .. } => "Signature",
+            AttributeInfo::SourceFile { .. } => "SourceFile",
+            AttributeInfo::SourceDebugExtension(_) => "SourceDebugExtension",
+            AttributeInfo::LineNumberTable(_) => "LineNumberTable",
+            AttributeInfo::LocalVariableTable(_) => "LocalVariableTable",
+            AttributeInfo::LocalVariableTypeTable(_) => "LocalVariableTypeTable",
+            AttributeInfo::Deprecated => "Deprecated",
+            AttributeInfo::RuntimeVisibleAnnotations(_) => "RuntimeVisibleAnnotations",
+            AttributeInfo::RuntimeInvisibleAnnotations(_) => "RuntimeInvisibleAnnotations",
+            AttributeInfo::RuntimeVisibleParameterAnnotations(_) => {
+                "RuntimeVisibleParameterAnnotations"
+            }
+            AttributeInfo::RuntimeInvisibleParameterAnnotations(_) => {
+                "RuntimeInvisibleParameterAnnotations"
+            }
+            AttributeInfo::RuntimeVisibleTypeAnnotations(_) => "RuntimeVisibleTypeAnnotations",
+            AttributeInfo::RuntimeInvisibleTypeAnnotations(_) => "RuntimeInvisibleTypeAnnotations",
+            AttributeInfo::AnnotationDefault(_) => "AnnotationDefault",
+            AttributeInfo::BootstrapMethods(_) => "BootstrapMethods",
+            AttributeInfo::MethodParameters(_) => "MethodParameters",
+            AttributeInfo::Module(_) => "Module",
+            AttributeInfo::ModulePackages(_) => "ModulePackages",
+            AttributeInfo::ModuleMainClass { .. } => "ModuleMainClass",
+            AttributeInfo::NestHost { .. } => "NestHost",
+            AttributeInfo::NestMembers(_) => "NestMembers",
+            AttributeInfo::Record(_) => "Record",
+            AttributeInfo::PermittedSubclasses(_) => "PermittedSubclasses",
+            AttributeInfo::Unknown(_) => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExceptionTableEntry {
     pub start_pc: u16,
     pub end_pc: u16,
@@ -36,7 +209,7 @@ pub struct ExceptionTableEntry {
     pub catch_type: u16,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CodeAttribute {
     pub max_stack: u16,
     pub max_locals: u16,
@@ -44,3 +217,309 @@ pub struct CodeAttribute {
     pub exception_table: Vec<ExceptionTableEntry>,
     pub attributes: Attributes,
 }
+
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.4
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackMapFrame {
+    /// frame_type 0-63. The offset_delta is the value of the frame_type.
+    Same { offset_delta: u16 },
+    /// frame_type 64-127. The offset_delta is frame_type - 64.
+    SameLocals1StackItem {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    /// frame_type 247.
+    SameLocals1StackItemExtended {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    /// frame_type 248-250. `k` is the number of chopped locals, 251 - frame_type.
+    Chop { k: u8, offset_delta: u16 },
+    /// frame_type 251.
+    SameExtended { offset_delta: u16 },
+    /// frame_type 252-254. The number of locals is frame_type - 251.
+    Append {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+    },
+    /// frame_type 255.
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+impl StackMapFrame {
+    pub fn offset_delta(&self) -> u16 {
+        match *self {
+            StackMapFrame::Same { offset_delta }
+            | StackMapFrame::SameLocals1StackItem { offset_delta, .. }
+            | StackMapFrame::SameLocals1StackItemExtended { offset_delta, .. }
+            | StackMapFrame::Chop { offset_delta, .. }
+            | StackMapFrame::SameExtended { offset_delta }
+            | StackMapFrame::Append { offset_delta, .. }
+            | StackMapFrame::Full { offset_delta, .. } => offset_delta,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object { cpool_index: u16 },
+    Uninitialized { offset: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerClass {
+    pub inner_class_info_index: u16,
+    pub outer_class_info_index: u16,
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: InnerClassAccessFlags,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnclosingMethodAttribute {
+    pub class_index: u16,
+    pub method_index: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineNumberTableEntry {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVariableTableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVariableTypeTableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub signature_index: u16,
+    pub index: u16,
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.16
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub type_index: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub value: ElementValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementValue {
+    Byte {
+        const_value_index: u16,
+    },
+    Char {
+        const_value_index: u16,
+    },
+    Double {
+        const_value_index: u16,
+    },
+    Float {
+        const_value_index: u16,
+    },
+    Int {
+        const_value_index: u16,
+    },
+    Long {
+        const_value_index: u16,
+    },
+    Short {
+        const_value_index: u16,
+    },
+    Boolean {
+        const_value_index: u16,
+    },
+    String {
+        const_value_index: u16,
+    },
+    Enum {
+        type_name_index: u16,
+        const_name_index: u16,
+    },
+    Class {
+        class_info_index: u16,
+    },
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+impl ElementValue {
+    pub fn tag(&self) -> u8 {
+        match self {
+            ElementValue::Byte { .. } => b'B',
+            ElementValue::Char { .. } => b'C',
+            ElementValue::Double { .. } => b'D',
+            ElementValue::Float { .. } => b'F',
+            ElementValue::Int { .. } => b'I',
+            ElementValue::Long { .. } => b'J',
+            ElementValue::Short { .. } => b'S',
+            ElementValue::Boolean { .. } => b'Z',
+            ElementValue::String { .. } => b's',
+            ElementValue::Enum { .. } => b'e',
+            ElementValue::Class { .. } => b'c',
+            ElementValue::Annotation(_) => b'@',
+            ElementValue::Array(_) => b'[',
+        }
+    }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.20
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAnnotation {
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    pub target_path: Vec<TypePathEntry>,
+    pub type_index: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetInfo {
+    TypeParameter {
+        type_parameter_index: u8,
+    },
+    Supertype {
+        supertype_index: u16,
+    },
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    Empty,
+    FormalParameter {
+        formal_parameter_index: u8,
+    },
+    Throws {
+        throws_type_index: u16,
+    },
+    Localvar(Vec<LocalvarTargetEntry>),
+    Catch {
+        exception_table_index: u16,
+    },
+    Offset {
+        offset: u16,
+    },
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalvarTargetEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypePathEntry {
+    pub type_path_kind: u8,
+    pub type_argument_index: u8,
+}
+impl TypePathEntry {
+    pub fn kind(&self) -> Option<TypePathKind> {
+        match self.type_path_kind {
+            0 => Some(TypePathKind::Array),
+            1 => Some(TypePathKind::Nested),
+            2 => Some(TypePathKind::WildcardBound),
+            3 => Some(TypePathKind::TypeArgument),
+            _ => None,
+        }
+    }
+}
+
+/// How a step of a type path goes deeper into the annotated type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypePathKind {
+    /// Into the component type of an array type.
+    Array,
+    /// Into a nested type.
+    Nested,
+    /// Onto the bound of a wildcard type argument.
+    WildcardBound,
+    /// Into the type argument at `type_argument_index` of a parameterized type.
+    TypeArgument,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BootstrapMethod {
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodParameter {
+    pub name_index: u16,
+    pub access_flags: MethodParameterFlags,
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.25
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleAttribute {
+    pub module_name_index: u16,
+    pub module_flags: ModuleFlags,
+    pub module_version_index: u16,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Opens>,
+    pub uses_index: Vec<u16>,
+    pub provides: Vec<Provides>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requires {
+    pub requires_index: u16,
+    pub requires_flags: RequiresFlags,
+    pub requires_version_index: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exports {
+    pub exports_index: u16,
+    pub exports_flags: ExportsFlags,
+    pub exports_to_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opens {
+    pub opens_index: u16,
+    pub opens_flags: ExportsFlags,
+    pub opens_to_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provides {
+    pub provides_index: u16,
+    pub provides_with_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordComponent {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Attributes,
+}
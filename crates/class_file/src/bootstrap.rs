@@ -0,0 +1,157 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.23
+//
+// Resolves the entries of the BootstrapMethods attribute that InvokeDynamic and Dynamic constant
+// pool entries refer to, e.g. to find the implementation method of a lambda or the recipe of a
+// string concatenation.
+
+use crate::{
+    constant_pool::{
+        CpIndex, CpInfo, DynamicInfo, InvokeDynamicInfo, MethodHandleInfo, NameAndTypeInfo,
+        ReferenceKind,
+    },
+    ClassFile, ClassFileError, Result,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedBootstrapMethod<'a> {
+    pub method_handle: MethodHandle<'a>,
+    pub arguments: Vec<LoadableConstant<'a>>,
+}
+
+/// A MethodHandle entry with the field or method it refers to resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodHandle<'a> {
+    pub reference_kind: ReferenceKind,
+    pub owner: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// Whether the method is declared by an interface, i.e. it is referred to by an
+    /// InterfaceMethodRef entry.
+    pub is_interface: bool,
+}
+
+/// A constant pool entry that can be loaded by `ldc` or passed as a static argument to a bootstrap
+/// method (§4.4).
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadableConstant<'a> {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(&'a str),
+    String(&'a str),
+    MethodHandle(MethodHandle<'a>),
+    /// The method descriptor of the method type.
+    MethodType(&'a str),
+    Dynamic(DynamicConstant<'a>),
+}
+
+/// A dynamically-computed constant or call site. Its bootstrap method is left unresolved, since
+/// its static arguments may in turn be dynamically-computed constants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicConstant<'a> {
+    pub bootstrap_method_attr_index: u16,
+    pub name: &'a str,
+    /// A field descriptor for a constant, a method descriptor for a call site.
+    pub descriptor: &'a str,
+}
+
+impl ClassFile {
+    /// The entry at `index` of the BootstrapMethods attribute.
+    pub fn bootstrap_method(&self, index: u16) -> Result<ResolvedBootstrapMethod<'_>> {
+        let bootstrap_method = self
+            .attributes
+            .bootstrap_methods()
+            .and_then(|methods| methods.get(index as usize))
+            .ok_or(ClassFileError::InvalidBootstrapMethodIndex(index))?;
+
+        Ok(ResolvedBootstrapMethod {
+            method_handle: self
+                .method_handle(CpIndex::new(bootstrap_method.bootstrap_method_ref))?,
+            arguments: bootstrap_method
+                .bootstrap_arguments
+                .iter()
+                .map(|argument| self.loadable_constant(*argument))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// The call site of the InvokeDynamic entry at `index`, e.g. the operand of an
+    /// `invokedynamic` instruction.
+    pub fn call_site(&self, index: CpIndex<InvokeDynamicInfo>) -> Result<DynamicConstant<'_>> {
+        let InvokeDynamicInfo {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } = self.constant_pool.entry(index)?;
+        self.dynamic_constant(*bootstrap_method_attr_index, *name_and_type_index)
+    }
+
+    pub fn method_handle(&self, index: CpIndex<MethodHandleInfo>) -> Result<MethodHandle<'_>> {
+        let constant_pool = &self.constant_pool;
+        let method_handle = constant_pool.entry(index)?;
+        let reference = constant_pool.entry(method_handle.reference_index)?;
+        let name_and_type = constant_pool.entry(reference.name_and_type_index)?;
+
+        Ok(MethodHandle {
+            reference_kind: method_handle.reference_kind,
+            owner: constant_pool.class_name(reference.class_index)?,
+            name: constant_pool.utf8(name_and_type.name_index)?,
+            descriptor: constant_pool.utf8(name_and_type.descriptor_index)?,
+            is_interface: matches!(
+                constant_pool.get(method_handle.reference_index.get()),
+                Some(CpInfo::InterfaceMethodRef(_))
+            ),
+        })
+    }
+
+    /// The loadable constant at `index`, an error if the entry there is not loadable.
+    pub fn loadable_constant(&self, index: u16) -> Result<LoadableConstant<'_>> {
+        let constant_pool = &self.constant_pool;
+        let cp_info = constant_pool
+            .get(index)
+            .ok_or(ClassFileError::InvalidConstantPoolIndex(index))?;
+
+        Ok(match cp_info {
+            CpInfo::Integer(value) => LoadableConstant::Integer(*value),
+            CpInfo::Float(value) => LoadableConstant::Float(*value),
+            CpInfo::Long(value) => LoadableConstant::Long(*value),
+            CpInfo::Double(value) => LoadableConstant::Double(*value),
+            CpInfo::Class(class) => LoadableConstant::Class(constant_pool.utf8(class.name_index)?),
+            CpInfo::String { string_index } => {
+                LoadableConstant::String(constant_pool.utf8(*string_index)?)
+            }
+            CpInfo::MethodHandle(_) => {
+                LoadableConstant::MethodHandle(self.method_handle(CpIndex::new(index))?)
+            }
+            CpInfo::MethodType(method_type) => {
+                LoadableConstant::MethodType(constant_pool.utf8(method_type.descriptor_index)?)
+            }
+            CpInfo::Dynamic(DynamicInfo {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            }) => LoadableConstant::Dynamic(
+                self.dynamic_constant(*bootstrap_method_attr_index, *name_and_type_index)?,
+            ),
+            _ => {
+                return Err(ClassFileError::UnexpectedConstantPoolEntry(
+                    "loadable constant",
+                    cp_info.clone(),
+                ))
+            }
+        })
+    }
+
+    fn dynamic_constant(
+        &self,
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: CpIndex<NameAndTypeInfo>,
+    ) -> Result<DynamicConstant<'_>> {
+        let name_and_type = self.constant_pool.entry(name_and_type_index)?;
+
+        Ok(DynamicConstant {
+            bootstrap_method_attr_index,
+            name: self.constant_pool.utf8(name_and_type.name_index)?,
+            descriptor: self.constant_pool.utf8(name_and_type.descriptor_index)?,
+        })
+    }
+}
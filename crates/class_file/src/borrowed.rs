@@ -0,0 +1,473 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.1
+//
+// A class file that borrows from the bytes it was parsed from. Parsing only finds where the
+// constant pool entries and attributes are; they are decoded on access, and strings, code arrays
+// and attribute bodies are returned as slices of the input wherever possible. This makes scanning
+// many classes, e.g. out of a memory-mapped jimage, much cheaper than with the owned
+// [`crate::ClassFile`], which [`ClassFile::to_class_file`] converts to.
+
+use std::{borrow::Cow, io::ErrorKind};
+
+use crate::{
+    attributes::{self, AttributeInfo, ExceptionTableEntry},
+    constant_pool::{ClassInfo, CpIndex, CpInfo},
+    instruction::Instructions,
+    mutf8,
+    parser::{check_version, AttributeNames},
+    ClassAccessFlags, ClassFileError, ClassFileVersion, FieldAccessFlags, MethodAccessFlags,
+    Parser, Result,
+};
+
+#[derive(Debug, Clone)]
+pub struct ClassFile<'a> {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: ConstantPool<'a>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<FieldInfo<'a>>,
+    pub methods: Vec<MethodInfo<'a>>,
+    pub attributes: Attributes<'a>,
+}
+impl<'a> ClassFile<'a> {
+    /// Parses a class file of a version supported by default, see [`Parser::max_version`].
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut r = Reader { bytes, pos: 0 };
+
+        match r.read_u32()? {
+            0xCAFEBABE => {}
+            magic_identifier => {
+                return Err(ClassFileError::InvalidMagicIdentifier(magic_identifier))
+            }
+        }
+        let minor_version = r.read_u16()?;
+        let major_version = r.read_u16()?;
+        check_version(major_version, minor_version, Some(ClassFileVersion::LATEST))?;
+
+        let constant_pool = ConstantPool::parse(&mut r)?;
+        let access_flags = ClassAccessFlags::from_bits_retain(r.read_u16()?);
+        let this_class = r.read_u16()?;
+        let super_class = r.read_u16()?;
+        let interfaces_count = r.read_u16()?;
+        let interfaces = (0..interfaces_count)
+            .map(|_| r.read_u16())
+            .collect::<Result<Vec<_>>>()?;
+
+        let fields_count = r.read_u16()?;
+        let fields = (0..fields_count)
+            .map(|_| {
+                Ok(FieldInfo {
+                    access_flags: FieldAccessFlags::from_bits_retain(r.read_u16()?),
+                    name_index: r.read_u16()?,
+                    descriptor_index: r.read_u16()?,
+                    attributes: Attributes::parse(&mut r)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let methods_count = r.read_u16()?;
+        let methods = (0..methods_count)
+            .map(|_| {
+                Ok(MethodInfo {
+                    access_flags: MethodAccessFlags::from_bits_retain(r.read_u16()?),
+                    name_index: r.read_u16()?,
+                    descriptor_index: r.read_u16()?,
+                    attributes: Attributes::parse(&mut r)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let attributes = Attributes::parse(&mut r)?;
+
+        Ok(Self {
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    pub fn class_name(&self) -> Result<Cow<'a, str>> {
+        self.constant_pool.class_name(self.this_class)
+    }
+
+    /// The name of the direct superclass, or `None` for `java/lang/Object`.
+    pub fn super_class(&self) -> Result<Option<Cow<'a, str>>> {
+        if self.super_class == 0 {
+            return Ok(None);
+        }
+
+        self.constant_pool.class_name(self.super_class).map(Some)
+    }
+
+    pub fn field_name(&self, field: &FieldInfo) -> Result<Cow<'a, str>> {
+        self.constant_pool.utf8(field.name_index)
+    }
+
+    pub fn field_descriptor(&self, field: &FieldInfo) -> Result<Cow<'a, str>> {
+        self.constant_pool.utf8(field.descriptor_index)
+    }
+
+    pub fn method_name(&self, method: &MethodInfo) -> Result<Cow<'a, str>> {
+        self.constant_pool.utf8(method.name_index)
+    }
+
+    pub fn method_descriptor(&self, method: &MethodInfo) -> Result<Cow<'a, str>> {
+        self.constant_pool.utf8(method.descriptor_index)
+    }
+
+    /// Decodes everything into an owned class file, as [`crate::ClassFile::parse`] would.
+    pub fn to_class_file(&self) -> Result<crate::ClassFile> {
+        let constant_pool = self.constant_pool.to_constant_pool()?;
+        let to_attributes = |attributes: &Attributes| attributes.to_attributes(&self.constant_pool);
+
+        Ok(crate::ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            access_flags: self.access_flags,
+            this_class: CpIndex::new(self.this_class),
+            super_class: CpIndex::new(self.super_class),
+            interfaces: self.interfaces.iter().copied().map(CpIndex::new).collect(),
+            fields: self
+                .fields
+                .iter()
+                .map(|field| {
+                    Ok(crate::FieldInfo {
+                        access_flags: field.access_flags,
+                        name_index: CpIndex::new(field.name_index),
+                        descriptor_index: CpIndex::new(field.descriptor_index),
+                        attributes: to_attributes(&field.attributes)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            methods: self
+                .methods
+                .iter()
+                .map(|method| {
+                    Ok(crate::MethodInfo {
+                        access_flags: method.access_flags,
+                        name_index: CpIndex::new(method.name_index),
+                        descriptor_index: CpIndex::new(method.descriptor_index),
+                        attributes: to_attributes(&method.attributes)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            attributes: to_attributes(&self.attributes)?,
+            constant_pool,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldInfo<'a> {
+    pub access_flags: FieldAccessFlags,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Attributes<'a>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MethodInfo<'a> {
+    pub access_flags: MethodAccessFlags,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Attributes<'a>,
+}
+impl<'a> MethodInfo<'a> {
+    /// The Code attribute of the method, `None` for abstract and native methods.
+    pub fn code(&self, constant_pool: &ConstantPool<'a>) -> Result<Option<CodeAttribute<'a>>> {
+        self.attributes
+            .find_by_name("Code", constant_pool)?
+            .map(|attribute| CodeAttribute::parse(attribute.info))
+            .transpose()
+    }
+}
+
+/// The constant pool of a borrowed class file, which decodes each entry when it is accessed.
+#[derive(Debug, Clone)]
+pub struct ConstantPool<'a> {
+    /// The bytes of the class file.
+    bytes: &'a [u8],
+    /// The offset of each entry into `bytes`, or `None` for the slot after a Long or Double.
+    offsets: Vec<Option<u32>>,
+}
+impl<'a> ConstantPool<'a> {
+    fn parse(r: &mut Reader<'a>) -> Result<Self> {
+        let constant_pool_count = r.read_u16()?;
+
+        // The count is one more than the number of slots, and a Long or Double takes up two.
+        let count = (constant_pool_count as usize).checked_sub(1).ok_or(
+            ClassFileError::InvalidConstantPoolCount(constant_pool_count),
+        )?;
+        let mut offsets = Vec::with_capacity(count);
+        while offsets.len() < count {
+            offsets.push(Some(r.pos as u32));
+
+            let tag = r.read_u8()?;
+            let length = match tag {
+                1 => r.read_u16()? as usize,
+                3 | 4 => 4,
+                5 | 6 => {
+                    offsets.push(None);
+                    8
+                }
+                7 | 8 | 16 | 19 | 20 => 2,
+                15 => 3,
+                9 | 10 | 11 | 12 | 17 | 18 => 4,
+                _ => return Err(ClassFileError::InvalidCpInfoTag(tag)),
+            };
+            r.read_bytes(length)?;
+        }
+        if offsets.len() > count {
+            return Err(ClassFileError::InvalidConstantPoolCount(
+                constant_pool_count,
+            ));
+        }
+
+        Ok(Self {
+            bytes: r.bytes,
+            offsets,
+        })
+    }
+
+    /// The number of slots in the constant pool, one less than the constant_pool_count.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// The offset of the entry at `index`, `None` if the slot is unusable.
+    fn offset(&self, index: u16) -> Result<Option<usize>> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.offsets.get(i as usize))
+            .map(|offset| offset.map(|offset| offset as usize))
+            .ok_or(ClassFileError::InvalidConstantPoolIndex(index))
+    }
+
+    /// Decodes the entry at `index`.
+    pub fn get(&self, index: u16) -> Result<CpInfo> {
+        self.decode(index, false)
+    }
+
+    fn decode(&self, index: u16, keep_raw_utf8: bool) -> Result<CpInfo> {
+        match self.offset(index)? {
+            Some(offset) => Ok(Parser::parse_cp_info_from(&self.bytes[offset..], keep_raw_utf8)?.0),
+            None => Ok(CpInfo::Unusable),
+        }
+    }
+
+    /// The string of the Utf8 entry at `index`, borrowed from the class file unless it has to be
+    /// decoded. Unpaired surrogates are replaced with U+FFFD.
+    pub fn utf8(&self, index: u16) -> Result<Cow<'a, str>> {
+        let offset = match self.offset(index)? {
+            Some(offset) if self.bytes[offset] == 1 => offset,
+            _ => {
+                return Err(ClassFileError::UnexpectedConstantPoolEntry(
+                    "Utf8",
+                    self.get(index)?,
+                ))
+            }
+        };
+
+        let mut r = Reader {
+            bytes: self.bytes,
+            pos: offset + 1,
+        };
+        let length = r.read_u16()? as usize;
+        let bytes = r.read_bytes(length)?;
+        match mutf8::decode(bytes) {
+            Err(ClassFileError::UnpairedSurrogate) => mutf8::decode_lossy(bytes),
+            result => result,
+        }
+    }
+
+    /// The name of the Class entry at `index`.
+    pub fn class_name(&self, index: u16) -> Result<Cow<'a, str>> {
+        match self.get(index)? {
+            CpInfo::Class(ClassInfo { name_index }) => self.utf8(name_index.get()),
+            cp_info => Err(ClassFileError::UnexpectedConstantPoolEntry(
+                "Class", cp_info,
+            )),
+        }
+    }
+
+    /// Decodes every entry into an owned constant pool.
+    pub fn to_constant_pool(&self) -> Result<crate::ConstantPool> {
+        self.decode_all(false)
+    }
+
+    /// Decodes every entry into an owned constant pool, keeping the Utf8 entries with unpaired
+    /// surrogates as [`CpInfo::RawUtf8`] so that the pool is written back unchanged.
+    pub(crate) fn to_raw_constant_pool(&self) -> Result<crate::ConstantPool> {
+        self.decode_all(true)
+    }
+
+    fn decode_all(&self, keep_raw_utf8: bool) -> Result<crate::ConstantPool> {
+        (1..=self.len() as u16)
+            .map(|index| self.decode(index, keep_raw_utf8))
+            .collect::<Result<_>>()
+            .map(crate::ConstantPool::new)
+    }
+}
+impl AttributeNames for ConstantPool<'_> {
+    fn attribute_name(&self, index: u16) -> Result<Cow<'_, str>> {
+        self.utf8(index)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Attributes<'a>(pub Vec<Attribute<'a>>);
+impl<'a> Attributes<'a> {
+    fn parse(r: &mut Reader<'a>) -> Result<Self> {
+        let attributes_count = r.read_u16()?;
+        (0..attributes_count)
+            .map(|_| {
+                let attribute_name_index = r.read_u16()?;
+                let attribute_length = r.read_u32()?;
+                Ok(Attribute {
+                    attribute_name_index,
+                    info: r.read_bytes(attribute_length as usize)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Attributes)
+    }
+
+    pub fn find_by_name(
+        &self,
+        name: &str,
+        constant_pool: &ConstantPool<'a>,
+    ) -> Result<Option<&Attribute<'a>>> {
+        for attribute in &self.0 {
+            if attribute.name(constant_pool)? == name {
+                return Ok(Some(attribute));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Attribute<'a>> {
+        self.0.iter()
+    }
+
+    fn to_attributes(&self, constant_pool: &ConstantPool<'a>) -> Result<attributes::Attributes> {
+        self.iter()
+            .map(|attribute| {
+                Ok(attributes::Attribute {
+                    attribute_name_index: attribute.attribute_name_index,
+                    info: attribute.decode(constant_pool)?,
+                })
+            })
+            .collect::<Result<_>>()
+            .map(attributes::Attributes)
+    }
+}
+
+/// An attribute whose body has not been decoded.
+#[derive(Debug, Clone, Copy)]
+pub struct Attribute<'a> {
+    pub attribute_name_index: u16,
+    pub info: &'a [u8],
+}
+impl<'a> Attribute<'a> {
+    pub fn name(&self, constant_pool: &ConstantPool<'a>) -> Result<Cow<'a, str>> {
+        constant_pool.utf8(self.attribute_name_index)
+    }
+
+    /// Decodes the body of the attribute, as [`AttributeInfo::Unknown`] if the attribute is not
+    /// one of the attributes defined by the JVMS.
+    pub fn decode(&self, constant_pool: &ConstantPool<'a>) -> Result<AttributeInfo> {
+        let name = self.name(constant_pool)?;
+        let decoded = Parser::parse_attribute_body(&name, self.info, constant_pool, false)?;
+        Ok(decoded.unwrap_or_else(|| AttributeInfo::Unknown(self.info.to_vec())))
+    }
+}
+
+/// A Code attribute that borrows its code array and attributes.
+#[derive(Debug, Clone)]
+pub struct CodeAttribute<'a> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: &'a [u8],
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub attributes: Attributes<'a>,
+}
+impl<'a> CodeAttribute<'a> {
+    pub(crate) fn parse(info: &'a [u8]) -> Result<Self> {
+        let mut r = Reader {
+            bytes: info,
+            pos: 0,
+        };
+        let max_stack = r.read_u16()?;
+        let max_locals = r.read_u16()?;
+        let code_length = r.read_u32()?;
+        let code = r.read_bytes(code_length as usize)?;
+        let exception_table_length = r.read_u16()?;
+        let exception_table = (0..exception_table_length)
+            .map(|_| {
+                Ok(ExceptionTableEntry {
+                    start_pc: r.read_u16()?,
+                    end_pc: r.read_u16()?,
+                    handler_pc: r.read_u16()?,
+                    catch_type: r.read_u16()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let attributes = Attributes::parse(&mut r)?;
+        if r.pos != info.len() {
+            return Err(ClassFileError::InvalidAttributeLength("Code".to_owned()));
+        }
+
+        Ok(Self {
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
+        })
+    }
+
+    pub fn instructions(&self) -> Instructions<'a> {
+        Instructions::new(self.code)
+    }
+}
+
+/// Reads big-endian values from a slice, failing like [`std::io::Read::read_exact`] at its end.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+}
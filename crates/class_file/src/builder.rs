@@ -0,0 +1,365 @@
+// Generates class files without a compiler: a `ClassBuilder` declares the members of a class, a
+// `CodeBuilder` assembles the code of a method from symbolic instructions and labels, and both add
+// the constant pool entries they refer to through a `ConstantPoolBuilder`.
+
+mod code;
+
+pub use self::code::{CodeBuilder, Label};
+use crate::{
+    attributes::{Attribute, AttributeInfo, Attributes},
+    constant_pool::{
+        ClassInfo, CpIndex, CpInfo, InvokeDynamicInfo, MethodHandleInfo, MethodTypeInfo,
+        NameAndTypeInfo, RefInfo, ReferenceKind,
+    },
+    ClassAccessFlags, ClassFile, ClassFileVersion, ConstantPool, FieldAccessFlags, FieldInfo,
+    FieldType, MethodAccessFlags, MethodDescriptor, MethodInfo, Result,
+};
+
+/// Builds a constant pool, adding each entry only once. The entries are looked up with
+/// [`ConstantPool::find_or_insert`], and there are methods for the entries code refers to.
+#[derive(Debug, Default)]
+pub struct ConstantPoolBuilder {
+    constant_pool: ConstantPool,
+}
+impl ConstantPoolBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continues an existing constant pool, reusing its entries.
+    pub fn from_constant_pool(constant_pool: ConstantPool) -> Self {
+        Self { constant_pool }
+    }
+
+    pub fn constant_pool(&self) -> &ConstantPool {
+        &self.constant_pool
+    }
+
+    pub fn build(self) -> ConstantPool {
+        self.constant_pool
+    }
+
+    /// The index of the entry equal to `cp_info`, which is added if there is none.
+    pub fn insert(&mut self, cp_info: CpInfo) -> Result<CpIndex<CpInfo>> {
+        self.find_or_insert(cp_info)
+    }
+
+    pub fn utf8(&mut self, s: &str) -> Result<CpIndex<str>> {
+        self.constant_pool.find_or_insert_utf8(s)
+    }
+
+    /// A Class entry for the class, interface or array type named in internal form.
+    pub fn class(&mut self, name: &str) -> Result<CpIndex<ClassInfo>> {
+        self.constant_pool.find_or_insert_class(name)
+    }
+
+    /// A String entry, whose structure is the index of the Utf8 entry of its value.
+    pub fn string(&mut self, s: &str) -> Result<CpIndex<CpIndex<str>>> {
+        let string_index = self.utf8(s)?;
+        self.find_or_insert(CpInfo::String { string_index })
+    }
+
+    pub fn integer(&mut self, value: i32) -> Result<CpIndex<i32>> {
+        self.find_or_insert(CpInfo::Integer(value))
+    }
+
+    pub fn float(&mut self, value: f32) -> Result<CpIndex<f32>> {
+        self.find_or_insert(CpInfo::Float(value))
+    }
+
+    pub fn long(&mut self, value: i64) -> Result<CpIndex<i64>> {
+        self.find_or_insert(CpInfo::Long(value))
+    }
+
+    pub fn double(&mut self, value: f64) -> Result<CpIndex<f64>> {
+        self.find_or_insert(CpInfo::Double(value))
+    }
+
+    pub fn name_and_type(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<NameAndTypeInfo>> {
+        self.constant_pool
+            .find_or_insert_name_and_type(name, descriptor)
+    }
+
+    pub fn field_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        self.constant_pool
+            .find_or_insert_field_ref(class, name, descriptor)
+    }
+
+    pub fn method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        self.constant_pool
+            .find_or_insert_method_ref(class, name, descriptor)
+    }
+
+    pub fn interface_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        self.constant_pool
+            .find_or_insert_interface_method_ref(class, name, descriptor)
+    }
+
+    pub fn method_type(&mut self, descriptor: &str) -> Result<CpIndex<MethodTypeInfo>> {
+        let descriptor_index = self.utf8(descriptor)?;
+        self.find_or_insert(CpInfo::MethodType(MethodTypeInfo { descriptor_index }))
+    }
+
+    /// A MethodHandle entry of the `reference_kind` referring to the field or method
+    /// entry at `reference_index`.
+    pub fn method_handle(
+        &mut self,
+        reference_kind: ReferenceKind,
+        reference_index: CpIndex<RefInfo>,
+    ) -> Result<CpIndex<MethodHandleInfo>> {
+        self.find_or_insert(CpInfo::MethodHandle(MethodHandleInfo {
+            reference_kind,
+            reference_index,
+        }))
+    }
+
+    pub fn invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<InvokeDynamicInfo>> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.find_or_insert(CpInfo::InvokeDynamic(InvokeDynamicInfo {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        }))
+    }
+
+    /// [`ConstantPool::find_or_insert`], typed as an index of the entry.
+    fn find_or_insert<T: ?Sized>(&mut self, cp_info: CpInfo) -> Result<CpIndex<T>> {
+        self.constant_pool.find_or_insert(cp_info).map(CpIndex::new)
+    }
+}
+
+/// Builds a class file.
+pub struct ClassBuilder {
+    version: ClassFileVersion,
+    access_flags: ClassAccessFlags,
+    this_class: CpIndex<ClassInfo>,
+    super_class: CpIndex<ClassInfo>,
+    interfaces: Vec<CpIndex<ClassInfo>>,
+    fields: Vec<FieldInfo>,
+    methods: Vec<MethodInfo>,
+    attributes: Vec<Attribute>,
+    constant_pool: ConstantPoolBuilder,
+}
+impl ClassBuilder {
+    /// A class named in internal form, extending `java/lang/Object`, of the latest class file
+    /// version.
+    pub fn new(name: &str, access_flags: ClassAccessFlags) -> Result<Self> {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let this_class = constant_pool.class(name)?;
+        let super_class = constant_pool.class("java/lang/Object")?;
+
+        Ok(Self {
+            version: ClassFileVersion::LATEST,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            constant_pool,
+        })
+    }
+
+    pub fn version(&mut self, version: ClassFileVersion) -> &mut Self {
+        self.version = version;
+        self
+    }
+
+    pub fn super_class(&mut self, name: &str) -> Result<&mut Self> {
+        self.super_class = self.constant_pool.class(name)?;
+        Ok(self)
+    }
+
+    pub fn interface(&mut self, name: &str) -> Result<&mut Self> {
+        let interface = self.constant_pool.class(name)?;
+        self.interfaces.push(interface);
+        Ok(self)
+    }
+
+    /// The constant pool, e.g. to refer to entries from attributes.
+    pub fn constant_pool(&mut self) -> &mut ConstantPoolBuilder {
+        &mut self.constant_pool
+    }
+
+    pub fn field(
+        &mut self,
+        access_flags: FieldAccessFlags,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self> {
+        FieldType::parse(descriptor)?;
+        self.fields.push(FieldInfo {
+            access_flags,
+            name_index: self.constant_pool.utf8(name)?,
+            descriptor_index: self.constant_pool.utf8(descriptor)?,
+            attributes: Attributes::default(),
+        });
+        Ok(self)
+    }
+
+    /// Adds a method with the code assembled by `f`.
+    pub fn method(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: &str,
+        descriptor: &str,
+        f: impl FnOnce(&mut CodeBuilder) -> Result<()>,
+    ) -> Result<&mut Self> {
+        let mut method = self.method_info(access_flags, name, descriptor)?;
+
+        let mut code = CodeBuilder::new(&mut self.constant_pool);
+        f(&mut code)?;
+        let code = code.build()?;
+
+        method.attributes.0.push(Attribute {
+            attribute_name_index: self.constant_pool.utf8("Code")?.get(),
+            info: AttributeInfo::Code(code),
+        });
+        self.methods.push(method);
+        Ok(self)
+    }
+
+    /// Adds a method without code, e.g. an abstract or native method.
+    pub fn method_without_code(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self> {
+        let method = self.method_info(access_flags, name, descriptor)?;
+        self.methods.push(method);
+        Ok(self)
+    }
+
+    fn method_info(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<MethodInfo> {
+        MethodDescriptor::parse(descriptor)?;
+        Ok(MethodInfo {
+            access_flags,
+            name_index: self.constant_pool.utf8(name)?,
+            descriptor_index: self.constant_pool.utf8(descriptor)?,
+            attributes: Attributes::default(),
+        })
+    }
+
+    /// Adds an attribute of the class, whose constant pool indices refer to
+    /// [`ClassBuilder::constant_pool`].
+    pub fn attribute(&mut self, name: &str, info: AttributeInfo) -> Result<&mut Self> {
+        self.attributes.push(Attribute {
+            attribute_name_index: self.constant_pool.utf8(name)?.get(),
+            info,
+        });
+        Ok(self)
+    }
+
+    /// The class file. The `max_stack` and `max_locals` of the code of its methods are those set
+    /// with [`CodeBuilder::maxs`], and it has no stack map frames: [`ClassFile::update_frames`]
+    /// computes both.
+    pub fn build(self) -> ClassFile {
+        ClassFile {
+            minor_version: self.version.minor,
+            major_version: self.version.major,
+            constant_pool: self.constant_pool.build(),
+            access_flags: self.access_flags,
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces: self.interfaces,
+            fields: self.fields,
+            methods: self.methods,
+            attributes: Attributes(self.attributes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod constant_pool_builder_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_add_entries_once() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+
+        let method_ref = constant_pool
+            .method_ref("java/io/PrintStream", "println", "(I)V")
+            .unwrap();
+        let class = constant_pool.class("java/io/PrintStream").unwrap();
+        let nan = constant_pool.float(f32::NAN).unwrap();
+
+        assert_eq!(
+            method_ref,
+            constant_pool
+                .method_ref("java/io/PrintStream", "println", "(I)V")
+                .unwrap()
+        );
+        assert_eq!(class, constant_pool.class("java/io/PrintStream").unwrap());
+        assert_eq!(nan, constant_pool.float(f32::NAN).unwrap());
+        assert_ne!(nan, constant_pool.float(-f32::NAN).unwrap());
+    }
+
+    #[test]
+    fn it_should_give_longs_and_doubles_two_slots() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+
+        assert_eq!(1, constant_pool.long(1).unwrap().get());
+        assert_eq!(3, constant_pool.double(1.0).unwrap().get());
+        assert_eq!(5, constant_pool.integer(1).unwrap().get());
+        assert_eq!(CpInfo::Unusable, constant_pool.constant_pool()[4]);
+    }
+
+    #[test]
+    fn it_should_type_the_indices_of_constants() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+
+        let string = constant_pool.string("Hello").unwrap();
+        let long = constant_pool.long(1 << 40).unwrap();
+        let float = constant_pool.float(0.5).unwrap();
+
+        let constant_pool = constant_pool.build();
+        let string_index = *constant_pool.entry(string).unwrap();
+        assert_eq!("Hello", constant_pool.utf8(string_index).unwrap());
+        assert_eq!(1 << 40, *constant_pool.entry(long).unwrap());
+        assert_eq!(0.5, *constant_pool.entry(float).unwrap());
+        assert!(constant_pool
+            .entry(CpIndex::<i32>::new(long.get()))
+            .is_err());
+    }
+
+    #[test]
+    fn it_should_reuse_the_entries_of_an_existing_constant_pool() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let class = constant_pool.class("java/lang/Object").unwrap();
+
+        let mut constant_pool = ConstantPoolBuilder::from_constant_pool(constant_pool.build());
+
+        assert_eq!(class, constant_pool.class("java/lang/Object").unwrap());
+        assert_eq!(3, constant_pool.utf8("java/lang/String").unwrap().get());
+    }
+}
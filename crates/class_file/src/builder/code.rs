@@ -0,0 +1,735 @@
+use super::ConstantPoolBuilder;
+use crate::{
+    attributes::{Attributes, CodeAttribute, ExceptionTableEntry},
+    constant_pool::CpIndex,
+    instruction::WideInstruction,
+    ClassFileError, Instruction, MethodDescriptor, Result,
+};
+
+const GOTO: u8 = 0xa7;
+const GOTO_W: u8 = 0xc8;
+const IFNULL: u8 = 0xc6;
+const IFNONNULL: u8 = 0xc7;
+
+/// A position in the code, which jumps and exception handlers can refer to before it is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(usize);
+
+/// Assembles the code of a method. Instructions referring to the constant pool are given
+/// symbolically, and jumps refer to [`Label`]s, which are resolved when the code is built.
+///
+/// A jump is encoded with a 16 bit offset if its target is close enough, and otherwise with
+/// `goto_w`. A conditional jump that is too long jumps with the opposite condition over a
+/// `goto_w` to the target.
+pub struct CodeBuilder<'a> {
+    constant_pool: &'a mut ConstantPoolBuilder,
+    items: Vec<Item>,
+    /// The index in `items` at which each label is placed.
+    labels: Vec<Option<usize>>,
+    handlers: Vec<Handler>,
+    max_stack: u16,
+    max_locals: u16,
+}
+
+enum Item {
+    Instruction(Instruction),
+    Jump {
+        opcode: u8,
+        target: Label,
+    },
+    Tableswitch {
+        default: Label,
+        low: i32,
+        targets: Vec<Label>,
+    },
+    Lookupswitch {
+        default: Label,
+        pairs: Vec<(i32, Label)>,
+    },
+}
+
+struct Handler {
+    start: Label,
+    end: Label,
+    handler: Label,
+    catch_type: u16,
+}
+
+macro_rules! jumps {
+    ($($name:ident = $opcode:expr),* $(,)?) => {
+        $(
+            pub fn $name(&mut self, target: Label) -> &mut Self {
+                self.jump($opcode, target)
+            }
+        )*
+    };
+}
+
+macro_rules! locals {
+    ($($(#[$attr:meta])* $name:ident = $opcode:expr, $compact_opcode:expr;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&mut self, index: u16) -> &mut Self {
+                self.local($opcode, $compact_opcode, index)
+            }
+        )*
+    };
+}
+
+impl<'a> CodeBuilder<'a> {
+    pub(crate) fn new(constant_pool: &'a mut ConstantPoolBuilder) -> Self {
+        Self {
+            constant_pool,
+            items: vec![],
+            labels: vec![],
+            handlers: vec![],
+            max_stack: 0,
+            max_locals: 0,
+        }
+    }
+
+    /// The constant pool of the class, e.g. for instructions given with [`CodeBuilder::instruction`].
+    pub fn constant_pool(&mut self) -> &mut ConstantPoolBuilder {
+        self.constant_pool
+    }
+
+    /// Appends an instruction as is. The offsets of jumps and switches given this way are not
+    /// adjusted, [`Label`]s should be used instead.
+    pub fn instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.items.push(Item::Instruction(instruction));
+        self
+    }
+
+    pub fn maxs(&mut self, max_stack: u16, max_locals: u16) -> &mut Self {
+        self.max_stack = max_stack;
+        self.max_locals = max_locals;
+        self
+    }
+
+    /// Pushes an int constant with the shortest instruction that can.
+    pub fn iconst(&mut self, value: i32) -> Result<&mut Self> {
+        let instruction = match value {
+            -1 => Instruction::IconstM1,
+            0 => Instruction::Iconst0,
+            1 => Instruction::Iconst1,
+            2 => Instruction::Iconst2,
+            3 => Instruction::Iconst3,
+            4 => Instruction::Iconst4,
+            5 => Instruction::Iconst5,
+            _ if i8::try_from(value).is_ok() => Instruction::Bipush(value as i8),
+            _ if i16::try_from(value).is_ok() => Instruction::Sipush(value as i16),
+            _ => {
+                let index = self.constant_pool.integer(value)?;
+                return Ok(self.ldc(index));
+            }
+        };
+        Ok(self.instruction(instruction))
+    }
+
+    pub fn lconst(&mut self, value: i64) -> Result<&mut Self> {
+        let instruction = match value {
+            0 => Instruction::Lconst0,
+            1 => Instruction::Lconst1,
+            _ => Instruction::Ldc2W(self.constant_pool.long(value)?.get()),
+        };
+        Ok(self.instruction(instruction))
+    }
+
+    pub fn fconst(&mut self, value: f32) -> Result<&mut Self> {
+        // Compare the bits, since -0.0 == 0.0.
+        let instruction = match value.to_bits() {
+            0x0000_0000 => Instruction::Fconst0,
+            0x3F80_0000 => Instruction::Fconst1,
+            0x4000_0000 => Instruction::Fconst2,
+            _ => {
+                let index = self.constant_pool.float(value)?;
+                return Ok(self.ldc(index));
+            }
+        };
+        Ok(self.instruction(instruction))
+    }
+
+    pub fn dconst(&mut self, value: f64) -> Result<&mut Self> {
+        let instruction = match value.to_bits() {
+            0x0000_0000_0000_0000 => Instruction::Dconst0,
+            0x3FF0_0000_0000_0000 => Instruction::Dconst1,
+            _ => Instruction::Ldc2W(self.constant_pool.double(value)?.get()),
+        };
+        Ok(self.instruction(instruction))
+    }
+
+    /// Pushes a `java.lang.String` constant.
+    pub fn ldc_string(&mut self, s: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.string(s)?;
+        Ok(self.ldc(index))
+    }
+
+    /// Pushes the `java.lang.Class` of the class, interface or array type named in internal form.
+    pub fn ldc_class(&mut self, name: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.class(name)?;
+        Ok(self.ldc(index))
+    }
+
+    /// Pushes the single slot constant at `index` with `ldc` or `ldc_w`.
+    fn ldc<T: ?Sized>(&mut self, index: CpIndex<T>) -> &mut Self {
+        let index = index.get();
+        self.instruction(match u8::try_from(index) {
+            Ok(index) => Instruction::Ldc(index),
+            Err(_) => Instruction::LdcW(index),
+        })
+    }
+
+    locals! {
+        iload = 0x15, 0x1a;
+        lload = 0x16, 0x1e;
+        fload = 0x17, 0x22;
+        dload = 0x18, 0x26;
+        aload = 0x19, 0x2a;
+        istore = 0x36, 0x3b;
+        lstore = 0x37, 0x3f;
+        fstore = 0x38, 0x43;
+        dstore = 0x39, 0x47;
+        astore = 0x3a, 0x4b;
+    }
+
+    /// Loads or stores the local variable at `index` with the shortest form of the instruction:
+    /// the one at `compact_opcode + index` for the first four, the `wide` one beyond 255.
+    fn local(&mut self, opcode: u8, compact_opcode: u8, index: u16) -> &mut Self {
+        let bytes = match index {
+            0..=3 => vec![compact_opcode + index as u8],
+            4..=255 => vec![opcode, index as u8],
+            _ => {
+                let [high, low] = index.to_be_bytes();
+                vec![0xc4, opcode, high, low]
+            }
+        };
+        let (instruction, _) =
+            Instruction::decode(&bytes, 0).expect("local variable instructions are valid");
+        self.instruction(instruction)
+    }
+
+    pub fn iinc(&mut self, index: u16, value: i16) -> &mut Self {
+        self.instruction(match (u8::try_from(index), i8::try_from(value)) {
+            (Ok(index), Ok(value)) => Instruction::Iinc { index, value },
+            _ => Instruction::Wide(WideInstruction::Iinc { index, value }),
+        })
+    }
+
+    pub fn getstatic(&mut self, class: &str, name: &str, descriptor: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.field_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Getstatic(index.get())))
+    }
+
+    pub fn putstatic(&mut self, class: &str, name: &str, descriptor: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.field_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Putstatic(index.get())))
+    }
+
+    pub fn getfield(&mut self, class: &str, name: &str, descriptor: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.field_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Getfield(index.get())))
+    }
+
+    pub fn putfield(&mut self, class: &str, name: &str, descriptor: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.field_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Putfield(index.get())))
+    }
+
+    pub fn invokevirtual(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self> {
+        let index = self.constant_pool.method_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Invokevirtual(index.get())))
+    }
+
+    pub fn invokespecial(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self> {
+        let index = self.constant_pool.method_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Invokespecial(index.get())))
+    }
+
+    pub fn invokestatic(&mut self, class: &str, name: &str, descriptor: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.method_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Invokestatic(index.get())))
+    }
+
+    pub fn invokeinterface(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self> {
+        // The count is the number of argument slots, including the receiver, which the limit of
+        // 255 parameter slots also includes.
+        let count = u8::try_from(MethodDescriptor::parse(descriptor)?.parameter_slots() + 1)
+            .map_err(|_| ClassFileError::InvalidDescriptor(descriptor.to_owned()))?;
+        let index = self
+            .constant_pool
+            .interface_method_ref(class, name, descriptor)?;
+        Ok(self.instruction(Instruction::Invokeinterface {
+            index: index.get(),
+            count,
+        }))
+    }
+
+    /// Creates an object of the class, named `new` in the instruction set.
+    pub fn new_object(&mut self, class: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.class(class)?;
+        Ok(self.instruction(Instruction::New(index.get())))
+    }
+
+    /// Creates an array of the class, interface or array type named in internal form.
+    pub fn anewarray(&mut self, component_type: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.class(component_type)?;
+        Ok(self.instruction(Instruction::Anewarray(index.get())))
+    }
+
+    /// Creates a multidimensional array of the array type given by its descriptor.
+    pub fn multianewarray(&mut self, descriptor: &str, dimensions: u8) -> Result<&mut Self> {
+        let index = self.constant_pool.class(descriptor)?;
+        Ok(self.instruction(Instruction::Multianewarray {
+            index: index.get(),
+            dimensions,
+        }))
+    }
+
+    pub fn checkcast(&mut self, class: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.class(class)?;
+        Ok(self.instruction(Instruction::Checkcast(index.get())))
+    }
+
+    pub fn instanceof(&mut self, class: &str) -> Result<&mut Self> {
+        let index = self.constant_pool.class(class)?;
+        Ok(self.instruction(Instruction::Instanceof(index.get())))
+    }
+
+    /// A label to be placed with [`CodeBuilder::place_label`].
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Places the label at the next instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the label is already placed or comes from another builder.
+    pub fn place_label(&mut self, label: Label) -> &mut Self {
+        let position = &mut self.labels[label.0];
+        assert!(position.is_none(), "label {} is already placed", label.0);
+        *position = Some(self.items.len());
+        self
+    }
+
+    jumps! {
+        ifeq = 0x99,
+        ifne = 0x9a,
+        iflt = 0x9b,
+        ifge = 0x9c,
+        ifgt = 0x9d,
+        ifle = 0x9e,
+        if_icmpeq = 0x9f,
+        if_icmpne = 0xa0,
+        if_icmplt = 0xa1,
+        if_icmpge = 0xa2,
+        if_icmpgt = 0xa3,
+        if_icmple = 0xa4,
+        if_acmpeq = 0xa5,
+        if_acmpne = 0xa6,
+        goto = GOTO,
+        ifnull = IFNULL,
+        ifnonnull = IFNONNULL,
+    }
+
+    fn jump(&mut self, opcode: u8, target: Label) -> &mut Self {
+        self.items.push(Item::Jump { opcode, target });
+        self
+    }
+
+    /// Jumps to `targets[key - low]`, or to `default` if the key is out of range. There must be
+    /// at least one target, and the last key must fit in an `i32`.
+    pub fn tableswitch(
+        &mut self,
+        low: i32,
+        default: Label,
+        targets: &[Label],
+    ) -> Result<&mut Self> {
+        let last_key = i32::try_from(targets.len())
+            .ok()
+            .and_then(|n| low.checked_add(n - 1));
+        if targets.is_empty() || last_key.is_none() {
+            return Err(ClassFileError::InvalidTableswitch(low, targets.len()));
+        }
+
+        self.items.push(Item::Tableswitch {
+            default,
+            low,
+            targets: targets.to_vec(),
+        });
+        Ok(self)
+    }
+
+    /// Jumps to the target of the key, or to `default` if there is none.
+    pub fn lookupswitch(&mut self, default: Label, pairs: &[(i32, Label)]) -> &mut Self {
+        let mut pairs = pairs.to_vec();
+        // The pairs must be sorted in increasing numerical order by match.
+        pairs.sort_by_key(|(key, _)| *key);
+        self.items.push(Item::Lookupswitch { default, pairs });
+        self
+    }
+
+    /// Handles the exceptions of the class `catch_type`, or any exception if `None`, thrown from
+    /// `start` up to but not including `end`, at `handler`.
+    pub fn try_catch(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: Option<&str>,
+    ) -> Result<&mut Self> {
+        let catch_type = match catch_type {
+            Some(catch_type) => self.constant_pool.class(catch_type)?.get(),
+            None => 0,
+        };
+        self.handlers.push(Handler {
+            start,
+            end,
+            handler,
+            catch_type,
+        });
+        Ok(self)
+    }
+
+    pub(crate) fn build(self) -> Result<CodeAttribute> {
+        // Jumps only ever get longer, so widening those that are out of range until none are
+        // reaches a fixed point.
+        let mut wide = vec![false; self.items.len()];
+        let pcs = loop {
+            let pcs = self.layout(&wide)?;
+            let mut widened = false;
+            for (i, item) in self.items.iter().enumerate() {
+                if let Item::Jump { target, .. } = item {
+                    let offset = self.pc(*target, &pcs)? as i64 - pcs[i] as i64;
+                    if !wide[i] && i16::try_from(offset).is_err() {
+                        wide[i] = true;
+                        widened = true;
+                    }
+                }
+            }
+            if !widened {
+                break pcs;
+            }
+        };
+
+        // The code_length must be less than 65536.
+        let code_length = pcs[self.items.len()];
+        if code_length > u16::MAX as u32 {
+            return Err(ClassFileError::ValueTooLarge("code"));
+        }
+
+        let mut code = Vec::with_capacity(code_length as usize);
+        for (i, item) in self.items.iter().enumerate() {
+            let pc = pcs[i];
+            let offset =
+                |label: Label| -> Result<i32> { Ok(self.pc(label, &pcs)? as i32 - pc as i32) };
+            match item {
+                Item::Instruction(instruction) => instruction.encode(&mut code)?,
+                Item::Jump { opcode, target } if !wide[i] => {
+                    code.push(*opcode);
+                    code.extend((offset(*target)? as i16).to_be_bytes());
+                }
+                Item::Jump {
+                    opcode: GOTO,
+                    target,
+                } => {
+                    code.push(GOTO_W);
+                    code.extend(offset(*target)?.to_be_bytes());
+                }
+                Item::Jump { opcode, target } => {
+                    // Jump over the goto_w when the condition doesn't hold.
+                    code.push(opposite_condition(*opcode));
+                    code.extend(8i16.to_be_bytes());
+                    code.push(GOTO_W);
+                    code.extend((offset(*target)? - 3).to_be_bytes());
+                }
+                Item::Tableswitch {
+                    default,
+                    low,
+                    targets,
+                } => Instruction::Tableswitch {
+                    default: offset(*default)?,
+                    low: *low,
+                    offsets: targets.iter().map(|t| offset(*t)).collect::<Result<_>>()?,
+                }
+                .encode(&mut code)?,
+                Item::Lookupswitch { default, pairs } => Instruction::Lookupswitch {
+                    default: offset(*default)?,
+                    pairs: pairs
+                        .iter()
+                        .map(|(key, target)| Ok((*key, offset(*target)?)))
+                        .collect::<Result<_>>()?,
+                }
+                .encode(&mut code)?,
+            }
+        }
+        debug_assert_eq!(code_length as usize, code.len());
+
+        let exception_table = self
+            .handlers
+            .iter()
+            .map(|handler| {
+                Ok(ExceptionTableEntry {
+                    start_pc: self.pc(handler.start, &pcs)? as u16,
+                    end_pc: self.pc(handler.end, &pcs)? as u16,
+                    handler_pc: self.pc(handler.handler, &pcs)? as u16,
+                    catch_type: handler.catch_type,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(CodeAttribute {
+            max_stack: self.max_stack,
+            max_locals: self.max_locals,
+            code,
+            exception_table,
+            attributes: Attributes::default(),
+        })
+    }
+
+    /// The pc of each item if the jumps marked `wide` are widened, followed by the code length.
+    fn layout(&self, wide: &[bool]) -> Result<Vec<u32>> {
+        let mut pcs = Vec::with_capacity(self.items.len() + 1);
+        let mut pc = 0;
+        for (i, item) in self.items.iter().enumerate() {
+            pcs.push(pc);
+            // The switches are padded to start their operands at a multiple of 4.
+            let padding = 3 - pc % 4;
+            pc += match item {
+                Item::Instruction(instruction) => {
+                    let mut code = vec![0; (pc % 4) as usize];
+                    instruction.encode(&mut code)?;
+                    code.len() as u32 - pc % 4
+                }
+                Item::Jump { .. } if !wide[i] => 3,
+                Item::Jump { opcode: GOTO, .. } => 5,
+                Item::Jump { .. } => 8,
+                Item::Tableswitch { targets, .. } => 1 + padding + 12 + 4 * targets.len() as u32,
+                Item::Lookupswitch { pairs, .. } => 1 + padding + 8 + 8 * pairs.len() as u32,
+            };
+        }
+        pcs.push(pc);
+        Ok(pcs)
+    }
+
+    fn pc(&self, label: Label, pcs: &[u32]) -> Result<u32> {
+        match self.labels.get(label.0) {
+            Some(Some(position)) => Ok(pcs[*position]),
+            _ => Err(ClassFileError::UnplacedLabel(label.0)),
+        }
+    }
+}
+
+/// The conditional jump taken exactly when the one of `opcode` is not.
+fn opposite_condition(opcode: u8) -> u8 {
+    match opcode {
+        // The conditions from ifeq to if_acmpne come in pairs of opposites.
+        0x99..=0xa6 => ((opcode - 0x99) ^ 1) + 0x99,
+        IFNULL => IFNONNULL,
+        IFNONNULL => IFNULL,
+        _ => unreachable!("0x{:X} is not a conditional jump", opcode),
+    }
+}
+
+#[cfg(test)]
+mod code_builder_tests {
+    use super::*;
+
+    fn instructions(code: &CodeAttribute) -> Vec<(u32, Instruction)> {
+        code.instructions().map(|i| i.unwrap()).collect()
+    }
+
+    #[test]
+    fn it_should_resolve_forward_and_backward_labels() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+        let start = code.new_label();
+        let end = code.new_label();
+
+        code.place_label(start)
+            .iload(0)
+            .ifeq(end)
+            .iinc(0, -1)
+            .goto(start)
+            .place_label(end)
+            .instruction(Instruction::Return);
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload0),
+                (1, Instruction::Ifeq(9)),
+                (
+                    4,
+                    Instruction::Iinc {
+                        index: 0,
+                        value: -1
+                    }
+                ),
+                (7, Instruction::Goto(-7)),
+                (10, Instruction::Return),
+            ],
+            instructions(&code.build().unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_widen_jumps_out_of_range() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+        let start = code.new_label();
+        let end = code.new_label();
+
+        code.place_label(start).iload(0).ifeq(end);
+        for _ in 0..40000 {
+            code.instruction(Instruction::Nop);
+        }
+        code.goto(start)
+            .place_label(end)
+            .instruction(Instruction::Return);
+
+        let instructions = instructions(&code.build().unwrap());
+        assert_eq!(
+            [
+                (0, Instruction::Iload0),
+                (1, Instruction::Ifne(8)),
+                (4, Instruction::GotoW(40010)),
+            ],
+            instructions[..3]
+        );
+        assert_eq!(
+            [
+                (40009, Instruction::GotoW(-40009)),
+                (40014, Instruction::Return)
+            ],
+            instructions[instructions.len() - 2..]
+        );
+    }
+
+    #[test]
+    fn it_should_pad_switches() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+        let (one, other) = (code.new_label(), code.new_label());
+
+        code.iload(0)
+            .lookupswitch(other, &[(1, one)])
+            .place_label(one)
+            .instruction(Instruction::Return)
+            .place_label(other)
+            .instruction(Instruction::Return);
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload0),
+                (
+                    1,
+                    Instruction::Lookupswitch {
+                        default: 20,
+                        pairs: vec![(1, 19)]
+                    }
+                ),
+                (20, Instruction::Return),
+                (21, Instruction::Return),
+            ],
+            instructions(&code.build().unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_reject_tableswitches_without_a_valid_range() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+        let label = code.new_label();
+
+        assert!(matches!(
+            code.tableswitch(0, label, &[]),
+            Err(ClassFileError::InvalidTableswitch(0, 0))
+        ));
+        assert!(matches!(
+            code.tableswitch(i32::MAX, label, &[label, label]),
+            Err(ClassFileError::InvalidTableswitch(i32::MAX, 2))
+        ));
+        assert!(code.tableswitch(i32::MAX, label, &[label]).is_ok());
+    }
+
+    #[test]
+    fn it_should_count_the_receiver_of_interface_methods() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+
+        code.invokeinterface("java/util/List", "add", "(Ljava/lang/Object;)Z")
+            .unwrap();
+        assert!(matches!(
+            code.invokeinterface("my/I", "m", &format!("({})V", "I".repeat(255))),
+            Err(ClassFileError::InvalidDescriptor(_))
+        ));
+
+        assert_eq!(
+            vec![(0, Instruction::Invokeinterface { index: 6, count: 2 })],
+            instructions(&code.build().unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_use_the_shortest_instructions() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+
+        code.iconst(3)
+            .unwrap()
+            .iconst(100)
+            .unwrap()
+            .iconst(100000)
+            .unwrap();
+        code.aload(3).aload(4).aload(300).iinc(1, 200);
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iconst3),
+                (1, Instruction::Bipush(100)),
+                (3, Instruction::Ldc(1)),
+                (5, Instruction::Aload3),
+                (6, Instruction::Aload(4)),
+                (8, Instruction::Wide(WideInstruction::Aload(300))),
+                (
+                    12,
+                    Instruction::Wide(WideInstruction::Iinc {
+                        index: 1,
+                        value: 200
+                    })
+                ),
+            ],
+            instructions(&code.build().unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_fail_on_unplaced_labels() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new(&mut constant_pool);
+        let label = code.new_label();
+
+        code.goto(label);
+
+        assert!(matches!(
+            code.build(),
+            Err(ClassFileError::UnplacedLabel(0))
+        ));
+    }
+}
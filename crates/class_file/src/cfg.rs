@@ -0,0 +1,273 @@
+// Splits the code of a method into basic blocks, maximal runs of instructions that are only
+// entered at the first and, exceptions aside, only left after the last. Blocks are numbered in the
+// order of their pcs, so block 0 is the entry of the method.
+//
+// Besides its normal edges, a block has an exceptional edge to each handler whose range covers
+// it. Blocks are also split at the bounds of these ranges, so that every block is either wholly
+// inside or wholly outside each of them.
+
+mod dominators;
+
+use std::{collections::BTreeSet, fmt};
+
+pub use self::dominators::{Dominators, Loop};
+use crate::{
+    attributes::CodeAttribute,
+    instruction::{Instruction, WideInstruction},
+    verifier::{branch_targets, falls_through},
+    ClassFileError, Result,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start_pc: u32,
+    /// The pc following the last instruction of the block.
+    pub end_pc: u32,
+    pub instructions: Vec<(u32, Instruction)>,
+    /// The blocks control may go to after the last instruction: its branch targets, then the
+    /// following block if the instruction falls through. A `ret` has no successors.
+    pub successors: Vec<usize>,
+    pub predecessors: Vec<usize>,
+    /// The handlers of the exceptions thrown in the block, in the order of the exception table.
+    pub exception_handlers: Vec<ExceptionEdge>,
+    /// The blocks whose exceptions are handled by this block.
+    pub exception_predecessors: Vec<usize>,
+}
+impl BasicBlock {
+    /// The successors and exception handlers of the block.
+    pub fn all_successors(&self) -> impl Iterator<Item = usize> + '_ {
+        self.successors
+            .iter()
+            .copied()
+            .chain(self.exception_handlers.iter().map(|edge| edge.handler))
+    }
+
+    /// The predecessors of the block and the blocks whose exceptions it handles.
+    pub fn all_predecessors(&self) -> impl Iterator<Item = usize> + '_ {
+        self.predecessors
+            .iter()
+            .chain(&self.exception_predecessors)
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionEdge {
+    pub handler: usize,
+    /// The index of the Class entry of the exceptions caught, 0 for all exceptions.
+    pub catch_type: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+}
+impl ControlFlowGraph {
+    pub fn new(code: &CodeAttribute) -> Result<Self> {
+        let instructions = code.instructions().collect::<Result<Vec<_>>>()?;
+        let code_length = code.code.len() as u32;
+        let is_instruction = |pc: u32| {
+            instructions
+                .binary_search_by_key(&pc, |(instruction_pc, _)| *instruction_pc)
+                .is_ok()
+        };
+
+        let mut leaders = BTreeSet::from([0]);
+        let mut targets = Vec::with_capacity(instructions.len());
+        for (i, (pc, instruction)) in instructions.iter().enumerate() {
+            let branch_targets = branch_targets(*pc, instruction, code_length as usize)
+                .map_err(|_| ClassFileError::InvalidInstruction(*pc))?;
+            for target in &branch_targets {
+                if !is_instruction(*target) {
+                    return Err(ClassFileError::InvalidInstruction(*pc));
+                }
+            }
+            if !branch_targets.is_empty() || !continues(instruction) {
+                if let Some((next_pc, _)) = instructions.get(i + 1) {
+                    leaders.insert(*next_pc);
+                }
+            }
+            leaders.extend(branch_targets.iter().copied());
+            targets.push(branch_targets);
+        }
+        for handler in &code.exception_table {
+            let (start_pc, end_pc, handler_pc) = (
+                handler.start_pc as u32,
+                handler.end_pc as u32,
+                handler.handler_pc as u32,
+            );
+            for pc in [start_pc, handler_pc] {
+                if !is_instruction(pc) {
+                    return Err(ClassFileError::InvalidCodeOffset(pc));
+                }
+            }
+            if end_pc != code_length && !is_instruction(end_pc) {
+                return Err(ClassFileError::InvalidCodeOffset(end_pc));
+            }
+            leaders.extend([start_pc, handler_pc]);
+            if end_pc < code_length {
+                leaders.insert(end_pc);
+            }
+        }
+
+        // The instructions of each block, with the branch targets of the last one.
+        let mut blocks = Vec::<BasicBlock>::new();
+        let mut last_targets = vec![];
+        for ((pc, instruction), targets) in instructions.into_iter().zip(targets) {
+            if leaders.contains(&pc) {
+                blocks.push(BasicBlock {
+                    start_pc: pc,
+                    end_pc: pc,
+                    instructions: vec![],
+                    successors: vec![],
+                    predecessors: vec![],
+                    exception_handlers: vec![],
+                    exception_predecessors: vec![],
+                });
+                last_targets.push(vec![]);
+            }
+            let block = blocks
+                .last_mut()
+                .expect("the first instruction starts a block");
+            block.instructions.push((pc, instruction));
+            *last_targets.last_mut().expect("pushed with the block") = targets;
+        }
+        for i in 0..blocks.len() {
+            blocks[i].end_pc = blocks.get(i + 1).map_or(code_length, |next| next.start_pc);
+        }
+
+        let mut cfg = Self { blocks };
+        for (i, targets) in last_targets.into_iter().enumerate() {
+            let block = &cfg.blocks[i];
+            let (_, last) = block.instructions.last().expect("blocks are not empty");
+            let mut successors = targets
+                .into_iter()
+                .map(|target| cfg.block_at(target).expect("targets are instructions"))
+                .collect::<Vec<_>>();
+            if continues(last) && i + 1 < cfg.blocks.len() {
+                successors.push(i + 1);
+            }
+            let mut seen = BTreeSet::new();
+            successors.retain(|successor| seen.insert(*successor));
+
+            let exception_handlers = code
+                .exception_table
+                .iter()
+                .filter(|handler| {
+                    handler.start_pc as u32 <= block.start_pc
+                        && block.start_pc < handler.end_pc as u32
+                })
+                .map(|handler| ExceptionEdge {
+                    handler: cfg
+                        .block_at(handler.handler_pc as u32)
+                        .expect("handlers are instructions"),
+                    catch_type: handler.catch_type,
+                })
+                .collect::<Vec<_>>();
+
+            for successor in &successors {
+                cfg.blocks[*successor].predecessors.push(i);
+            }
+            for edge in &exception_handlers {
+                let predecessors = &mut cfg.blocks[edge.handler].exception_predecessors;
+                if !predecessors.contains(&i) {
+                    predecessors.push(i);
+                }
+            }
+            cfg.blocks[i].successors = successors;
+            cfg.blocks[i].exception_handlers = exception_handlers;
+        }
+
+        Ok(cfg)
+    }
+
+    /// The block of the instruction at `pc`.
+    pub fn block_at(&self, pc: u32) -> Option<usize> {
+        let index = self
+            .blocks
+            .partition_point(|block| block.start_pc <= pc)
+            .checked_sub(1)?;
+        (pc < self.blocks[index].end_pc).then_some(index)
+    }
+
+    /// The blocks reachable from the entry, through normal or exceptional edges, in reverse
+    /// postorder: each block comes before its successors, except along back edges.
+    pub fn reverse_postorder(&self) -> Vec<usize> {
+        let mut postorder = Vec::with_capacity(self.blocks.len());
+        if self.blocks.is_empty() {
+            return postorder;
+        }
+
+        let mut visited = vec![false; self.blocks.len()];
+        // The blocks being visited, with their successors still to visit.
+        let mut stack = vec![(0, self.blocks[0].all_successors().collect::<Vec<_>>())];
+        visited[0] = true;
+        while let Some((block, successors)) = stack.last_mut() {
+            match successors.pop() {
+                Some(successor) if !visited[successor] => {
+                    visited[successor] = true;
+                    let successors = self.blocks[successor].all_successors().collect();
+                    stack.push((successor, successors));
+                }
+                Some(_) => {}
+                None => {
+                    postorder.push(*block);
+                    stack.pop();
+                }
+            }
+        }
+
+        postorder.reverse();
+        postorder
+    }
+
+    /// The graph in the DOT language of Graphviz, with a node for each block listing its
+    /// instructions. Exceptional edges are dashed.
+    pub fn dot(&self) -> Dot<'_> {
+        Dot { cfg: self }
+    }
+}
+
+/// Whether control may go on to the next instruction after `instruction`. A `ret` returns to the
+/// instruction following a `jsr`, which is not known here, so it ends the block like a return.
+fn continues(instruction: &Instruction) -> bool {
+    falls_through(instruction)
+        && !matches!(
+            instruction,
+            Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_))
+        )
+}
+
+pub struct Dot<'a> {
+    cfg: &'a ControlFlowGraph,
+}
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph cfg {{")?;
+        writeln!(f, "  node [shape=box, fontname=\"monospace\"];")?;
+        for (i, block) in self.cfg.blocks.iter().enumerate() {
+            write!(f, "  b{} [label=\"", i)?;
+            for (pc, instruction) in &block.instructions {
+                write!(f, "{}: {}\\l", pc, instruction.mnemonic())?;
+            }
+            writeln!(f, "\"];")?;
+        }
+        for (i, block) in self.cfg.blocks.iter().enumerate() {
+            for successor in &block.successors {
+                writeln!(f, "  b{} -> b{};", i, successor)?;
+            }
+            for edge in &block.exception_handlers {
+                let catch_type = match edge.catch_type {
+                    0 => "any".to_owned(),
+                    catch_type => format!("#{}", catch_type),
+                };
+                writeln!(
+                    f,
+                    "  b{} -> b{} [style=dashed, label=\"{}\"];",
+                    i, edge.handler, catch_type
+                )?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
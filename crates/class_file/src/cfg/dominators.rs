@@ -0,0 +1,163 @@
+// Dominators are computed with the iterative algorithm of Cooper, Harvey and Kennedy, "A Simple,
+// Fast Dominance Algorithm", over both normal and exceptional edges.
+
+use std::collections::BTreeMap;
+
+use super::ControlFlowGraph;
+
+/// The dominator tree of a control flow graph. A block dominates another if every path from the
+/// entry to the other block goes through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dominators {
+    /// The immediate dominator of each block, the entry for itself and `None` for unreachable
+    /// blocks.
+    idoms: Vec<Option<usize>>,
+}
+impl Dominators {
+    /// The immediate dominator of `block`, `None` for the entry and unreachable blocks.
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.idoms[block].filter(|_| block != 0)
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.idoms[block].is_some()
+    }
+
+    /// Whether `a` dominates `b`. Every reachable block dominates itself.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        if !self.is_reachable(b) {
+            return false;
+        }
+
+        let mut block = b;
+        loop {
+            if block == a {
+                return true;
+            }
+            match self.immediate_dominator(block) {
+                Some(idom) => block = idom,
+                None => return false,
+            }
+        }
+    }
+
+    /// The blocks `block` immediately dominates, i.e. its children in the dominator tree.
+    pub fn children(&self, block: usize) -> Vec<usize> {
+        (0..self.idoms.len())
+            .filter(|child| self.immediate_dominator(*child) == Some(block))
+            .collect()
+    }
+}
+
+/// A natural loop, the blocks from which a back edge to the header can be reached without going
+/// through the header. Loops that share a header are merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// The blocks with a back edge to the header.
+    pub latches: Vec<usize>,
+    /// The blocks of the loop in increasing order, including the header.
+    pub blocks: Vec<usize>,
+}
+impl Loop {
+    pub fn contains(&self, block: usize) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+}
+
+impl ControlFlowGraph {
+    pub fn dominators(&self) -> Dominators {
+        let mut idoms = vec![None; self.blocks.len()];
+        let reverse_postorder = self.reverse_postorder();
+        let Some(&entry) = reverse_postorder.first() else {
+            return Dominators { idoms };
+        };
+
+        let mut order = vec![usize::MAX; self.blocks.len()];
+        for (i, block) in reverse_postorder.iter().enumerate() {
+            order[*block] = i;
+        }
+        let intersect = |idoms: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while order[a] > order[b] {
+                    a = idoms[a].expect("processed blocks have dominators");
+                }
+                while order[b] > order[a] {
+                    b = idoms[b].expect("processed blocks have dominators");
+                }
+            }
+            a
+        };
+
+        idoms[entry] = Some(entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in &reverse_postorder[1..] {
+                let mut processed = self.blocks[*block]
+                    .all_predecessors()
+                    .filter(|predecessor| idoms[*predecessor].is_some());
+                let first = processed
+                    .next()
+                    .expect("a block follows one of its predecessors in reverse postorder");
+                let idom = processed.fold(first, |idom, predecessor| {
+                    intersect(&idoms, predecessor, idom)
+                });
+                if idoms[*block] != Some(idom) {
+                    idoms[*block] = Some(idom);
+                    changed = true;
+                }
+            }
+        }
+
+        Dominators { idoms }
+    }
+
+    /// The natural loops of the graph, ordered by header. Only normal edges are back edges, since
+    /// the handler of a `finally` block commonly covers its own first instructions. Loops whose
+    /// header does not dominate the rest of the loop, which Java compilers do not generate, are
+    /// not found.
+    pub fn loops(&self) -> Vec<Loop> {
+        let dominators = self.dominators();
+
+        let mut latches = BTreeMap::<usize, Vec<usize>>::new();
+        for (block, basic_block) in self.blocks.iter().enumerate() {
+            for &successor in &basic_block.successors {
+                if dominators.dominates(successor, block) {
+                    let latches = latches.entry(successor).or_default();
+                    if !latches.contains(&block) {
+                        latches.push(block);
+                    }
+                }
+            }
+        }
+
+        latches
+            .into_iter()
+            .map(|(header, latches)| {
+                let mut in_loop = vec![false; self.blocks.len()];
+                in_loop[header] = true;
+                let mut worklist = latches.clone();
+                while let Some(block) = worklist.pop() {
+                    if !in_loop[block] {
+                        in_loop[block] = true;
+                        // Unreachable blocks may jump into the loop, but are not part of it.
+                        worklist.extend(
+                            self.blocks[block]
+                                .all_predecessors()
+                                .filter(|predecessor| dominators.dominates(header, *predecessor)),
+                        );
+                    }
+                }
+
+                Loop {
+                    header,
+                    latches,
+                    blocks: (0..self.blocks.len())
+                        .filter(|block| in_loop[*block])
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
@@ -1,24 +1,29 @@
 use std::io::{Read, Seek};
 
 use crate::{
-    attributes::Attributes, constant_pool::ClassInfo, matches_cp_info, parser::Parser, AccessFlags,
-    ConstantPool, Result,
+    attributes::Attributes,
+    constant_pool::{ClassInfo, CpIndex},
+    parser::Parser,
+    ClassAccessFlags, ConstantPool, FieldAccessFlags, MethodAccessFlags, Result,
 };
 
 #[derive(Debug)]
 pub struct ClassFile {
+    pub minor_version: u16,
+    pub major_version: u16,
     pub constant_pool: ConstantPool,
-    pub access_flags: AccessFlags,
-    pub this_class: u16,
-    pub super_class: u16,
-    pub interfaces: Vec<u16>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: CpIndex<ClassInfo>,
+    /// Zero for `java/lang/Object`, which has no superclass.
+    pub super_class: CpIndex<ClassInfo>,
+    pub interfaces: Vec<CpIndex<ClassInfo>>,
     pub fields: Vec<FieldInfo>,
     pub methods: Vec<MethodInfo>,
     pub attributes: Attributes,
 }
 impl ClassFile {
     pub fn parse(bytes: impl Read + Seek) -> Result<ClassFile> {
-        Ok(Parser::new(bytes).parse()?)
+        Parser::new(bytes).parse()
     }
 
     pub fn super_class(&self) -> Result<Option<&str>> {
@@ -29,24 +34,16 @@ impl ClassFile {
         // any of its superclasses may have the ACC_FINAL flag set in the access_flags item of its
         // ClassFile structure.
         //
-        // FIXME: For an interface, the value of the super_class item must always be a valid index
-        //        into the constant_pool table. The constant_pool entry at that index must be a
-        //        CONSTANT_Class_info structure representing the class Object.
-
-        let ClassInfo { name_index } =
-            matches_cp_info!(self.constant_pool, self.super_class, Class)?;
+        // For an interface, the super_class item must be the class Object. This is checked by
+        // `ClassFile::format_check`.
 
         // If the value of the super_class item is zero, then this class file must represent the class Object,
         // the only class or interface without a direct superclass.
-        if *name_index == 0 {
+        if self.super_class.get() == 0 {
             return Ok(None);
         }
 
-        Ok(Some(matches_cp_info!(
-            self.constant_pool,
-            *name_index,
-            Utf8
-        )?))
+        self.constant_pool.class_name(self.super_class).map(Some)
     }
 
     pub fn class_name(&self) -> Result<&str> {
@@ -54,57 +51,38 @@ impl ClassFile {
         // The constant_pool entry at that index must be a CONSTANT_Class_info structure (§4.4.1)
         // representing the class or interface defined by this class file.
 
-        let ClassInfo { name_index } =
-            matches_cp_info!(self.constant_pool, self.this_class, Class)?;
-
-        matches_cp_info!(self.constant_pool, *name_index, Utf8)
+        self.constant_pool.class_name(self.this_class)
     }
 
     pub fn field_name(&self, field: &FieldInfo) -> Result<&str> {
-        Ok(matches_cp_info!(
-            self.constant_pool,
-            field.name_index,
-            Utf8
-        )?)
+        self.constant_pool.utf8(field.name_index)
     }
 
     pub fn field_descriptor(&self, field: &FieldInfo) -> Result<&str> {
-        Ok(matches_cp_info!(
-            self.constant_pool,
-            field.descriptor_index,
-            Utf8
-        )?)
+        self.constant_pool.utf8(field.descriptor_index)
     }
 
     pub fn method_name(&self, method: &MethodInfo) -> Result<&str> {
-        Ok(matches_cp_info!(
-            self.constant_pool,
-            method.name_index,
-            Utf8
-        )?)
+        self.constant_pool.utf8(method.name_index)
     }
 
     pub fn method_descriptor(&self, method: &MethodInfo) -> Result<&str> {
-        Ok(matches_cp_info!(
-            self.constant_pool,
-            method.descriptor_index,
-            Utf8
-        )?)
+        self.constant_pool.utf8(method.descriptor_index)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FieldInfo {
-    pub access_flags: AccessFlags,
-    pub name_index: u16,
-    pub descriptor_index: u16,
+    pub access_flags: FieldAccessFlags,
+    pub name_index: CpIndex<str>,
+    pub descriptor_index: CpIndex<str>,
     pub attributes: Attributes,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MethodInfo {
-    pub access_flags: AccessFlags,
-    pub name_index: u16,
-    pub descriptor_index: u16,
+    pub access_flags: MethodAccessFlags,
+    pub name_index: CpIndex<str>,
+    pub descriptor_index: CpIndex<str>,
     pub attributes: Attributes,
 }
@@ -1,14 +1,336 @@
-use std::ops::Index;
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
+    ops::Index,
+};
 
-#[derive(Debug, Default)]
+use crate::{ClassFileError, Result};
+
+/// An index into the constant pool of an entry of type `T`, e.g. `CpIndex<ClassInfo>` for a Class
+/// entry and `CpIndex<str>` for a Utf8 entry. The type is not checked when the index is created,
+/// but [`ConstantPool::entry`] checks it once for every use.
+pub struct CpIndex<T: ?Sized> {
+    index: u16,
+    entry: PhantomData<fn() -> T>,
+}
+impl<T: ?Sized> CpIndex<T> {
+    pub const fn new(index: u16) -> Self {
+        Self {
+            index,
+            entry: PhantomData,
+        }
+    }
+
+    pub const fn get(self) -> u16 {
+        self.index
+    }
+}
+impl<T: ?Sized> From<CpIndex<T>> for u16 {
+    fn from(index: CpIndex<T>) -> Self {
+        index.index
+    }
+}
+// Implemented by hand since `T` needn't implement the traits.
+impl<T: ?Sized> Clone for CpIndex<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Copy for CpIndex<T> {}
+impl<T: ?Sized> PartialEq for CpIndex<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+impl<T: ?Sized> Eq for CpIndex<T> {}
+impl<T: ?Sized> Hash for CpIndex<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state)
+    }
+}
+impl<T: ?Sized> fmt::Debug for CpIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.index, f)
+    }
+}
+impl<T: ?Sized> fmt::Display for CpIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.index, f)
+    }
+}
+
+/// A structure of the constant pool that a [`CpIndex`] can refer to.
+pub trait CpEntry {
+    /// The name of the entry in errors, as in [`ClassFileError::UnexpectedConstantPoolEntry`].
+    const NAME: &'static str;
+
+    fn from_cp_info(cp_info: &CpInfo) -> Option<&Self>;
+}
+impl CpEntry for str {
+    const NAME: &'static str = "Utf8";
+
+    fn from_cp_info(cp_info: &CpInfo) -> Option<&Self> {
+        match cp_info {
+            CpInfo::Utf8(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+/// Any of the FieldRef, MethodRef and InterfaceMethodRef entries.
+impl CpEntry for RefInfo {
+    const NAME: &'static str = "Ref";
+
+    fn from_cp_info(cp_info: &CpInfo) -> Option<&Self> {
+        match cp_info {
+            CpInfo::FieldRef(r) | CpInfo::MethodRef(r) | CpInfo::InterfaceMethodRef(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+/// Any entry, for an index whose type isn't known.
+impl CpEntry for CpInfo {
+    const NAME: &'static str = "Entry";
+
+    fn from_cp_info(cp_info: &CpInfo) -> Option<&Self> {
+        Some(cp_info)
+    }
+}
+/// A String entry, whose structure is the index of the Utf8 entry of its value.
+impl CpEntry for CpIndex<str> {
+    const NAME: &'static str = "String";
+
+    fn from_cp_info(cp_info: &CpInfo) -> Option<&Self> {
+        match cp_info {
+            CpInfo::String { string_index } => Some(string_index),
+            _ => None,
+        }
+    }
+}
+macro_rules! cp_entries {
+    ($($t:ty => $i:ident),* $(,)?) => {
+        $(
+            impl CpEntry for $t {
+                const NAME: &'static str = stringify!($i);
+
+                fn from_cp_info(cp_info: &CpInfo) -> Option<&Self> {
+                    match cp_info {
+                        CpInfo::$i(entry) => Some(entry),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+cp_entries! {
+    ClassInfo => Class,
+    NameAndTypeInfo => NameAndType,
+    InvokeDynamicInfo => InvokeDynamic,
+    DynamicInfo => Dynamic,
+    MethodHandleInfo => MethodHandle,
+    MethodTypeInfo => MethodType,
+    ModuleInfo => Module,
+    PackageInfo => Package,
+    i32 => Integer,
+    f32 => Float,
+    i64 => Long,
+    f64 => Double,
+}
+
+#[derive(Default, Clone)]
 pub struct ConstantPool {
     cp_infos: Vec<CpInfo>,
+    /// The index of the first of each entry, for [`ConstantPool::find_or_insert`]. Only the
+    /// first `indexed` slots are in it, the rest are added on the next lookup so that parsing
+    /// doesn't pay for it.
+    indices: HashMap<Key, u16>,
+    indexed: usize,
 }
 impl ConstantPool {
     pub fn new(cp_infos: Vec<CpInfo>) -> Self {
-        Self { cp_infos }
+        Self {
+            cp_infos,
+            ..Self::default()
+        }
+    }
+
+    /// The number of slots in the constant pool, one less than the constant_pool_count.
+    pub fn len(&self) -> usize {
+        self.cp_infos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cp_infos.is_empty()
+    }
+
+    /// The entry at `index`, `None` for index 0 and beyond the end of the constant pool.
+    pub fn get(&self, index: u16) -> Option<&CpInfo> {
+        (index as usize)
+            .checked_sub(1)
+            .and_then(|i| self.cp_infos.get(i))
+    }
+
+    /// The entry at `index`, if it is of the type of the index.
+    pub fn entry<T: CpEntry + ?Sized>(&self, index: CpIndex<T>) -> Result<&T> {
+        let cp_info = self
+            .get(index.get())
+            .ok_or(ClassFileError::InvalidConstantPoolIndex(index.get()))?;
+        T::from_cp_info(cp_info)
+            .ok_or_else(|| ClassFileError::UnexpectedConstantPoolEntry(T::NAME, cp_info.clone()))
+    }
+
+    /// The string of the Utf8 entry at `index`.
+    pub fn utf8(&self, index: CpIndex<str>) -> Result<&str> {
+        self.entry(index)
+    }
+
+    /// The name of the Class entry at `index`.
+    pub fn class_name(&self, index: CpIndex<ClassInfo>) -> Result<&str> {
+        self.utf8(self.entry(index)?.name_index)
+    }
+
+    /// The name of the Module entry at `index`.
+    pub fn module_name(&self, index: CpIndex<ModuleInfo>) -> Result<&str> {
+        self.utf8(self.entry(index)?.name_index)
+    }
+
+    /// The name of the Package entry at `index`, in internal form.
+    pub fn package_name(&self, index: CpIndex<PackageInfo>) -> Result<&str> {
+        self.utf8(self.entry(index)?.name_index)
+    }
+
+    /// The index of the first entry equal to `cp_info`, which is added at the end if there is
+    /// none. Floats and doubles are equal if their bits are, so that NaN is found and 0.0 and -0.0
+    /// are told apart.
+    pub fn find_or_insert(&mut self, cp_info: CpInfo) -> Result<u16> {
+        for (i, cp_info) in self.cp_infos.iter().enumerate().skip(self.indexed) {
+            if *cp_info != CpInfo::Unusable {
+                self.indices
+                    .entry(Key(cp_info.clone()))
+                    .or_insert(i as u16 + 1);
+            }
+        }
+        self.indexed = self.cp_infos.len();
+
+        let key = Key(cp_info);
+        if let Some(index) = self.indices.get(&key) {
+            return Ok(*index);
+        }
+
+        let index = self.push(key.0.clone())?;
+        self.indices.insert(key, index);
+        self.indexed = self.cp_infos.len();
+        Ok(index)
+    }
+
+    /// Adds `cp_info` at the end, followed by an unusable slot if it is a Long or Double, and
+    /// returns its index.
+    pub(crate) fn push(&mut self, cp_info: CpInfo) -> Result<u16> {
+        let slots = match cp_info {
+            CpInfo::Long(_) | CpInfo::Double(_) => 2,
+            _ => 1,
+        };
+        // The constant_pool_count is one more than the number of entries.
+        if self.cp_infos.len() + slots >= u16::MAX as usize {
+            return Err(ClassFileError::ValueTooLarge("constant_pool_count"));
+        }
+
+        self.cp_infos.push(cp_info);
+        let index = self.cp_infos.len() as u16;
+        if slots == 2 {
+            self.cp_infos.push(CpInfo::Unusable);
+        }
+        Ok(index)
+    }
+
+    pub fn find_or_insert_utf8(&mut self, s: &str) -> Result<CpIndex<str>> {
+        self.find_or_insert(CpInfo::Utf8(s.to_owned()))
+            .map(CpIndex::new)
+    }
+
+    /// A Class entry for the class, interface or array type named in internal form.
+    pub fn find_or_insert_class(&mut self, name: &str) -> Result<CpIndex<ClassInfo>> {
+        let name_index = self.find_or_insert_utf8(name)?;
+        self.find_or_insert(CpInfo::Class(ClassInfo { name_index }))
+            .map(CpIndex::new)
+    }
+
+    pub fn find_or_insert_name_and_type(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<NameAndTypeInfo>> {
+        let name_index = self.find_or_insert_utf8(name)?;
+        let descriptor_index = self.find_or_insert_utf8(descriptor)?;
+        self.find_or_insert(CpInfo::NameAndType(NameAndTypeInfo {
+            name_index,
+            descriptor_index,
+        }))
+        .map(CpIndex::new)
+    }
+
+    pub fn find_or_insert_field_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        let ref_info = self.find_or_insert_ref_info(class, name, descriptor)?;
+        self.find_or_insert(CpInfo::FieldRef(ref_info))
+            .map(CpIndex::new)
+    }
+
+    pub fn find_or_insert_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        let ref_info = self.find_or_insert_ref_info(class, name, descriptor)?;
+        self.find_or_insert(CpInfo::MethodRef(ref_info))
+            .map(CpIndex::new)
+    }
+
+    pub fn find_or_insert_interface_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<CpIndex<RefInfo>> {
+        let ref_info = self.find_or_insert_ref_info(class, name, descriptor)?;
+        self.find_or_insert(CpInfo::InterfaceMethodRef(ref_info))
+            .map(CpIndex::new)
+    }
+
+    fn find_or_insert_ref_info(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<RefInfo> {
+        Ok(RefInfo {
+            class_index: self.find_or_insert_class(class)?,
+            name_and_type_index: self.find_or_insert_name_and_type(name, descriptor)?,
+        })
+    }
+}
+// The index is left out, since it only caches the entries.
+impl fmt::Debug for ConstantPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConstantPool")
+            .field("cp_infos", &self.cp_infos)
+            .finish()
+    }
+}
+impl PartialEq for ConstantPool {
+    fn eq(&self, other: &Self) -> bool {
+        self.cp_infos == other.cp_infos
     }
 }
+/// Panics if there is no entry at `index`, see [`ConstantPool::get`].
 impl Index<u16> for ConstantPool {
     type Output = CpInfo;
 
@@ -16,6 +338,13 @@ impl Index<u16> for ConstantPool {
         &self.cp_infos[index as usize - 1]
     }
 }
+impl<T: ?Sized> Index<CpIndex<T>> for ConstantPool {
+    type Output = CpInfo;
+
+    fn index(&self, index: CpIndex<T>) -> &Self::Output {
+        &self[index.get()]
+    }
+}
 impl<'a> IntoIterator for &'a ConstantPool {
     type Item = &'a CpInfo;
     type IntoIter = std::slice::Iter<'a, CpInfo>;
@@ -25,17 +354,45 @@ impl<'a> IntoIterator for &'a ConstantPool {
     }
 }
 
-#[macro_export]
-macro_rules! matches_cp_info {
-    ($cp:expr, $index:expr, $i:ident) => {
-        match &$cp[$index] {
-            crate::constant_pool::CpInfo::$i(ref n) => Ok(n),
-            c => Err(crate::ClassFileError::UnexpectedConstantPoolEntry(
-                stringify!($i),
-                c.clone(),
-            )),
+/// An entry as a key of [`ConstantPool::find_or_insert`]. Floats and doubles are equal if their
+/// bits are, so that NaN is found and 0.0 and -0.0 are told apart.
+#[derive(Debug, Clone)]
+struct Key(CpInfo);
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (CpInfo::Float(a), CpInfo::Float(b)) => a.to_bits() == b.to_bits(),
+            (CpInfo::Double(a), CpInfo::Double(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => a == b,
         }
-    };
+    }
+}
+impl Eq for Key {}
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(&self.0).hash(state);
+        match &self.0 {
+            CpInfo::MethodRef(r) | CpInfo::FieldRef(r) | CpInfo::InterfaceMethodRef(r) => {
+                r.hash(state)
+            }
+            CpInfo::Float(f) => f.to_bits().hash(state),
+            CpInfo::Class(c) => c.hash(state),
+            CpInfo::NameAndType(nat) => nat.hash(state),
+            CpInfo::Utf8(s) => s.hash(state),
+            CpInfo::RawUtf8(bytes) => bytes.hash(state),
+            CpInfo::String { string_index } => string_index.hash(state),
+            CpInfo::InvokeDynamic(i) => i.hash(state),
+            CpInfo::Integer(i) => i.hash(state),
+            CpInfo::MethodHandle(h) => h.hash(state),
+            CpInfo::MethodType(t) => t.hash(state),
+            CpInfo::Long(l) => l.hash(state),
+            CpInfo::Double(d) => d.to_bits().hash(state),
+            CpInfo::Dynamic(d) => d.hash(state),
+            CpInfo::Module(m) => m.hash(state),
+            CpInfo::Package(p) => p.hash(state),
+            CpInfo::Unusable => {}
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -47,48 +404,161 @@ pub enum CpInfo {
     Class(ClassInfo),
     NameAndType(NameAndTypeInfo),
     Utf8(String),
-    String { string_index: u16 },
+    /// A CONSTANT_Utf8 entry containing unpaired surrogates, kept as raw Modified UTF-8 bytes
+    /// when the parser is asked to. See [`crate::Parser::keep_raw_utf8`].
+    RawUtf8(Vec<u8>),
+    String {
+        string_index: CpIndex<str>,
+    },
     InvokeDynamic(InvokeDynamicInfo),
     Integer(i32),
     MethodHandle(MethodHandleInfo),
     MethodType(MethodTypeInfo),
     Long(i64),
+    Double(f64),
+    Dynamic(DynamicInfo),
+    Module(ModuleInfo),
+    Package(PackageInfo),
+    /// The slot following a Long or Double, which takes up two entries in the constant pool.
     Unusable,
 }
+impl CpInfo {
+    /// The name of the kind of entry, the [`CpEntry::NAME`] of the entries with a structure of
+    /// their own.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CpInfo::MethodRef(_) => "MethodRef",
+            CpInfo::FieldRef(_) => "FieldRef",
+            CpInfo::InterfaceMethodRef(_) => "InterfaceMethodRef",
+            CpInfo::Float(_) => "Float",
+            CpInfo::String { .. } => "String",
+            CpInfo::Integer(_) => "Integer",
+            CpInfo::Long(_) => "Long",
+            CpInfo::Double(_) => "Double",
+            CpInfo::Unusable => "Unusable",
+            CpInfo::Utf8(_) | CpInfo::RawUtf8(_) => str::NAME,
+            CpInfo::Class(_) => ClassInfo::NAME,
+            CpInfo::NameAndType(_) => NameAndTypeInfo::NAME,
+            CpInfo::InvokeDynamic(_) => InvokeDynamicInfo::NAME,
+            CpInfo::MethodHandle(_) => MethodHandleInfo::NAME,
+            CpInfo::MethodType(_) => MethodTypeInfo::NAME,
+            CpInfo::Dynamic(_) => DynamicInfo::NAME,
+            CpInfo::Module(_) => ModuleInfo::NAME,
+            CpInfo::Package(_) => PackageInfo::NAME,
+        }
+    }
+}
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct RefInfo {
-    pub class_index: u16,
-    pub name_and_type_index: u16,
+    pub class_index: CpIndex<ClassInfo>,
+    pub name_and_type_index: CpIndex<NameAndTypeInfo>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ClassInfo {
     // The value of the name_index item must be a valid index into the constant_pool table.
     // The constant_pool entry at that index must be a CONSTANT_Utf8_info structure (§4.4.7)
     // representing a valid binary class or interface name encoded in internal form (§4.2.1).
-    pub name_index: u16,
+    pub name_index: CpIndex<str>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct NameAndTypeInfo {
-    pub name_index: u16,
-    pub descriptor_index: u16,
+    pub name_index: CpIndex<str>,
+    pub descriptor_index: CpIndex<str>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct InvokeDynamicInfo {
     pub bootstrap_method_attr_index: u16,
-    pub name_and_type_index: u16,
+    pub name_and_type_index: CpIndex<NameAndTypeInfo>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DynamicInfo {
+    pub bootstrap_method_attr_index: u16,
+    pub name_and_type_index: CpIndex<NameAndTypeInfo>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ModuleInfo {
+    // The constant_pool entry at name_index must be a CONSTANT_Utf8_info structure (§4.4.7)
+    // representing a valid module name (§4.2.3).
+    pub name_index: CpIndex<str>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PackageInfo {
+    // The constant_pool entry at name_index must be a CONSTANT_Utf8_info structure (§4.4.7)
+    // representing a valid package name encoded in internal form (§4.2.3).
+    pub name_index: CpIndex<str>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct MethodHandleInfo {
-    pub reference_kind: u8,
-    pub reference_index: u16,
+    pub reference_kind: ReferenceKind,
+    pub reference_index: CpIndex<RefInfo>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// The kind of a method handle, which characterizes its bytecode behavior (§5.4.3.5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+impl ReferenceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceKind::GetField => "REF_getField",
+            ReferenceKind::GetStatic => "REF_getStatic",
+            ReferenceKind::PutField => "REF_putField",
+            ReferenceKind::PutStatic => "REF_putStatic",
+            ReferenceKind::InvokeVirtual => "REF_invokeVirtual",
+            ReferenceKind::InvokeStatic => "REF_invokeStatic",
+            ReferenceKind::InvokeSpecial => "REF_invokeSpecial",
+            ReferenceKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+            ReferenceKind::InvokeInterface => "REF_invokeInterface",
+        }
+    }
+
+    /// Whether the method handle refers to a field rather than a method.
+    pub fn is_field(self) -> bool {
+        (self as u8) <= ReferenceKind::PutStatic as u8
+    }
+}
+impl TryFrom<u8> for ReferenceKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ReferenceKind::GetField),
+            2 => Ok(ReferenceKind::GetStatic),
+            3 => Ok(ReferenceKind::PutField),
+            4 => Ok(ReferenceKind::PutStatic),
+            5 => Ok(ReferenceKind::InvokeVirtual),
+            6 => Ok(ReferenceKind::InvokeStatic),
+            7 => Ok(ReferenceKind::InvokeSpecial),
+            8 => Ok(ReferenceKind::NewInvokeSpecial),
+            9 => Ok(ReferenceKind::InvokeInterface),
+            _ => Err(value),
+        }
+    }
+}
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct MethodTypeInfo {
-    pub descriptor_index: u16,
+    pub descriptor_index: CpIndex<str>,
 }
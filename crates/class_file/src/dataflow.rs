@@ -0,0 +1,251 @@
+// A data-flow framework over the control flow graph of a method. An `Analysis` gives the state at
+// the boundary of the method, how states are merged where control flow joins and how each
+// instruction transforms a state. `ControlFlowGraph::analyze` then iterates to a fixpoint and
+// returns the state before and after each instruction.
+//
+// Forward analyses follow control flow from the entry of the method, backward analyses go
+// against it from its exits. An instruction in the range of an exception handler may throw before
+// it has any effect, so going forward the handler receives the state before every instruction of
+// the range, and going backward that state includes what the handler needs.
+
+mod liveness;
+mod reaching_definitions;
+
+use std::collections::{BTreeMap, VecDeque};
+
+pub use self::{
+    liveness::Liveness,
+    reaching_definitions::{Definition, ReachingDefinitions},
+};
+use crate::{
+    cfg::{ControlFlowGraph, ExceptionEdge},
+    instruction::{Instruction, WideInstruction},
+    verifier::{is_store, local_index},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+pub trait Analysis {
+    type State: Clone + PartialEq;
+
+    const DIRECTION: Direction;
+
+    /// The state at the entry of the method for a forward analysis, at its exits for a backward
+    /// one.
+    fn boundary_state(&self) -> Self::State;
+
+    /// Merges `other` into `state` where control flow joins.
+    fn join(&self, state: &mut Self::State, other: &Self::State);
+
+    /// Applies the effect of the instruction at `pc` to `state`, which is the state before the
+    /// instruction for a forward analysis and after it for a backward one.
+    fn transfer(&self, pc: u32, instruction: &Instruction, state: &mut Self::State);
+
+    /// The state that flows along an exceptional edge to or, going backward, from the handler.
+    /// By default the state is unchanged.
+    fn exception_edge(&self, state: &Self::State, _edge: &ExceptionEdge) -> Self::State {
+        state.clone()
+    }
+}
+
+/// The fixpoint of an analysis: the states before and after each instruction, in the order of
+/// execution whatever the direction of the analysis. Going forward, instructions that cannot be
+/// reached from the entry have no states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Results<S> {
+    before: BTreeMap<u32, S>,
+    after: BTreeMap<u32, S>,
+}
+impl<S> Results<S> {
+    pub fn before(&self, pc: u32) -> Option<&S> {
+        self.before.get(&pc)
+    }
+
+    pub fn after(&self, pc: u32) -> Option<&S> {
+        self.after.get(&pc)
+    }
+}
+
+/// A state of the local variables and the operand stack, for analyses that interpret the
+/// instructions. Long and double values take up one element of the stack, like in the
+/// verifier's frames.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FrameState<V> {
+    pub locals: Vec<V>,
+    pub stack: Vec<V>,
+}
+impl<V: Clone> FrameState<V> {
+    /// The state at the entry of a handler: the locals are kept, and the stack only holds the
+    /// exception.
+    pub fn for_handler(&self, exception: V) -> Self {
+        Self {
+            locals: self.locals.clone(),
+            stack: vec![exception],
+        }
+    }
+
+    /// Merges `other` into this state with `join` for each pair of values. The operand stack has
+    /// the same depth wherever control flow joins in verified code, so extra values are dropped.
+    pub fn join_with(&mut self, other: &Self, mut join: impl FnMut(&mut V, &V)) {
+        for (values, others) in [
+            (&mut self.locals, &other.locals),
+            (&mut self.stack, &other.stack),
+        ] {
+            values.truncate(others.len());
+            for (value, other) in values.iter_mut().zip(others) {
+                join(value, other);
+            }
+        }
+    }
+}
+
+impl ControlFlowGraph {
+    pub fn analyze<A: Analysis>(&self, analysis: &A) -> Results<A::State> {
+        let mut results = Results {
+            before: BTreeMap::new(),
+            after: BTreeMap::new(),
+        };
+        let block_count = self.blocks.len();
+        if block_count == 0 {
+            return results;
+        }
+
+        // The state entering each block in the direction of the analysis, `None` until control
+        // reaches it.
+        let mut inputs = vec![None::<A::State>; block_count];
+        let mut order = self.reverse_postorder();
+        let forward = A::DIRECTION == Direction::Forward;
+        if !forward {
+            order.reverse();
+        }
+        let mut worklist = VecDeque::new();
+        let mut queued = vec![false; block_count];
+
+        if forward {
+            inputs[0] = Some(analysis.boundary_state());
+            worklist.push_back(0);
+            queued[0] = true;
+        } else {
+            for (block, basic_block) in self.blocks.iter().enumerate() {
+                if basic_block.successors.is_empty() {
+                    inputs[block] = Some(analysis.boundary_state());
+                }
+            }
+            worklist.extend(order.iter().copied().filter(|b| inputs[*b].is_some()));
+            for block in &worklist {
+                queued[*block] = true;
+            }
+        }
+
+        loop {
+            let Some(block) = worklist.pop_front() else {
+                // Code from which no exit can be reached, like an infinite loop, is analyzed as
+                // if it left the method after its last block.
+                let unreached = match A::DIRECTION {
+                    Direction::Forward => None,
+                    Direction::Backward => order.iter().copied().find(|b| inputs[*b].is_none()),
+                };
+                let Some(block) = unreached else {
+                    break;
+                };
+                inputs[block] = Some(analysis.boundary_state());
+                worklist.push_back(block);
+                queued[block] = true;
+                continue;
+            };
+            queued[block] = false;
+
+            let basic_block = &self.blocks[block];
+            let mut state = inputs[block].clone().expect("queued blocks have a state");
+            // The states that flow out of the block, to its successors and handlers going
+            // forward or to its predecessors going backward.
+            let mut outputs = vec![];
+            if forward {
+                for (pc, instruction) in &basic_block.instructions {
+                    results.before.insert(*pc, state.clone());
+                    for edge in &basic_block.exception_handlers {
+                        outputs.push((edge.handler, analysis.exception_edge(&state, edge)));
+                    }
+                    analysis.transfer(*pc, instruction, &mut state);
+                    results.after.insert(*pc, state.clone());
+                }
+                for successor in &basic_block.successors {
+                    outputs.push((*successor, state.clone()));
+                }
+            } else {
+                let previous = results.before(basic_block.start_pc).cloned();
+                for (pc, instruction) in basic_block.instructions.iter().rev() {
+                    results.after.insert(*pc, state.clone());
+                    analysis.transfer(*pc, instruction, &mut state);
+                    for edge in &basic_block.exception_handlers {
+                        let handler_pc = self.blocks[edge.handler].start_pc;
+                        if let Some(handler) = results.before(handler_pc) {
+                            let handler = analysis.exception_edge(handler, edge);
+                            analysis.join(&mut state, &handler);
+                        }
+                    }
+                    results.before.insert(*pc, state.clone());
+                }
+                for predecessor in &basic_block.predecessors {
+                    outputs.push((*predecessor, state.clone()));
+                }
+                // The blocks this block handles the exceptions of depend on its first state.
+                if previous.as_ref() != Some(&state) {
+                    for predecessor in &basic_block.exception_predecessors {
+                        if inputs[*predecessor].is_some() && !queued[*predecessor] {
+                            worklist.push_back(*predecessor);
+                            queued[*predecessor] = true;
+                        }
+                    }
+                }
+            }
+
+            for (target, output) in outputs {
+                let changed = match &mut inputs[target] {
+                    Some(input) => {
+                        let previous = input.clone();
+                        analysis.join(input, &output);
+                        *input != previous
+                    }
+                    input @ None => {
+                        *input = Some(output);
+                        true
+                    }
+                };
+                if changed && !queued[target] {
+                    worklist.push_back(target);
+                    queued[target] = true;
+                }
+            }
+        }
+
+        results
+    }
+}
+
+/// How an instruction uses the local variable at `index`.
+struct LocalAccess {
+    index: u16,
+    loads: bool,
+    stores: bool,
+}
+
+fn local_access(instruction: &Instruction) -> Option<LocalAccess> {
+    let index = local_index(instruction)?;
+    let stores = is_store(instruction);
+    // `iinc` loads the variable it increments, and `ret` loads its return address.
+    let increments = matches!(
+        instruction,
+        Instruction::Iinc { .. } | Instruction::Wide(WideInstruction::Iinc { .. })
+    );
+
+    Some(LocalAccess {
+        index,
+        loads: !stores || increments,
+        stores,
+    })
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeSet;
+
+use super::{local_access, Analysis, Direction};
+use crate::instruction::Instruction;
+
+/// Finds the local variables that are live before and after each instruction, i.e. whose value
+/// may be loaded before it is overwritten. A long or double is identified by the lower of its two
+/// indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Liveness;
+
+impl Analysis for Liveness {
+    /// The indices of the live local variables.
+    type State = BTreeSet<u16>;
+
+    const DIRECTION: Direction = Direction::Backward;
+
+    fn boundary_state(&self) -> Self::State {
+        BTreeSet::new()
+    }
+
+    fn join(&self, state: &mut Self::State, other: &Self::State) {
+        state.extend(other);
+    }
+
+    fn transfer(&self, _pc: u32, instruction: &Instruction, state: &mut Self::State) {
+        let Some(access) = local_access(instruction) else {
+            return;
+        };
+
+        if access.stores {
+            state.remove(&access.index);
+        }
+        if access.loads {
+            state.insert(access.index);
+        }
+    }
+}
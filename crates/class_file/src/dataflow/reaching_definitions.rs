@@ -0,0 +1,86 @@
+use std::collections::BTreeSet;
+
+use super::{local_access, Analysis, Direction};
+use crate::{instruction::Instruction, verifier::is_wide_local, MethodDescriptor};
+
+/// An assignment of a local variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Definition {
+    pub index: u16,
+    /// The pc of the store or `iinc`, `None` for `this` and the parameters, which are assigned
+    /// on entry to the method.
+    pub pc: Option<u32>,
+    /// Whether a long or double is assigned, which also takes up the variable at `index + 1`.
+    pub wide: bool,
+}
+impl Definition {
+    /// Whether the definition assigns any of the `slots` variables from `index`.
+    fn overlaps(&self, index: u16, slots: u16) -> bool {
+        let own_slots = if self.wide { 2 } else { 1 };
+        u32::from(self.index) < u32::from(index) + u32::from(slots)
+            && u32::from(index) < u32::from(self.index) + own_slots
+    }
+}
+
+/// Finds the definitions of local variables that reach each instruction, i.e. that may have
+/// assigned the current value of their variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachingDefinitions {
+    parameters: BTreeSet<Definition>,
+}
+impl ReachingDefinitions {
+    /// The analysis of a method with the descriptor, whose `this`, unless it is static, and
+    /// parameters are defined on entry.
+    pub fn new(is_static: bool, descriptor: &MethodDescriptor) -> Self {
+        let mut parameters = BTreeSet::new();
+        let mut index = 0;
+        if !is_static {
+            parameters.insert(Definition {
+                index,
+                pc: None,
+                wide: false,
+            });
+            index += 1;
+        }
+        for parameter in &descriptor.params {
+            parameters.insert(Definition {
+                index,
+                pc: None,
+                wide: parameter.slots() == 2,
+            });
+            index += parameter.slots();
+        }
+
+        Self { parameters }
+    }
+}
+
+impl Analysis for ReachingDefinitions {
+    type State = BTreeSet<Definition>;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn boundary_state(&self) -> Self::State {
+        self.parameters.clone()
+    }
+
+    fn join(&self, state: &mut Self::State, other: &Self::State) {
+        state.extend(other);
+    }
+
+    fn transfer(&self, pc: u32, instruction: &Instruction, state: &mut Self::State) {
+        let Some(access) = local_access(instruction).filter(|access| access.stores) else {
+            return;
+        };
+
+        // A long or double also overwrites the variable at the next index, and a store to either
+        // half of a long or double overwrites it.
+        let wide = is_wide_local(instruction);
+        state.retain(|definition| !definition.overlaps(access.index, if wide { 2 } else { 1 }));
+        state.insert(Definition {
+            index: access.index,
+            pc: Some(pc),
+            wide,
+        });
+    }
+}
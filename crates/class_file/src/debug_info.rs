@@ -0,0 +1,138 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.12
+//
+// Lookups in the debugging information a compiler emits with `-g`: the SourceFile attribute of a
+// class and the LineNumberTable, LocalVariableTable and LocalVariableTypeTable attributes of its
+// Code attributes. A method may have several tables of each kind, which are treated as one.
+
+use crate::{
+    attributes::{AttributeInfo, CodeAttribute, LineNumberTableEntry},
+    constant_pool::CpIndex,
+    ClassFile, Result,
+};
+
+/// A local variable that has a value over a range of pcs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalVariable<'a> {
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// The generic signature of the variable, if its type uses type variables or parameterized
+    /// types.
+    pub signature: Option<&'a str>,
+    /// The index of the variable in the local variable array. A long or double also takes up the
+    /// slot at `index + 1`.
+    pub index: u16,
+    pub start_pc: u16,
+    pub length: u16,
+}
+
+impl CodeAttribute {
+    fn line_numbers(&self) -> impl Iterator<Item = &LineNumberTableEntry> {
+        self.attributes.iter().flat_map(|a| match &a.info {
+            AttributeInfo::LineNumberTable(lines) => lines.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// The source line of the instruction at `pc`, `None` if there is no line number
+    /// information for it.
+    pub fn line_for_pc(&self, pc: u32) -> Option<u16> {
+        if pc as usize >= self.code.len() {
+            return None;
+        }
+
+        // A line starts at its start_pc and lasts until the start of the next line in the code.
+        self.line_numbers()
+            .filter(|entry| entry.start_pc as u32 <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
+
+    /// The pcs, in increasing order, at which the code of the source line `line` starts. A line
+    /// can start at several pcs, e.g. the condition of a loop that is compiled after its body.
+    pub fn pcs_for_line(&self, line: u16) -> Vec<u32> {
+        let mut pcs = self
+            .line_numbers()
+            .filter(|entry| entry.line_number == line)
+            .map(|entry| entry.start_pc as u32)
+            .collect::<Vec<_>>();
+        pcs.sort_unstable();
+        pcs.dedup();
+        pcs
+    }
+}
+
+impl ClassFile {
+    /// The name of the source file the class was compiled from, without its directory.
+    pub fn source_file(&self) -> Result<Option<&str>> {
+        self.attributes
+            .find_map(|info| match info {
+                AttributeInfo::SourceFile { sourcefile_index } => Some(*sourcefile_index),
+                _ => None,
+            })
+            .map(|index| self.constant_pool.utf8(CpIndex::new(index)))
+            .transpose()
+    }
+
+    /// The local variables of `code` that have a value at `pc`, ordered by index.
+    pub fn locals_live_at<'a>(
+        &'a self,
+        code: &CodeAttribute,
+        pc: u32,
+    ) -> Result<Vec<LocalVariable<'a>>> {
+        let live = |start_pc: u16, length: u16| {
+            start_pc as u32 <= pc && pc < start_pc as u32 + length as u32
+        };
+
+        let mut locals = vec![];
+        for attribute in &code.attributes {
+            let AttributeInfo::LocalVariableTable(variables) = &attribute.info else {
+                continue;
+            };
+            for variable in variables {
+                if !live(variable.start_pc, variable.length) {
+                    continue;
+                }
+
+                locals.push(LocalVariable {
+                    name: self.constant_pool.utf8(CpIndex::new(variable.name_index))?,
+                    descriptor: self
+                        .constant_pool
+                        .utf8(CpIndex::new(variable.descriptor_index))?,
+                    signature: None,
+                    index: variable.index,
+                    start_pc: variable.start_pc,
+                    length: variable.length,
+                });
+            }
+        }
+
+        // An entry of the LocalVariableTypeTable describes the same variable as the entry of the
+        // LocalVariableTable with the same start_pc, length, name and index.
+        for attribute in &code.attributes {
+            let AttributeInfo::LocalVariableTypeTable(variables) = &attribute.info else {
+                continue;
+            };
+            for variable in variables {
+                if !live(variable.start_pc, variable.length) {
+                    continue;
+                }
+
+                let name = self.constant_pool.utf8(CpIndex::new(variable.name_index))?;
+                if let Some(local) = locals.iter_mut().find(|local| {
+                    local.start_pc == variable.start_pc
+                        && local.length == variable.length
+                        && local.index == variable.index
+                        && local.name == name
+                }) {
+                    local.signature = Some(
+                        self.constant_pool
+                            .utf8(CpIndex::new(variable.signature_index))?,
+                    );
+                }
+            }
+        }
+
+        locals.sort_by_key(|local| local.index);
+        Ok(locals)
+    }
+}
@@ -0,0 +1,401 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.3
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    class_file::{FieldInfo, MethodInfo},
+    ClassFile, ClassFileError, Result,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+}
+impl BaseType {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'B' => Some(BaseType::Byte),
+            'C' => Some(BaseType::Char),
+            'D' => Some(BaseType::Double),
+            'F' => Some(BaseType::Float),
+            'I' => Some(BaseType::Int),
+            'J' => Some(BaseType::Long),
+            'S' => Some(BaseType::Short),
+            'Z' => Some(BaseType::Boolean),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z',
+        }
+    }
+
+    /// The name of the type in the Java language, e.g. `int`.
+    pub fn java_name(&self) -> &'static str {
+        match self {
+            BaseType::Byte => "byte",
+            BaseType::Char => "char",
+            BaseType::Double => "double",
+            BaseType::Float => "float",
+            BaseType::Int => "int",
+            BaseType::Long => "long",
+            BaseType::Short => "short",
+            BaseType::Boolean => "boolean",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Base(BaseType),
+    /// A class or interface, by its binary name in internal form, e.g. `java/lang/Object`.
+    Object(String),
+    /// An array type. The element type is never itself an array.
+    Array {
+        dimensions: u8,
+        element_type: Box<FieldType>,
+    },
+}
+impl FieldType {
+    pub fn parse(descriptor: &str) -> Result<Self> {
+        let mut r = DescriptorReader::new(descriptor);
+        let field_type = r.read_field_type()?;
+        r.expect_end()?;
+        Ok(field_type)
+    }
+
+    /// The number of local variable or operand stack slots a value of this type occupies.
+    pub fn slots(&self) -> u16 {
+        match self {
+            FieldType::Base(BaseType::Long) | FieldType::Base(BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        !matches!(self, FieldType::Base(_))
+    }
+}
+impl FromStr for FieldType {
+    type Err = ClassFileError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        FieldType::parse(s)
+    }
+}
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Base(b) => write!(f, "{}", b.as_char()),
+            FieldType::Object(class_name) => write!(f, "L{};", class_name),
+            FieldType::Array {
+                dimensions,
+                element_type,
+            } => {
+                for _ in 0..*dimensions {
+                    f.write_str("[")?;
+                }
+                write!(f, "{}", element_type)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub params: Vec<FieldType>,
+    /// `None` if the method returns `void`.
+    pub return_type: Option<FieldType>,
+}
+impl MethodDescriptor {
+    pub fn parse(descriptor: &str) -> Result<Self> {
+        let mut r = DescriptorReader::new(descriptor);
+        r.expect('(')?;
+        let mut params = vec![];
+        while r.peek() != Some(')') {
+            params.push(r.read_field_type()?);
+        }
+        r.expect(')')?;
+        let return_type = if r.peek() == Some('V') {
+            r.next();
+            None
+        } else {
+            Some(r.read_field_type()?)
+        };
+        r.expect_end()?;
+
+        let method_descriptor = MethodDescriptor {
+            params,
+            return_type,
+        };
+
+        // A method descriptor is valid only if it represents method parameters with a total
+        // length of 255 or less, where that length includes the contribution for this in the case
+        // of instance or interface method invocations.
+        if method_descriptor.parameter_slots() > 255 {
+            return Err(ClassFileError::InvalidDescriptor(descriptor.to_owned()));
+        }
+
+        Ok(method_descriptor)
+    }
+
+    /// The number of local variable slots taken by the parameters, not counting `this`. At most
+    /// 255 for a parsed descriptor, and saturating at `u16::MAX` for others.
+    pub fn parameter_slots(&self) -> u16 {
+        self.params
+            .iter()
+            .map(FieldType::slots)
+            .fold(0, u16::saturating_add)
+    }
+
+    /// The number of operand stack slots taken by the return value.
+    pub fn return_slots(&self) -> u16 {
+        self.return_type.as_ref().map_or(0, FieldType::slots)
+    }
+}
+impl FromStr for MethodDescriptor {
+    type Err = ClassFileError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        MethodDescriptor::parse(s)
+    }
+}
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(")?;
+        for param in &self.params {
+            write!(f, "{}", param)?;
+        }
+        f.write_str(")")?;
+        match &self.return_type {
+            Some(return_type) => write!(f, "{}", return_type),
+            None => f.write_str("V"),
+        }
+    }
+}
+
+impl ClassFile {
+    pub fn field_type(&self, field: &FieldInfo) -> Result<FieldType> {
+        FieldType::parse(self.field_descriptor(field)?)
+    }
+
+    pub fn method_type(&self, method: &MethodInfo) -> Result<MethodDescriptor> {
+        MethodDescriptor::parse(self.method_descriptor(method)?)
+    }
+}
+
+struct DescriptorReader<'a> {
+    descriptor: &'a str,
+    rest: &'a str,
+}
+impl<'a> DescriptorReader<'a> {
+    fn new(descriptor: &'a str) -> Self {
+        Self {
+            descriptor,
+            rest: descriptor,
+        }
+    }
+
+    fn error(&self) -> ClassFileError {
+        ClassFileError::InvalidDescriptor(self.descriptor.to_owned())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn read_field_type(&mut self) -> Result<FieldType> {
+        let mut dimensions = 0usize;
+        while self.peek() == Some('[') {
+            self.next();
+            dimensions += 1;
+        }
+
+        let element_type = match self.next() {
+            Some('L') => {
+                let end = self.rest.find(';').ok_or_else(|| self.error())?;
+                let class_name = &self.rest[..end];
+                if !is_binary_name(class_name) {
+                    return Err(self.error());
+                }
+                self.rest = &self.rest[end + 1..];
+                FieldType::Object(class_name.to_owned())
+            }
+            Some(c) => FieldType::Base(BaseType::from_char(c).ok_or_else(|| self.error())?),
+            None => return Err(self.error()),
+        };
+
+        if dimensions == 0 {
+            return Ok(element_type);
+        }
+
+        // An array type descriptor is valid only if it represents 255 or fewer dimensions.
+        let dimensions = u8::try_from(dimensions).map_err(|_| self.error())?;
+        Ok(FieldType::Array {
+            dimensions,
+            element_type: Box::new(element_type),
+        })
+    }
+}
+
+/// Whether `name` is a binary class or interface name in internal form, e.g. `java/lang/Thread`.
+pub(crate) fn is_binary_name(name: &str) -> bool {
+    name.split('/').all(is_unqualified_name)
+}
+
+/// Whether `name` is an unqualified name as used for fields, locals and formal parameters.
+pub(crate) fn is_unqualified_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_base_types() {
+        assert_eq!(
+            FieldType::parse("J").unwrap(),
+            FieldType::Base(BaseType::Long)
+        );
+    }
+
+    #[test]
+    fn it_should_parse_object_types() {
+        assert_eq!(
+            FieldType::parse("Ljava/lang/Object;").unwrap(),
+            FieldType::Object("java/lang/Object".into())
+        );
+    }
+
+    #[test]
+    fn it_should_parse_array_types() {
+        assert_eq!(
+            FieldType::parse("[[Ljava/lang/String;").unwrap(),
+            FieldType::Array {
+                dimensions: 2,
+                element_type: Box::new(FieldType::Object("java/lang/String".into())),
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_parse_method_descriptors() {
+        assert_eq!(
+            MethodDescriptor::parse("(IDLjava/lang/Thread;)Ljava/lang/Object;").unwrap(),
+            MethodDescriptor {
+                params: vec![
+                    FieldType::Base(BaseType::Int),
+                    FieldType::Base(BaseType::Double),
+                    FieldType::Object("java/lang/Thread".into()),
+                ],
+                return_type: Some(FieldType::Object("java/lang/Object".into())),
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_count_parameter_slots() {
+        let descriptor = MethodDescriptor::parse("(IJ[DD)V").unwrap();
+
+        assert_eq!(6, descriptor.parameter_slots());
+        assert_eq!(0, descriptor.return_slots());
+    }
+
+    #[test]
+    fn it_should_reject_too_many_parameter_slots() {
+        for descriptor in [
+            format!("({})V", "I".repeat(256)),
+            format!("({})V", "J".repeat(32768)),
+        ] {
+            assert!(matches!(
+                MethodDescriptor::parse(&descriptor),
+                Err(ClassFileError::InvalidDescriptor(_))
+            ));
+        }
+        assert!(MethodDescriptor::parse(&format!("({})V", "I".repeat(255))).is_ok());
+    }
+
+    #[test]
+    fn it_should_display_descriptors() {
+        for descriptor in ["()V", "(I[[JLjava/lang/String;)[Ljava/lang/Object;"] {
+            assert_eq!(
+                descriptor,
+                MethodDescriptor::parse(descriptor).unwrap().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_fail_on_invalid_descriptors() {
+        for descriptor in [
+            "",
+            "V",
+            "Q",
+            "II",
+            "L;",
+            "Ljava/lang/Object",
+            "La.b;",
+            "[",
+            "La//b;",
+        ] {
+            assert!(FieldType::parse(descriptor).is_err(), "{}", descriptor);
+        }
+        for descriptor in ["", "I", "()", "(V)V", "()VV", "(I"] {
+            assert!(
+                MethodDescriptor::parse(descriptor).is_err(),
+                "{}",
+                descriptor
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_fail_on_too_many_dimensions() {
+        assert!(FieldType::parse(&format!("{}I", "[".repeat(255))).is_ok());
+        assert!(FieldType::parse(&format!("{}I", "[".repeat(256))).is_err());
+    }
+
+    #[test]
+    fn it_should_fail_on_too_many_parameter_slots() {
+        assert!(MethodDescriptor::parse(&format!("({})V", "I".repeat(255))).is_ok());
+        assert!(MethodDescriptor::parse(&format!("({})V", "J".repeat(128))).is_err());
+    }
+}
@@ -0,0 +1,1089 @@
+// Renders a class file in roughly the same format as `javap -c -v -p`.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::{
+    attributes::{
+        Annotation, AttributeInfo, Attributes, CodeAttribute, ElementValue, StackMapFrame,
+        TargetInfo, TypeAnnotation, VerificationTypeInfo,
+    },
+    class_file::{FieldInfo, MethodInfo},
+    constant_pool::{CpInfo, DynamicInfo, InvokeDynamicInfo},
+    instruction::{Instruction, WideInstruction},
+    ClassFile,
+};
+
+pub struct Disassembler<'a> {
+    class_file: &'a ClassFile,
+}
+impl<'a> Disassembler<'a> {
+    pub fn new(class_file: &'a ClassFile) -> Self {
+        Self { class_file }
+    }
+
+    fn cp_info(&self, index: u16) -> Option<&'a CpInfo> {
+        self.class_file.constant_pool.get(index)
+    }
+
+    fn utf8(&self, index: impl Into<u16>) -> String {
+        let index = index.into();
+        match self.cp_info(index) {
+            Some(CpInfo::Utf8(s)) => s.clone(),
+            _ => format!("<invalid #{}>", index),
+        }
+    }
+
+    fn class_name(&self, index: impl Into<u16>) -> String {
+        let index = index.into();
+        match self.cp_info(index) {
+            Some(CpInfo::Class(c)) => self.utf8(c.name_index),
+            _ => format!("<invalid #{}>", index),
+        }
+    }
+
+    fn name_and_type(&self, index: impl Into<u16>) -> String {
+        let index = index.into();
+        match self.cp_info(index) {
+            Some(CpInfo::NameAndType(nat)) => {
+                let name = self.utf8(nat.name_index);
+                let name = if name.starts_with('<') {
+                    format!("\"{}\"", name)
+                } else {
+                    name
+                };
+                format!("{}:{}", name, self.utf8(nat.descriptor_index))
+            }
+            _ => format!("<invalid #{}>", index),
+        }
+    }
+
+    /// The class, name and descriptor of a field or method reference.
+    fn member_ref(&self, index: impl Into<u16>) -> String {
+        let index = index.into();
+        match self.cp_info(index) {
+            Some(CpInfo::FieldRef(r) | CpInfo::MethodRef(r) | CpInfo::InterfaceMethodRef(r)) => {
+                format!(
+                    "{}.{}",
+                    self.class_name(r.class_index),
+                    self.name_and_type(r.name_and_type_index)
+                )
+            }
+            _ => format!("<invalid #{}>", index),
+        }
+    }
+
+    /// The symbolic value of a constant pool entry, as shown in the comments of `javap`.
+    fn resolve(&self, index: impl Into<u16>) -> String {
+        let index = index.into();
+        let Some(cp_info) = self.cp_info(index) else {
+            return format!("<invalid #{}>", index);
+        };
+
+        match cp_info {
+            CpInfo::Utf8(s) => s.clone(),
+            CpInfo::RawUtf8(bytes) => raw_utf8(bytes),
+            CpInfo::Integer(i) => i.to_string(),
+            CpInfo::Float(f) => format!("{}f", java_float(*f)),
+            CpInfo::Long(l) => format!("{}l", l),
+            CpInfo::Double(d) => format!("{}d", java_float(*d)),
+            CpInfo::Class(c) => self.utf8(c.name_index),
+            CpInfo::Module(m) => self.utf8(m.name_index),
+            CpInfo::Package(p) => self.utf8(p.name_index),
+            CpInfo::String { string_index } => self.utf8(*string_index),
+            CpInfo::FieldRef(_) | CpInfo::MethodRef(_) | CpInfo::InterfaceMethodRef(_) => {
+                self.member_ref(index)
+            }
+            CpInfo::NameAndType(_) => self.name_and_type(index),
+            CpInfo::MethodHandle(h) => {
+                format!(
+                    "{} {}",
+                    h.reference_kind,
+                    self.member_ref(h.reference_index)
+                )
+            }
+            CpInfo::MethodType(t) => self.utf8(t.descriptor_index),
+            CpInfo::InvokeDynamic(InvokeDynamicInfo {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            })
+            | CpInfo::Dynamic(DynamicInfo {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            }) => format!(
+                "#{}:{}",
+                bootstrap_method_attr_index,
+                self.name_and_type(*name_and_type_index)
+            ),
+            CpInfo::Unusable => format!("<unusable #{}>", index),
+        }
+    }
+
+    /// The resolved value prefixed with the kind of the entry, e.g. `Method java/lang/Object."<init>":()V`.
+    fn comment(&self, index: u16) -> String {
+        let kind = match self.cp_info(index) {
+            Some(CpInfo::FieldRef(_)) => "Field",
+            Some(CpInfo::MethodRef(_)) => "Method",
+            Some(CpInfo::InterfaceMethodRef(_)) => "InterfaceMethod",
+            Some(CpInfo::Class(_)) => "class",
+            Some(CpInfo::String { .. }) => "String",
+            Some(CpInfo::Integer(_)) => "int",
+            Some(CpInfo::Float(_)) => "float",
+            Some(CpInfo::Long(_)) => "long",
+            Some(CpInfo::Double(_)) => "double",
+            Some(CpInfo::Dynamic(_)) => "Dynamic",
+            Some(CpInfo::Module(_)) => "Module",
+            Some(CpInfo::Package(_)) => "Package",
+            Some(CpInfo::InvokeDynamic(_)) => "InvokeDynamic",
+            Some(CpInfo::MethodHandle(_)) => "MethodHandle",
+            Some(CpInfo::MethodType(_)) => "MethodType",
+            Some(CpInfo::NameAndType(_)) => "NameAndType",
+            Some(CpInfo::Utf8(_)) | Some(CpInfo::RawUtf8(_)) | Some(CpInfo::Unusable) | None => {
+                return self.resolve(index);
+            }
+        };
+        format!("{} {}", kind, self.resolve(index))
+    }
+
+    fn write_constant_pool(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Constant pool:")?;
+        for (i, cp_info) in self.class_file.constant_pool.into_iter().enumerate() {
+            let index = i as u16 + 1;
+            let (kind, operands) = match cp_info {
+                CpInfo::Utf8(s) => ("Utf8", s.clone()),
+                CpInfo::RawUtf8(bytes) => ("Utf8", raw_utf8(bytes)),
+                CpInfo::Integer(i) => ("Integer", i.to_string()),
+                CpInfo::Float(f) => ("Float", format!("{}f", java_float(*f))),
+                CpInfo::Long(l) => ("Long", format!("{}l", l)),
+                CpInfo::Double(d) => ("Double", format!("{}d", java_float(*d))),
+                CpInfo::Class(c) => ("Class", format!("#{}", c.name_index)),
+                CpInfo::Module(m) => ("Module", format!("#{}", m.name_index)),
+                CpInfo::Package(p) => ("Package", format!("#{}", p.name_index)),
+                CpInfo::String { string_index } => ("String", format!("#{}", string_index)),
+                CpInfo::FieldRef(r) => (
+                    "Fieldref",
+                    format!("#{}.#{}", r.class_index, r.name_and_type_index),
+                ),
+                CpInfo::MethodRef(r) => (
+                    "Methodref",
+                    format!("#{}.#{}", r.class_index, r.name_and_type_index),
+                ),
+                CpInfo::InterfaceMethodRef(r) => (
+                    "InterfaceMethodref",
+                    format!("#{}.#{}", r.class_index, r.name_and_type_index),
+                ),
+                CpInfo::NameAndType(nat) => (
+                    "NameAndType",
+                    format!("#{}:#{}", nat.name_index, nat.descriptor_index),
+                ),
+                CpInfo::MethodHandle(h) => (
+                    "MethodHandle",
+                    format!("{}:#{}", h.reference_kind as u8, h.reference_index),
+                ),
+                CpInfo::MethodType(t) => ("MethodType", format!("#{}", t.descriptor_index)),
+                CpInfo::InvokeDynamic(i) => (
+                    "InvokeDynamic",
+                    format!(
+                        "#{}:#{}",
+                        i.bootstrap_method_attr_index, i.name_and_type_index
+                    ),
+                ),
+                CpInfo::Dynamic(d) => (
+                    "Dynamic",
+                    format!(
+                        "#{}:#{}",
+                        d.bootstrap_method_attr_index, d.name_and_type_index
+                    ),
+                ),
+                CpInfo::Unusable => continue,
+            };
+
+            let number = format!("#{}", index);
+            match cp_info {
+                CpInfo::Utf8(_)
+                | CpInfo::RawUtf8(_)
+                | CpInfo::Integer(_)
+                | CpInfo::Float(_)
+                | CpInfo::Long(_)
+                | CpInfo::Double(_) => writeln!(f, "{:>5} = {:<18} {}", number, kind, operands)?,
+                _ => writeln!(
+                    f,
+                    "{:>5} = {:<18} {:<14} // {}",
+                    number,
+                    kind,
+                    operands,
+                    self.resolve(index)
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_field(&self, f: &mut Formatter<'_>, field: &FieldInfo) -> fmt::Result {
+        writeln!(f, "  {}", self.utf8(field.name_index))?;
+        writeln!(f, "    descriptor: {}", self.utf8(field.descriptor_index))?;
+        write_flags(
+            f,
+            4,
+            field.access_flags.bits(),
+            field.access_flags.iter_names(),
+        )?;
+        self.write_attributes(f, 4, &field.attributes)
+    }
+
+    fn write_method(&self, f: &mut Formatter<'_>, method: &MethodInfo) -> fmt::Result {
+        writeln!(f, "  {}", self.utf8(method.name_index))?;
+        writeln!(f, "    descriptor: {}", self.utf8(method.descriptor_index))?;
+        write_flags(
+            f,
+            4,
+            method.access_flags.bits(),
+            method.access_flags.iter_names(),
+        )?;
+        self.write_attributes(f, 4, &method.attributes)
+    }
+
+    fn write_attributes(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: usize,
+        attributes: &Attributes,
+    ) -> fmt::Result {
+        for attribute in attributes {
+            let name = self.utf8(attribute.attribute_name_index);
+            self.write_attribute(f, indent, &name, &attribute.info)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_attribute(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: usize,
+        name: &str,
+        info: &AttributeInfo,
+    ) -> fmt::Result {
+        let pad = "";
+        let inner = indent + 2;
+        match info {
+            AttributeInfo::ConstantValue {
+                constantvalue_index,
+            } => writeln!(
+                f,
+                "{pad:indent$}{name}: {}",
+                self.comment(*constantvalue_index)
+            ),
+            AttributeInfo::Code(code) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                self.write_code(f, inner, code)
+            }
+            AttributeInfo::StackMapTable(frames) => {
+                writeln!(
+                    f,
+                    "{pad:indent$}{name}: number_of_entries = {}",
+                    frames.len()
+                )?;
+                for frame in frames {
+                    self.write_stack_map_frame(f, inner, frame)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Exceptions(exceptions) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                let classes = exceptions
+                    .iter()
+                    .map(|i| self.class_name(*i))
+                    .collect::<Vec<_>>();
+                writeln!(f, "{pad:inner$}throws {}", classes.join(", "))
+            }
+            AttributeInfo::InnerClasses(classes) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for c in classes {
+                    let inner_name = match c.inner_name_index {
+                        0 => String::from("<anonymous>"),
+                        i => self.utf8(i),
+                    };
+                    write!(
+                        f,
+                        "{pad:inner$}{} (0x{:04x}) class {}",
+                        inner_name,
+                        c.inner_class_access_flags.bits(),
+                        self.class_name(c.inner_class_info_index)
+                    )?;
+                    if c.outer_class_info_index != 0 {
+                        write!(f, " of class {}", self.class_name(c.outer_class_info_index))?;
+                    }
+                    writeln!(f)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::EnclosingMethod(e) => {
+                write!(
+                    f,
+                    "{pad:indent$}{name}: #{}.#{} // {}",
+                    e.class_index,
+                    e.method_index,
+                    self.class_name(e.class_index)
+                )?;
+                if e.method_index != 0 {
+                    write!(f, ".{}", self.name_and_type(e.method_index))?;
+                }
+                writeln!(f)
+            }
+            AttributeInfo::Synthetic | AttributeInfo::Deprecated => {
+                writeln!(f, "{pad:indent$}{name}: true")
+            }
+            AttributeInfo::Signature { signature_index } => writeln!(
+                f,
+                "{pad:indent$}{name}: #{:<27}// {}",
+                signature_index,
+                self.utf8(*signature_index)
+            ),
+            AttributeInfo::SourceFile { sourcefile_index } => writeln!(
+                f,
+                "{pad:indent$}{name}: \"{}\"",
+                self.utf8(*sourcefile_index)
+            ),
+            AttributeInfo::SourceDebugExtension(bytes) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for line in String::from_utf8_lossy(bytes).lines() {
+                    writeln!(f, "{pad:inner$}{}", line)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::LineNumberTable(lines) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for l in lines {
+                    writeln!(f, "{pad:inner$}line {}: {}", l.line_number, l.start_pc)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::LocalVariableTable(locals) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                writeln!(f, "{pad:inner$}Start  Length  Slot  Name   Signature")?;
+                for l in locals {
+                    writeln!(
+                        f,
+                        "{pad:inner$}{:>5}  {:>6}  {:>4} {:>5}   {}",
+                        l.start_pc,
+                        l.length,
+                        l.index,
+                        self.utf8(l.name_index),
+                        self.utf8(l.descriptor_index)
+                    )?;
+                }
+                Ok(())
+            }
+            AttributeInfo::LocalVariableTypeTable(locals) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                writeln!(f, "{pad:inner$}Start  Length  Slot  Name   Signature")?;
+                for l in locals {
+                    writeln!(
+                        f,
+                        "{pad:inner$}{:>5}  {:>6}  {:>4} {:>5}   {}",
+                        l.start_pc,
+                        l.length,
+                        l.index,
+                        self.utf8(l.name_index),
+                        self.utf8(l.signature_index)
+                    )?;
+                }
+                Ok(())
+            }
+            AttributeInfo::RuntimeVisibleAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleAnnotations(annotations) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for (i, a) in annotations.iter().enumerate() {
+                    writeln!(f, "{pad:inner$}{}: {}", i, self.annotation(a))?;
+                }
+                Ok(())
+            }
+            AttributeInfo::RuntimeVisibleParameterAnnotations(parameters)
+            | AttributeInfo::RuntimeInvisibleParameterAnnotations(parameters) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for (i, annotations) in parameters.iter().enumerate() {
+                    writeln!(f, "{pad:inner$}parameter {}:", i)?;
+                    for (j, a) in annotations.iter().enumerate() {
+                        writeln!(f, "{pad:inner$}  {}: {}", j, self.annotation(a))?;
+                    }
+                }
+                Ok(())
+            }
+            AttributeInfo::RuntimeVisibleTypeAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleTypeAnnotations(annotations) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for (i, a) in annotations.iter().enumerate() {
+                    writeln!(f, "{pad:inner$}{}: {}", i, self.type_annotation(a))?;
+                }
+                Ok(())
+            }
+            AttributeInfo::AnnotationDefault(value) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                writeln!(
+                    f,
+                    "{pad:inner$}default_value: {}",
+                    self.element_value(value)
+                )
+            }
+            AttributeInfo::BootstrapMethods(methods) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for (i, m) in methods.iter().enumerate() {
+                    writeln!(
+                        f,
+                        "{pad:inner$}{}: #{} {}",
+                        i,
+                        m.bootstrap_method_ref,
+                        self.resolve(m.bootstrap_method_ref)
+                    )?;
+                    writeln!(f, "{pad:inner$}  Method arguments:")?;
+                    for a in &m.bootstrap_arguments {
+                        writeln!(f, "{pad:inner$}    #{} {}", a, self.resolve(*a))?;
+                    }
+                }
+                Ok(())
+            }
+            AttributeInfo::MethodParameters(parameters) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                writeln!(f, "{pad:inner$}Name                           Flags")?;
+                for p in parameters {
+                    let parameter_name = match p.name_index {
+                        0 => String::from("<no name>"),
+                        i => self.utf8(i),
+                    };
+                    writeln!(
+                        f,
+                        "{pad:inner$}{:<30} 0x{:04x}",
+                        parameter_name,
+                        p.access_flags.bits()
+                    )?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Module(module) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                writeln!(
+                    f,
+                    "{pad:inner$}module {} (0x{:04x})",
+                    self.resolve(module.module_name_index),
+                    module.module_flags.bits()
+                )?;
+                if module.module_version_index != 0 {
+                    writeln!(
+                        f,
+                        "{pad:inner$}version {}",
+                        self.utf8(module.module_version_index)
+                    )?;
+                }
+                for r in &module.requires {
+                    writeln!(
+                        f,
+                        "{pad:inner$}requires {} (0x{:04x})",
+                        self.resolve(r.requires_index),
+                        r.requires_flags.bits()
+                    )?;
+                }
+                for e in &module.exports {
+                    writeln!(
+                        f,
+                        "{pad:inner$}exports {} (0x{:04x}){}",
+                        self.resolve(e.exports_index),
+                        e.exports_flags.bits(),
+                        self.targets(&e.exports_to_index)
+                    )?;
+                }
+                for o in &module.opens {
+                    writeln!(
+                        f,
+                        "{pad:inner$}opens {} (0x{:04x}){}",
+                        self.resolve(o.opens_index),
+                        o.opens_flags.bits(),
+                        self.targets(&o.opens_to_index)
+                    )?;
+                }
+                for u in &module.uses_index {
+                    writeln!(f, "{pad:inner$}uses {}", self.class_name(*u))?;
+                }
+                for p in &module.provides {
+                    let with = p
+                        .provides_with_index
+                        .iter()
+                        .map(|i| self.class_name(*i))
+                        .collect::<Vec<_>>();
+                    writeln!(
+                        f,
+                        "{pad:inner$}provides {} with {}",
+                        self.class_name(p.provides_index),
+                        with.join(", ")
+                    )?;
+                }
+                Ok(())
+            }
+            AttributeInfo::ModulePackages(packages) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for p in packages {
+                    writeln!(f, "{pad:inner$}{}", self.resolve(*p))?;
+                }
+                Ok(())
+            }
+            AttributeInfo::ModuleMainClass { main_class_index } => writeln!(
+                f,
+                "{pad:indent$}{name}: {}",
+                self.class_name(*main_class_index)
+            ),
+            AttributeInfo::NestHost { host_class_index } => writeln!(
+                f,
+                "{pad:indent$}{name}: class {}",
+                self.class_name(*host_class_index)
+            ),
+            AttributeInfo::NestMembers(classes) | AttributeInfo::PermittedSubclasses(classes) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for c in classes {
+                    writeln!(f, "{pad:inner$}{}", self.class_name(*c))?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Record(components) => {
+                writeln!(f, "{pad:indent$}{name}:")?;
+                for c in components {
+                    writeln!(
+                        f,
+                        "{pad:inner$}{} {}",
+                        self.utf8(c.descriptor_index),
+                        self.utf8(c.name_index)
+                    )?;
+                    self.write_attributes(f, inner + 2, &c.attributes)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Unknown(bytes) => writeln!(
+                f,
+                "{pad:indent$}{name}: length = 0x{:X} (unknown attribute)",
+                bytes.len()
+            ),
+        }
+    }
+
+    fn write_code(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: usize,
+        code: &CodeAttribute,
+    ) -> fmt::Result {
+        let pad = "";
+        writeln!(
+            f,
+            "{pad:indent$}stack={}, locals={}",
+            code.max_stack, code.max_locals
+        )?;
+        for instruction in code.instructions() {
+            match instruction {
+                Ok((pc, instruction)) => {
+                    writeln!(
+                        f,
+                        "{pad:indent$}{:>5}: {}",
+                        pc,
+                        self.instruction(pc, &instruction, indent)
+                    )?;
+                }
+                Err(e) => writeln!(f, "{pad:indent$}<{}>", e)?,
+            }
+        }
+
+        if !code.exception_table.is_empty() {
+            writeln!(f, "{pad:indent$}Exception table:")?;
+            writeln!(f, "{pad:indent$}   from    to  target type")?;
+            for e in &code.exception_table {
+                let catch_type = match e.catch_type {
+                    0 => String::from("any"),
+                    i => format!("Class {}", self.class_name(i)),
+                };
+                writeln!(
+                    f,
+                    "{pad:indent$}  {:>5} {:>5} {:>5}   {}",
+                    e.start_pc, e.end_pc, e.handler_pc, catch_type
+                )?;
+            }
+        }
+
+        self.write_attributes(f, indent, &code.attributes)
+    }
+
+    fn instruction(&self, pc: u32, instruction: &Instruction, indent: usize) -> String {
+        let mnemonic = instruction.mnemonic();
+        let target = |offset: i32| pc as i64 + offset as i64;
+        let with_comment = |operands: String, index: u16| {
+            format!(
+                "{:<13} {:<18} // {}",
+                mnemonic,
+                operands,
+                self.comment(index)
+            )
+        };
+
+        match instruction {
+            Instruction::Bipush(n) => format!("{:<13} {}", mnemonic, n),
+            Instruction::Sipush(n) => format!("{:<13} {}", mnemonic, n),
+            Instruction::Ldc(i) => with_comment(format!("#{}", i), *i as u16),
+            Instruction::LdcW(i)
+            | Instruction::Ldc2W(i)
+            | Instruction::Getstatic(i)
+            | Instruction::Putstatic(i)
+            | Instruction::Getfield(i)
+            | Instruction::Putfield(i)
+            | Instruction::Invokevirtual(i)
+            | Instruction::Invokespecial(i)
+            | Instruction::Invokestatic(i)
+            | Instruction::New(i)
+            | Instruction::Anewarray(i)
+            | Instruction::Checkcast(i)
+            | Instruction::Instanceof(i) => with_comment(format!("#{}", i), *i),
+            Instruction::Invokeinterface { index, count } => {
+                with_comment(format!("#{},  {}", index, count), *index)
+            }
+            Instruction::Invokedynamic(i) => with_comment(format!("#{},  0", i), *i),
+            Instruction::Multianewarray { index, dimensions } => {
+                with_comment(format!("#{},  {}", index, dimensions), *index)
+            }
+            Instruction::Iload(i)
+            | Instruction::Lload(i)
+            | Instruction::Fload(i)
+            | Instruction::Dload(i)
+            | Instruction::Aload(i)
+            | Instruction::Istore(i)
+            | Instruction::Lstore(i)
+            | Instruction::Fstore(i)
+            | Instruction::Dstore(i)
+            | Instruction::Astore(i)
+            | Instruction::Ret(i) => format!("{:<13} {}", mnemonic, i),
+            Instruction::Iinc { index, value } => format!("{:<13} {}, {}", mnemonic, index, value),
+            Instruction::Ifeq(o)
+            | Instruction::Ifne(o)
+            | Instruction::Iflt(o)
+            | Instruction::Ifge(o)
+            | Instruction::Ifgt(o)
+            | Instruction::Ifle(o)
+            | Instruction::IfIcmpeq(o)
+            | Instruction::IfIcmpne(o)
+            | Instruction::IfIcmplt(o)
+            | Instruction::IfIcmpge(o)
+            | Instruction::IfIcmpgt(o)
+            | Instruction::IfIcmple(o)
+            | Instruction::IfAcmpeq(o)
+            | Instruction::IfAcmpne(o)
+            | Instruction::Goto(o)
+            | Instruction::Jsr(o)
+            | Instruction::Ifnull(o)
+            | Instruction::Ifnonnull(o) => format!("{:<13} {}", mnemonic, target(*o as i32)),
+            Instruction::GotoW(o) | Instruction::JsrW(o) => {
+                format!("{:<13} {}", mnemonic, target(*o))
+            }
+            Instruction::Newarray(t) => {
+                format!("{:<13} {}", mnemonic, format!("{:?}", t).to_lowercase())
+            }
+            Instruction::Wide(w) => match w {
+                WideInstruction::Iinc { index, value } => {
+                    format!("{:<13} {} {}, {}", mnemonic, w.mnemonic(), index, value)
+                }
+                WideInstruction::Iload(i)
+                | WideInstruction::Lload(i)
+                | WideInstruction::Fload(i)
+                | WideInstruction::Dload(i)
+                | WideInstruction::Aload(i)
+                | WideInstruction::Istore(i)
+                | WideInstruction::Lstore(i)
+                | WideInstruction::Fstore(i)
+                | WideInstruction::Dstore(i)
+                | WideInstruction::Astore(i)
+                | WideInstruction::Ret(i) => {
+                    format!("{:<13} {} {}", mnemonic, w.mnemonic(), i)
+                }
+            },
+            Instruction::Tableswitch {
+                default,
+                low,
+                offsets,
+            } => {
+                let mut s = format!(
+                    "{:<13} {{ // {} to {}",
+                    mnemonic,
+                    low,
+                    *low as i64 + offsets.len() as i64 - 1
+                );
+                for (i, offset) in offsets.iter().enumerate() {
+                    let _ = write!(
+                        s,
+                        "\n{:indent$}{:>24}: {}",
+                        "",
+                        *low as i64 + i as i64,
+                        target(*offset)
+                    );
+                }
+                let _ = write!(
+                    s,
+                    "\n{:indent$}{:>24}: {}\n{:indent$}     }}",
+                    "",
+                    "default",
+                    target(*default),
+                    ""
+                );
+                s
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                let mut s = format!("{:<13} {{ // {}", mnemonic, pairs.len());
+                for (key, offset) in pairs {
+                    let _ = write!(s, "\n{:indent$}{:>24}: {}", "", key, target(*offset));
+                }
+                let _ = write!(
+                    s,
+                    "\n{:indent$}{:>24}: {}\n{:indent$}     }}",
+                    "",
+                    "default",
+                    target(*default),
+                    ""
+                );
+                s
+            }
+            _ => mnemonic.to_owned(),
+        }
+    }
+
+    fn write_stack_map_frame(
+        &self,
+        f: &mut Formatter<'_>,
+        indent: usize,
+        frame: &StackMapFrame,
+    ) -> fmt::Result {
+        let pad = "";
+        let inner = indent + 2;
+        match frame {
+            StackMapFrame::Same { offset_delta } => {
+                writeln!(f, "{pad:indent$}frame_type = {} /* same */", offset_delta)
+            }
+            StackMapFrame::SameLocals1StackItem {
+                offset_delta,
+                stack,
+            } => {
+                writeln!(
+                    f,
+                    "{pad:indent$}frame_type = {} /* same_locals_1_stack_item */",
+                    offset_delta + 64
+                )?;
+                writeln!(
+                    f,
+                    "{pad:inner$}stack = [ {} ]",
+                    self.verification_type(stack)
+                )
+            }
+            StackMapFrame::SameLocals1StackItemExtended {
+                offset_delta,
+                stack,
+            } => {
+                writeln!(
+                    f,
+                    "{pad:indent$}frame_type = 247 /* same_locals_1_stack_item_frame_extended */"
+                )?;
+                writeln!(f, "{pad:inner$}offset_delta = {}", offset_delta)?;
+                writeln!(
+                    f,
+                    "{pad:inner$}stack = [ {} ]",
+                    self.verification_type(stack)
+                )
+            }
+            StackMapFrame::Chop { k, offset_delta } => {
+                writeln!(f, "{pad:indent$}frame_type = {} /* chop */", 251 - k)?;
+                writeln!(f, "{pad:inner$}offset_delta = {}", offset_delta)
+            }
+            StackMapFrame::SameExtended { offset_delta } => {
+                writeln!(f, "{pad:indent$}frame_type = 251 /* same_frame_extended */")?;
+                writeln!(f, "{pad:inner$}offset_delta = {}", offset_delta)
+            }
+            StackMapFrame::Append {
+                offset_delta,
+                locals,
+            } => {
+                writeln!(
+                    f,
+                    "{pad:indent$}frame_type = {} /* append */",
+                    251 + locals.len()
+                )?;
+                writeln!(f, "{pad:inner$}offset_delta = {}", offset_delta)?;
+                writeln!(
+                    f,
+                    "{pad:inner$}locals = {}",
+                    self.verification_types(locals)
+                )
+            }
+            StackMapFrame::Full {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                writeln!(f, "{pad:indent$}frame_type = 255 /* full_frame */")?;
+                writeln!(f, "{pad:inner$}offset_delta = {}", offset_delta)?;
+                writeln!(
+                    f,
+                    "{pad:inner$}locals = {}",
+                    self.verification_types(locals)
+                )?;
+                writeln!(f, "{pad:inner$}stack = {}", self.verification_types(stack))
+            }
+        }
+    }
+
+    fn verification_types(&self, types: &[VerificationTypeInfo]) -> String {
+        if types.is_empty() {
+            return String::from("[]");
+        }
+        let types = types
+            .iter()
+            .map(|t| self.verification_type(t))
+            .collect::<Vec<_>>();
+        format!("[ {} ]", types.join(", "))
+    }
+
+    fn verification_type(&self, t: &VerificationTypeInfo) -> String {
+        match t {
+            VerificationTypeInfo::Top => String::from("top"),
+            VerificationTypeInfo::Integer => String::from("int"),
+            VerificationTypeInfo::Float => String::from("float"),
+            VerificationTypeInfo::Double => String::from("double"),
+            VerificationTypeInfo::Long => String::from("long"),
+            VerificationTypeInfo::Null => String::from("null"),
+            VerificationTypeInfo::UninitializedThis => String::from("this"),
+            VerificationTypeInfo::Object { cpool_index } => {
+                format!("class {}", self.class_name(*cpool_index))
+            }
+            VerificationTypeInfo::Uninitialized { offset } => format!("uninitialized {}", offset),
+        }
+    }
+
+    fn targets(&self, indices: &[u16]) -> String {
+        if indices.is_empty() {
+            return String::new();
+        }
+        let targets = indices.iter().map(|i| self.resolve(*i)).collect::<Vec<_>>();
+        format!(" to {}", targets.join(", "))
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> String {
+        let pairs = annotation
+            .element_value_pairs
+            .iter()
+            .map(|p| {
+                format!(
+                    "{}={}",
+                    self.utf8(p.element_name_index),
+                    self.element_value(&p.value)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("{}({})", self.utf8(annotation.type_index), pairs.join(","))
+    }
+
+    fn type_annotation(&self, annotation: &TypeAnnotation) -> String {
+        let pairs = annotation
+            .element_value_pairs
+            .iter()
+            .map(|p| {
+                format!(
+                    "{}={}",
+                    self.utf8(p.element_name_index),
+                    self.element_value(&p.value)
+                )
+            })
+            .collect::<Vec<_>>();
+        let target = match &annotation.target_info {
+            TargetInfo::TypeParameter {
+                type_parameter_index,
+            } => format!("param_index={}", type_parameter_index),
+            TargetInfo::Supertype { supertype_index } => format!("type_index={}", supertype_index),
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => format!(
+                "param_index={}, bound_index={}",
+                type_parameter_index, bound_index
+            ),
+            TargetInfo::Empty => String::new(),
+            TargetInfo::FormalParameter {
+                formal_parameter_index,
+            } => format!("param_index={}", formal_parameter_index),
+            TargetInfo::Throws { throws_type_index } => {
+                format!("throws_index={}", throws_type_index)
+            }
+            TargetInfo::Localvar(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|e| {
+                        format!(
+                            "start_pc={}, length={}, index={}",
+                            e.start_pc, e.length, e.index
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", entries.join("; "))
+            }
+            TargetInfo::Catch {
+                exception_table_index,
+            } => format!("exception_index={}", exception_table_index),
+            TargetInfo::Offset { offset } => format!("offset={}", offset),
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index,
+            } => format!("offset={}, type_index={}", offset, type_argument_index),
+        };
+        let path = annotation
+            .target_path
+            .iter()
+            .map(|p| format!("{}:{}", p.type_path_kind, p.type_argument_index))
+            .collect::<Vec<_>>();
+        format!(
+            "{}({}): target_type=0x{:02x} {} location=[{}]",
+            self.utf8(annotation.type_index),
+            pairs.join(","),
+            annotation.target_type,
+            target,
+            path.join(", ")
+        )
+    }
+
+    fn element_value(&self, value: &ElementValue) -> String {
+        match value {
+            ElementValue::Byte { const_value_index }
+            | ElementValue::Char { const_value_index }
+            | ElementValue::Double { const_value_index }
+            | ElementValue::Float { const_value_index }
+            | ElementValue::Int { const_value_index }
+            | ElementValue::Long { const_value_index }
+            | ElementValue::Short { const_value_index }
+            | ElementValue::Boolean { const_value_index } => self.resolve(*const_value_index),
+            ElementValue::String { const_value_index } => {
+                format!("\"{}\"", self.resolve(*const_value_index))
+            }
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => format!(
+                "{}.{}",
+                self.utf8(*type_name_index),
+                self.utf8(*const_name_index)
+            ),
+            ElementValue::Class { class_info_index } => {
+                format!("class {}", self.utf8(*class_info_index))
+            }
+            ElementValue::Annotation(a) => format!("@{}", self.annotation(a)),
+            ElementValue::Array(values) => {
+                let values = values
+                    .iter()
+                    .map(|v| self.element_value(v))
+                    .collect::<Vec<_>>();
+                format!("[{}]", values.join(","))
+            }
+        }
+    }
+}
+impl Display for Disassembler<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let class_file = self.class_file;
+
+        writeln!(f, "class {}", self.class_name(class_file.this_class))?;
+        writeln!(f, "  minor version: {}", class_file.minor_version)?;
+        writeln!(f, "  major version: {}", class_file.major_version)?;
+        write_flags(
+            f,
+            2,
+            class_file.access_flags.bits(),
+            class_file.access_flags.iter_names(),
+        )?;
+        writeln!(
+            f,
+            "  this_class: #{:<27}// {}",
+            class_file.this_class,
+            self.class_name(class_file.this_class)
+        )?;
+        if class_file.super_class.get() == 0 {
+            writeln!(f, "  super_class: #0")?;
+        } else {
+            writeln!(
+                f,
+                "  super_class: #{:<26}// {}",
+                class_file.super_class,
+                self.class_name(class_file.super_class)
+            )?;
+        }
+        writeln!(
+            f,
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class_file.interfaces.len(),
+            class_file.fields.len(),
+            class_file.methods.len(),
+            class_file.attributes.0.len()
+        )?;
+        for interface in &class_file.interfaces {
+            writeln!(f, "  interface: {}", self.class_name(*interface))?;
+        }
+
+        self.write_constant_pool(f)?;
+
+        writeln!(f, "{{")?;
+        for (i, field) in class_file.fields.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            self.write_field(f, field)?;
+        }
+        for (i, method) in class_file.methods.iter().enumerate() {
+            if i > 0 || !class_file.fields.is_empty() {
+                writeln!(f)?;
+            }
+            self.write_method(f, method)?;
+        }
+        writeln!(f, "}}")?;
+
+        self.write_attributes(f, 0, &class_file.attributes)
+    }
+}
+
+impl ClassFile {
+    pub fn disassemble(&self) -> Disassembler<'_> {
+        Disassembler::new(self)
+    }
+}
+
+fn write_flags<'n>(
+    f: &mut Formatter<'_>,
+    indent: usize,
+    bits: u16,
+    names: impl Iterator<Item = (&'n str, impl Sized)>,
+) -> fmt::Result {
+    let names = names
+        .map(|(name, _)| format!("ACC_{}", name))
+        .collect::<Vec<_>>();
+    writeln!(
+        f,
+        "{:indent$}flags: (0x{:04x}) {}",
+        "",
+        bits,
+        names.join(", ")
+    )
+}
+
+/// A raw CONSTANT_Utf8 entry with its unpaired surrogates replaced.
+fn raw_utf8(bytes: &[u8]) -> String {
+    crate::mutf8::decode_lossy(bytes)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Non-finite values are spelled as in Java, e.g. `Infinity` rather than `inf`.
+fn java_float<F: Into<f64> + Display + Copy>(f: F) -> String {
+    let value: f64 = f.into();
+    if value.is_nan() {
+        String::from("NaN")
+    } else if value.is_infinite() {
+        String::from(if value > 0. { "Infinity" } else { "-Infinity" })
+    } else {
+        f.to_string()
+    }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::constant_pool;
+use crate::{constant_pool, ClassFileVersion};
 
 #[derive(Error, Debug)]
 pub enum ClassFileError {
@@ -8,8 +8,52 @@ pub enum ClassFileError {
     IOError(#[from] std::io::Error),
     #[error("Expected {0}, found {1:?}")]
     UnexpectedConstantPoolEntry(&'static str, constant_pool::CpInfo),
-    #[error("Invalid magic identifier: 0x{0:X}")]
-    InvalidCpInfoTag(u8),
+    #[error("Unsupported class file version {0}")]
+    UnsupportedVersion(ClassFileVersion),
+    #[error("Invalid constant pool index: {0}")]
+    InvalidConstantPoolIndex(u16),
+    #[error("Invalid constant pool count: {0}")]
+    InvalidConstantPoolCount(u16),
     #[error("Invalid cp info tag: {0}")]
+    InvalidCpInfoTag(u8),
+    #[error("Invalid magic identifier: 0x{0:X}")]
     InvalidMagicIdentifier(u32),
+    #[error("Invalid length for attribute {0}")]
+    InvalidAttributeLength(String),
+    #[error("Invalid stack map frame type: {0}")]
+    InvalidStackMapFrameType(u8),
+    #[error("Invalid verification type tag: {0}")]
+    InvalidVerificationTypeTag(u8),
+    #[error("Invalid bootstrap method index: {0}")]
+    InvalidBootstrapMethodIndex(u16),
+    #[error("Invalid method handle reference kind: {0}")]
+    InvalidReferenceKind(u8),
+    #[error("Invalid element value tag: {0}")]
+    InvalidElementValueTag(u8),
+    #[error("Invalid type annotation target type: 0x{0:X}")]
+    InvalidTargetType(u8),
+    #[error("Invalid opcode 0x{0:X} at pc {1}")]
+    InvalidOpcode(u8, u32),
+    #[error("Truncated instruction at pc {0}")]
+    TruncatedInstruction(u32),
+    #[error("Invalid instruction operands at pc {0}")]
+    InvalidInstruction(u32),
+    #[error("Code offset {0} is not the start of an instruction")]
+    InvalidCodeOffset(u32),
+    #[error("Invalid tableswitch from key {0} with {1} targets")]
+    InvalidTableswitch(i32, usize),
+    #[error("Illegal access flags: {0}")]
+    IllegalAccessFlags(&'static str),
+    #[error("Invalid descriptor: {0}")]
+    InvalidDescriptor(String),
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Invalid modified UTF-8 at byte {0}")]
+    InvalidModifiedUtf8(usize),
+    #[error("Unpaired surrogate in modified UTF-8 string")]
+    UnpairedSurrogate,
+    #[error("Label {0} is used but never placed")]
+    UnplacedLabel(usize),
+    #[error("Too many {0} to fit in a class file")]
+    ValueTooLarge(&'static str),
 }
@@ -0,0 +1,621 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.8
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    attributes::{AttributeInfo, Attributes},
+    class_file::{FieldInfo, MethodInfo},
+    constant_pool::{CpInfo, ReferenceKind},
+    descriptor::{self, BaseType, FieldType, MethodDescriptor},
+    ClassAccessFlags, ClassFile, MethodAccessFlags, Writer,
+};
+
+/// Where in the class file a [`Diagnostic`] was found. Fields and methods are identified by their
+/// position in [`ClassFile::fields`] and [`ClassFile::methods`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    ClassFile,
+    ConstantPool(u16),
+    Field(usize),
+    Method(usize),
+}
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::ClassFile => f.write_str("class file"),
+            Location::ConstantPool(index) => write!(f, "constant pool #{}", index),
+            Location::Field(index) => write!(f, "field {}", index),
+            Location::Method(index) => write!(f, "method {}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub location: Location,
+    pub message: String,
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl ClassFile {
+    /// Checks the format constraints of JVMS §4.8 and returns every violation found, so an empty
+    /// list means that the class file is well-formed. The attribute lengths of a parsed class file
+    /// are already checked by the [`crate::Parser`], so here only contents too large to be written
+    /// are reported.
+    pub fn format_check(&self) -> Vec<Diagnostic> {
+        let mut checker = FormatChecker {
+            class_file: self,
+            diagnostics: vec![],
+        };
+        checker.check();
+        checker.diagnostics
+    }
+}
+
+const OBJECT: &str = "java/lang/Object";
+
+/// Whether `name` may name a method. Only `<init>` and `<clinit>` may contain angle brackets.
+fn is_method_name(name: &str) -> bool {
+    name == "<init>"
+        || name == "<clinit>"
+        || (descriptor::is_unqualified_name(name) && !name.contains(['<', '>']))
+}
+
+struct FormatChecker<'a> {
+    class_file: &'a ClassFile,
+    diagnostics: Vec<Diagnostic>,
+}
+impl<'a> FormatChecker<'a> {
+    fn report(&mut self, location: Location, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            location,
+            message: message.into(),
+        });
+    }
+
+    fn cp_info(&self, index: u16) -> Option<&'a CpInfo> {
+        self.class_file.constant_pool.get(index)
+    }
+
+    /// The entry at `index`, reporting it if there is none or if it is of another kind.
+    fn entry(
+        &mut self,
+        location: Location,
+        index: impl Into<u16>,
+        expected: &[&'static str],
+    ) -> Option<&'a CpInfo> {
+        let index = index.into();
+        match self.cp_info(index) {
+            Some(cp_info) if expected.contains(&cp_info.name()) => Some(cp_info),
+            Some(cp_info) => {
+                self.report(
+                    location,
+                    format!(
+                        "#{} should be a {} entry, found {}",
+                        index,
+                        expected.join(" or "),
+                        cp_info.name()
+                    ),
+                );
+                None
+            }
+            None => {
+                self.report(
+                    location,
+                    format!("#{} is not a valid constant pool index", index),
+                );
+                None
+            }
+        }
+    }
+
+    /// The string at `index`, or `None` if it can't be resolved or was kept as raw bytes.
+    fn utf8(&mut self, location: Location, index: impl Into<u16>) -> Option<&'a str> {
+        match self.entry(location, index, &["Utf8"])? {
+            CpInfo::Utf8(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The name of the Class entry at `index`.
+    fn class_name(&mut self, location: Location, index: impl Into<u16>) -> Option<&'a str> {
+        match self.entry(location, index, &["Class"])? {
+            CpInfo::Class(c) => self.utf8(location, c.name_index),
+            _ => None,
+        }
+    }
+
+    /// The name and descriptor of the NameAndType entry at `index`.
+    fn name_and_type(
+        &mut self,
+        location: Location,
+        index: impl Into<u16>,
+    ) -> Option<(&'a str, &'a str)> {
+        match self.entry(location, index, &["NameAndType"])? {
+            CpInfo::NameAndType(nat) => Some((
+                self.utf8(location, nat.name_index)?,
+                self.utf8(location, nat.descriptor_index)?,
+            )),
+            _ => None,
+        }
+    }
+
+    fn field_type(&mut self, location: Location, descriptor: &str) -> Option<FieldType> {
+        match FieldType::parse(descriptor) {
+            Ok(field_type) => Some(field_type),
+            Err(e) => {
+                self.report(location, e.to_string());
+                None
+            }
+        }
+    }
+
+    fn method_descriptor(
+        &mut self,
+        location: Location,
+        descriptor: &str,
+    ) -> Option<MethodDescriptor> {
+        match MethodDescriptor::parse(descriptor) {
+            Ok(method_descriptor) => Some(method_descriptor),
+            Err(e) => {
+                self.report(location, e.to_string());
+                None
+            }
+        }
+    }
+
+    fn check(&mut self) {
+        self.check_constant_pool();
+        self.check_class();
+
+        let mut fields = HashSet::new();
+        for (i, field) in self.class_file.fields.iter().enumerate() {
+            if let Some(key) = self.check_field(Location::Field(i), field) {
+                if !fields.insert(key) {
+                    self.report(Location::Field(i), "duplicate field");
+                }
+            }
+        }
+
+        let mut methods = HashSet::new();
+        for (i, method) in self.class_file.methods.iter().enumerate() {
+            if let Some(key) = self.check_method(Location::Method(i), method) {
+                if !methods.insert(key) {
+                    self.report(Location::Method(i), "duplicate method");
+                }
+            }
+        }
+
+        self.check_attributes(Location::ClassFile, &self.class_file.attributes);
+    }
+
+    fn check_constant_pool(&mut self) {
+        let is_module = self
+            .class_file
+            .access_flags
+            .contains(ClassAccessFlags::MODULE);
+        let bootstrap_methods = self
+            .class_file
+            .attributes
+            .bootstrap_methods()
+            .map_or(0, |methods| methods.len());
+
+        let mut previous: Option<&CpInfo> = None;
+        for (i, cp_info) in self.class_file.constant_pool.into_iter().enumerate() {
+            let location = Location::ConstantPool(i as u16 + 1);
+            let follows_wide_constant =
+                matches!(previous, Some(&CpInfo::Long(_) | &CpInfo::Double(_)));
+            if follows_wide_constant != matches!(cp_info, CpInfo::Unusable) {
+                self.report(
+                    location,
+                    "Long and Double entries must be followed by exactly one unusable slot",
+                );
+            }
+            previous = Some(cp_info);
+
+            match cp_info {
+                CpInfo::Class(c) => {
+                    if let Some(name) = self.utf8(location, c.name_index) {
+                        let valid = if name.starts_with('[') {
+                            FieldType::parse(name).is_ok()
+                        } else {
+                            descriptor::is_binary_name(name)
+                        };
+                        if !valid {
+                            self.report(location, format!("invalid class name {}", name));
+                        }
+                    }
+                }
+                CpInfo::String { string_index } => {
+                    self.entry(location, *string_index, &["Utf8"]);
+                }
+                CpInfo::FieldRef(r) => {
+                    self.class_name(location, r.class_index);
+                    if let Some((name, descriptor)) =
+                        self.name_and_type(location, r.name_and_type_index)
+                    {
+                        if !descriptor::is_unqualified_name(name) {
+                            self.report(location, format!("invalid field name {}", name));
+                        }
+                        self.field_type(location, descriptor);
+                    }
+                }
+                CpInfo::MethodRef(r) | CpInfo::InterfaceMethodRef(r) => {
+                    self.class_name(location, r.class_index);
+                    if let Some((name, descriptor)) =
+                        self.name_and_type(location, r.name_and_type_index)
+                    {
+                        // Of the special names only <init> may be referenced.
+                        if !is_method_name(name) || name == "<clinit>" {
+                            self.report(location, format!("invalid method name {}", name));
+                        }
+                        let method_descriptor = self.method_descriptor(location, descriptor);
+                        if name == "<init>"
+                            && matches!(method_descriptor, Some(d) if d.return_type.is_some())
+                        {
+                            self.report(location, "<init> must return void");
+                        }
+                    }
+                }
+                CpInfo::NameAndType(nat) => {
+                    self.utf8(location, nat.name_index);
+                    self.utf8(location, nat.descriptor_index);
+                }
+                CpInfo::MethodHandle(h) => {
+                    self.check_method_handle(location, h.reference_kind, h.reference_index.get())
+                }
+                CpInfo::MethodType(t) => {
+                    if let Some(descriptor) = self.utf8(location, t.descriptor_index) {
+                        self.method_descriptor(location, descriptor);
+                    }
+                }
+                CpInfo::Dynamic(d) => {
+                    if d.bootstrap_method_attr_index as usize >= bootstrap_methods {
+                        self.report(location, "invalid bootstrap method index");
+                    }
+                    if let Some((_, descriptor)) =
+                        self.name_and_type(location, d.name_and_type_index)
+                    {
+                        self.field_type(location, descriptor);
+                    }
+                }
+                CpInfo::InvokeDynamic(i) => {
+                    if i.bootstrap_method_attr_index as usize >= bootstrap_methods {
+                        self.report(location, "invalid bootstrap method index");
+                    }
+                    if let Some((_, descriptor)) =
+                        self.name_and_type(location, i.name_and_type_index)
+                    {
+                        self.method_descriptor(location, descriptor);
+                    }
+                }
+                CpInfo::Module(m) => {
+                    if !is_module {
+                        self.report(location, "Module entries are only allowed in a module");
+                    }
+                    self.utf8(location, m.name_index);
+                }
+                CpInfo::Package(p) => {
+                    if !is_module {
+                        self.report(location, "Package entries are only allowed in a module");
+                    }
+                    if let Some(name) = self.utf8(location, p.name_index) {
+                        if !descriptor::is_binary_name(name) {
+                            self.report(location, format!("invalid package name {}", name));
+                        }
+                    }
+                }
+                CpInfo::Utf8(_)
+                | CpInfo::RawUtf8(_)
+                | CpInfo::Integer(_)
+                | CpInfo::Float(_)
+                | CpInfo::Long(_)
+                | CpInfo::Double(_)
+                | CpInfo::Unusable => {}
+            }
+        }
+    }
+
+    // https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.4.8
+    fn check_method_handle(
+        &mut self,
+        location: Location,
+        reference_kind: ReferenceKind,
+        index: u16,
+    ) {
+        let expected: &[&str] = match reference_kind {
+            ReferenceKind::GetField
+            | ReferenceKind::GetStatic
+            | ReferenceKind::PutField
+            | ReferenceKind::PutStatic => &["FieldRef"],
+            ReferenceKind::InvokeVirtual | ReferenceKind::NewInvokeSpecial => &["MethodRef"],
+            ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial
+                if self.class_file.major_version >= 52 =>
+            {
+                &["MethodRef", "InterfaceMethodRef"]
+            }
+            ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial => &["MethodRef"],
+            ReferenceKind::InvokeInterface => &["InterfaceMethodRef"],
+        };
+
+        let Some(CpInfo::MethodRef(r) | CpInfo::InterfaceMethodRef(r)) =
+            self.entry(location, index, expected)
+        else {
+            return;
+        };
+        let Some((name, _)) = self.name_and_type(location, r.name_and_type_index) else {
+            return;
+        };
+        if reference_kind == ReferenceKind::NewInvokeSpecial && name != "<init>" {
+            self.report(location, "REF_newInvokeSpecial must refer to <init>");
+        } else if reference_kind != ReferenceKind::NewInvokeSpecial
+            && (name == "<init>" || name == "<clinit>")
+        {
+            self.report(
+                location,
+                format!(
+                    "reference kind {} may not refer to {}",
+                    reference_kind, name
+                ),
+            );
+        }
+    }
+
+    fn check_class(&mut self) {
+        let class_file = self.class_file;
+        let location = Location::ClassFile;
+
+        if let Err(e) = class_file.access_flags.check() {
+            self.report(location, e.to_string());
+        }
+
+        let class_name = self.class_name(location, class_file.this_class);
+
+        if class_file.access_flags.contains(ClassAccessFlags::MODULE) {
+            if class_file.super_class.get() != 0
+                || !class_file.interfaces.is_empty()
+                || !class_file.fields.is_empty()
+                || !class_file.methods.is_empty()
+            {
+                self.report(
+                    location,
+                    "a module may not have a superclass, interfaces, fields or methods",
+                );
+            }
+            return;
+        }
+
+        if class_file.super_class.get() == 0 {
+            // Only Object has no direct superclass.
+            if class_name.is_some_and(|name| name != OBJECT) {
+                self.report(location, "only java/lang/Object may omit a superclass");
+            }
+        } else if let Some(super_class) = self.class_name(location, class_file.super_class) {
+            if class_file
+                .access_flags
+                .contains(ClassAccessFlags::INTERFACE)
+                && super_class != OBJECT
+            {
+                self.report(
+                    location,
+                    "the superclass of an interface must be java/lang/Object",
+                );
+            }
+        }
+
+        for interface in &class_file.interfaces {
+            self.class_name(location, *interface);
+        }
+    }
+
+    /// Checks a field, returning its name and descriptor to detect duplicates.
+    fn check_field(
+        &mut self,
+        location: Location,
+        field: &'a FieldInfo,
+    ) -> Option<(&'a str, &'a str)> {
+        if let Err(e) = field.access_flags.check(self.class_file.access_flags) {
+            self.report(location, e.to_string());
+        }
+        self.check_attributes(location, &field.attributes);
+
+        let name = self.utf8(location, field.name_index);
+        if let Some(name) = name.filter(|name| !descriptor::is_unqualified_name(name)) {
+            self.report(location, format!("invalid field name {}", name));
+        }
+        let descriptor = self.utf8(location, field.descriptor_index)?;
+        let field_type = self.field_type(location, descriptor);
+
+        let constantvalue_index = field.attributes.find_map(|info| match info {
+            AttributeInfo::ConstantValue {
+                constantvalue_index,
+            } => Some(*constantvalue_index),
+            _ => None,
+        });
+        if let (Some(constantvalue_index), Some(field_type)) = (constantvalue_index, field_type) {
+            let expected: &[&str] = match field_type {
+                FieldType::Base(BaseType::Long) => &["Long"],
+                FieldType::Base(BaseType::Float) => &["Float"],
+                FieldType::Base(BaseType::Double) => &["Double"],
+                FieldType::Base(_) => &["Integer"],
+                FieldType::Object(ref class_name) if class_name == "java/lang/String" => {
+                    &["String"]
+                }
+                _ => &[],
+            };
+            if expected.is_empty() {
+                self.report(location, "ConstantValue on a field that can't have one");
+            } else {
+                self.entry(location, constantvalue_index, expected);
+            }
+        }
+
+        Some((name?, descriptor))
+    }
+
+    /// Checks a method, returning its name and descriptor to detect duplicates.
+    fn check_method(
+        &mut self,
+        location: Location,
+        method: &'a MethodInfo,
+    ) -> Option<(&'a str, &'a str)> {
+        let class_flags = self.class_file.access_flags;
+        let version = self.class_file.version();
+        let flags = method.access_flags;
+        self.check_attributes(location, &method.attributes);
+
+        let has_code = method.attributes.code_attribute().is_some();
+        let needs_code = !flags.intersects(MethodAccessFlags::ABSTRACT | MethodAccessFlags::NATIVE);
+        if has_code != needs_code {
+            self.report(
+                location,
+                "a method must have a Code attribute unless it is native or abstract",
+            );
+        }
+
+        let name = self.utf8(location, method.name_index)?;
+        let descriptor = self.utf8(location, method.descriptor_index)?;
+        if !is_method_name(name) {
+            self.report(location, format!("invalid method name {}", name));
+        }
+        let method_descriptor = self.method_descriptor(location, descriptor);
+
+        match name {
+            "<clinit>" => {
+                // Before Java 7 any method named <clinit> is a class initializer.
+                if version.major >= 51 && !flags.contains(MethodAccessFlags::STATIC) {
+                    self.report(location, "<clinit> must be static");
+                }
+                if matches!(method_descriptor, Some(ref d) if !d.params.is_empty() || d.return_type.is_some())
+                {
+                    self.report(location, "<clinit> must take no arguments and return void");
+                }
+            }
+            _ => {
+                if let Err(e) = flags.check(class_flags, version) {
+                    self.report(location, e.to_string());
+                }
+            }
+        }
+
+        if name == "<init>" {
+            if class_flags.contains(ClassAccessFlags::INTERFACE) {
+                self.report(location, "an interface may not have an <init> method");
+            }
+            let allowed = MethodAccessFlags::PUBLIC
+                | MethodAccessFlags::PRIVATE
+                | MethodAccessFlags::PROTECTED
+                | MethodAccessFlags::VARARGS
+                | MethodAccessFlags::STRICT
+                | MethodAccessFlags::SYNTHETIC;
+            if !allowed.contains(flags) {
+                self.report(location, "invalid access flags for <init>");
+            }
+            if matches!(method_descriptor, Some(ref d) if d.return_type.is_some()) {
+                self.report(location, "<init> must return void");
+            }
+        }
+
+        // The limit of 255 parameter slots includes `this` for instance methods.
+        let this_slots = u16::from(!flags.contains(MethodAccessFlags::STATIC));
+        let slots = method_descriptor
+            .as_ref()
+            .map(|d| d.parameter_slots().saturating_add(this_slots));
+        if slots.is_some_and(|slots| slots > 255) {
+            self.report(location, "too many parameters");
+        }
+
+        Some((name, descriptor))
+    }
+
+    fn check_attributes(&mut self, location: Location, attributes: &'a Attributes) {
+        for attribute in attributes {
+            let name = self.utf8(location, attribute.attribute_name_index);
+            if let (Some(name), Some(expected)) = (name, attribute.info.name()) {
+                if name != expected {
+                    self.report(
+                        location,
+                        format!("{} attribute is named {}", expected, name),
+                    );
+                }
+            }
+
+            if let Err(e) = Writer::new(std::io::sink()).write_attribute(attribute) {
+                self.report(location, e.to_string());
+            }
+
+            match &attribute.info {
+                AttributeInfo::Code(code) => {
+                    if code.code.is_empty() || code.code.len() > 65535 {
+                        self.report(location, "the code array must have 1 to 65535 bytes");
+                    }
+                    for entry in &code.exception_table {
+                        if entry.catch_type != 0 {
+                            self.class_name(location, entry.catch_type);
+                        }
+                    }
+                    self.check_attributes(location, &code.attributes);
+                }
+                AttributeInfo::Exceptions(classes)
+                | AttributeInfo::NestMembers(classes)
+                | AttributeInfo::PermittedSubclasses(classes) => {
+                    for class in classes {
+                        self.class_name(location, *class);
+                    }
+                }
+                AttributeInfo::NestHost { host_class_index } => {
+                    self.class_name(location, *host_class_index);
+                }
+                AttributeInfo::InnerClasses(classes) => {
+                    for c in classes {
+                        self.class_name(location, c.inner_class_info_index);
+                        if c.outer_class_info_index != 0 {
+                            self.class_name(location, c.outer_class_info_index);
+                        }
+                        if c.inner_name_index != 0 {
+                            self.utf8(location, c.inner_name_index);
+                        }
+                        if let Err(e) = c.inner_class_access_flags.check() {
+                            self.report(location, e.to_string());
+                        }
+                    }
+                }
+                AttributeInfo::Signature {
+                    signature_index: index,
+                }
+                | AttributeInfo::SourceFile {
+                    sourcefile_index: index,
+                } => {
+                    self.utf8(location, *index);
+                }
+                AttributeInfo::BootstrapMethods(methods) => {
+                    for method in methods {
+                        self.entry(location, method.bootstrap_method_ref, &["MethodHandle"]);
+                        for argument in &method.bootstrap_arguments {
+                            self.entry(
+                                location,
+                                *argument,
+                                &[
+                                    "Integer",
+                                    "Float",
+                                    "Long",
+                                    "Double",
+                                    "Class",
+                                    "String",
+                                    "MethodHandle",
+                                    "MethodType",
+                                    "Dynamic",
+                                ],
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,1430 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-6.html
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{attributes::CodeAttribute, ClassFileError, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IconstM1,
+    Iconst0,
+    Iconst1,
+    Iconst2,
+    Iconst3,
+    Iconst4,
+    Iconst5,
+    Lconst0,
+    Lconst1,
+    Fconst0,
+    Fconst1,
+    Fconst2,
+    Dconst0,
+    Dconst1,
+    Bipush(i8),
+    Sipush(i16),
+    Ldc(u8),
+    LdcW(u16),
+    Ldc2W(u16),
+    Iload(u8),
+    Lload(u8),
+    Fload(u8),
+    Dload(u8),
+    Aload(u8),
+    Iload0,
+    Iload1,
+    Iload2,
+    Iload3,
+    Lload0,
+    Lload1,
+    Lload2,
+    Lload3,
+    Fload0,
+    Fload1,
+    Fload2,
+    Fload3,
+    Dload0,
+    Dload1,
+    Dload2,
+    Dload3,
+    Aload0,
+    Aload1,
+    Aload2,
+    Aload3,
+    Iaload,
+    Laload,
+    Faload,
+    Daload,
+    Aaload,
+    Baload,
+    Caload,
+    Saload,
+    Istore(u8),
+    Lstore(u8),
+    Fstore(u8),
+    Dstore(u8),
+    Astore(u8),
+    Istore0,
+    Istore1,
+    Istore2,
+    Istore3,
+    Lstore0,
+    Lstore1,
+    Lstore2,
+    Lstore3,
+    Fstore0,
+    Fstore1,
+    Fstore2,
+    Fstore3,
+    Dstore0,
+    Dstore1,
+    Dstore2,
+    Dstore3,
+    Astore0,
+    Astore1,
+    Astore2,
+    Astore3,
+    Iastore,
+    Lastore,
+    Fastore,
+    Dastore,
+    Aastore,
+    Bastore,
+    Castore,
+    Sastore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Iadd,
+    Ladd,
+    Fadd,
+    Dadd,
+    Isub,
+    Lsub,
+    Fsub,
+    Dsub,
+    Imul,
+    Lmul,
+    Fmul,
+    Dmul,
+    Idiv,
+    Ldiv,
+    Fdiv,
+    Ddiv,
+    Irem,
+    Lrem,
+    Frem,
+    Drem,
+    Ineg,
+    Lneg,
+    Fneg,
+    Dneg,
+    Ishl,
+    Lshl,
+    Ishr,
+    Lshr,
+    Iushr,
+    Lushr,
+    Iand,
+    Land,
+    Ior,
+    Lor,
+    Ixor,
+    Lxor,
+    Iinc {
+        index: u8,
+        value: i8,
+    },
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+    Ifeq(i16),
+    Ifne(i16),
+    Iflt(i16),
+    Ifge(i16),
+    Ifgt(i16),
+    Ifle(i16),
+    IfIcmpeq(i16),
+    IfIcmpne(i16),
+    IfIcmplt(i16),
+    IfIcmpge(i16),
+    IfIcmpgt(i16),
+    IfIcmple(i16),
+    IfAcmpeq(i16),
+    IfAcmpne(i16),
+    Goto(i16),
+    Jsr(i16),
+    Ret(u8),
+    Tableswitch {
+        default: i32,
+        low: i32,
+        offsets: Vec<i32>,
+    },
+    Lookupswitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
+    Ireturn,
+    Lreturn,
+    Freturn,
+    Dreturn,
+    Areturn,
+    Return,
+    Getstatic(u16),
+    Putstatic(u16),
+    Getfield(u16),
+    Putfield(u16),
+    Invokevirtual(u16),
+    Invokespecial(u16),
+    Invokestatic(u16),
+    Invokeinterface {
+        index: u16,
+        count: u8,
+    },
+    Invokedynamic(u16),
+    New(u16),
+    Newarray(ArrayType),
+    Anewarray(u16),
+    Arraylength,
+    Athrow,
+    Checkcast(u16),
+    Instanceof(u16),
+    Monitorenter,
+    Monitorexit,
+    Wide(WideInstruction),
+    Multianewarray {
+        index: u16,
+        dimensions: u8,
+    },
+    Ifnull(i16),
+    Ifnonnull(i16),
+    GotoW(i32),
+    JsrW(i32),
+}
+impl Instruction {
+    /// Decodes the instruction starting at `pc`, returning it together with the pc of the
+    /// instruction that follows.
+    pub fn decode(code: &[u8], pc: u32) -> Result<(Instruction, u32)> {
+        let mut r = CodeReader {
+            code,
+            pc,
+            pos: pc as usize,
+        };
+        let opcode = r.read_u8()?;
+        let instruction = match opcode {
+            0x00 => Instruction::Nop,
+            0x01 => Instruction::AconstNull,
+            0x02 => Instruction::IconstM1,
+            0x03 => Instruction::Iconst0,
+            0x04 => Instruction::Iconst1,
+            0x05 => Instruction::Iconst2,
+            0x06 => Instruction::Iconst3,
+            0x07 => Instruction::Iconst4,
+            0x08 => Instruction::Iconst5,
+            0x09 => Instruction::Lconst0,
+            0x0a => Instruction::Lconst1,
+            0x0b => Instruction::Fconst0,
+            0x0c => Instruction::Fconst1,
+            0x0d => Instruction::Fconst2,
+            0x0e => Instruction::Dconst0,
+            0x0f => Instruction::Dconst1,
+            0x10 => Instruction::Bipush(r.read_i8()?),
+            0x11 => Instruction::Sipush(r.read_i16()?),
+            0x12 => Instruction::Ldc(r.read_u8()?),
+            0x13 => Instruction::LdcW(r.read_u16()?),
+            0x14 => Instruction::Ldc2W(r.read_u16()?),
+            0x15 => Instruction::Iload(r.read_u8()?),
+            0x16 => Instruction::Lload(r.read_u8()?),
+            0x17 => Instruction::Fload(r.read_u8()?),
+            0x18 => Instruction::Dload(r.read_u8()?),
+            0x19 => Instruction::Aload(r.read_u8()?),
+            0x1a => Instruction::Iload0,
+            0x1b => Instruction::Iload1,
+            0x1c => Instruction::Iload2,
+            0x1d => Instruction::Iload3,
+            0x1e => Instruction::Lload0,
+            0x1f => Instruction::Lload1,
+            0x20 => Instruction::Lload2,
+            0x21 => Instruction::Lload3,
+            0x22 => Instruction::Fload0,
+            0x23 => Instruction::Fload1,
+            0x24 => Instruction::Fload2,
+            0x25 => Instruction::Fload3,
+            0x26 => Instruction::Dload0,
+            0x27 => Instruction::Dload1,
+            0x28 => Instruction::Dload2,
+            0x29 => Instruction::Dload3,
+            0x2a => Instruction::Aload0,
+            0x2b => Instruction::Aload1,
+            0x2c => Instruction::Aload2,
+            0x2d => Instruction::Aload3,
+            0x2e => Instruction::Iaload,
+            0x2f => Instruction::Laload,
+            0x30 => Instruction::Faload,
+            0x31 => Instruction::Daload,
+            0x32 => Instruction::Aaload,
+            0x33 => Instruction::Baload,
+            0x34 => Instruction::Caload,
+            0x35 => Instruction::Saload,
+            0x36 => Instruction::Istore(r.read_u8()?),
+            0x37 => Instruction::Lstore(r.read_u8()?),
+            0x38 => Instruction::Fstore(r.read_u8()?),
+            0x39 => Instruction::Dstore(r.read_u8()?),
+            0x3a => Instruction::Astore(r.read_u8()?),
+            0x3b => Instruction::Istore0,
+            0x3c => Instruction::Istore1,
+            0x3d => Instruction::Istore2,
+            0x3e => Instruction::Istore3,
+            0x3f => Instruction::Lstore0,
+            0x40 => Instruction::Lstore1,
+            0x41 => Instruction::Lstore2,
+            0x42 => Instruction::Lstore3,
+            0x43 => Instruction::Fstore0,
+            0x44 => Instruction::Fstore1,
+            0x45 => Instruction::Fstore2,
+            0x46 => Instruction::Fstore3,
+            0x47 => Instruction::Dstore0,
+            0x48 => Instruction::Dstore1,
+            0x49 => Instruction::Dstore2,
+            0x4a => Instruction::Dstore3,
+            0x4b => Instruction::Astore0,
+            0x4c => Instruction::Astore1,
+            0x4d => Instruction::Astore2,
+            0x4e => Instruction::Astore3,
+            0x4f => Instruction::Iastore,
+            0x50 => Instruction::Lastore,
+            0x51 => Instruction::Fastore,
+            0x52 => Instruction::Dastore,
+            0x53 => Instruction::Aastore,
+            0x54 => Instruction::Bastore,
+            0x55 => Instruction::Castore,
+            0x56 => Instruction::Sastore,
+            0x57 => Instruction::Pop,
+            0x58 => Instruction::Pop2,
+            0x59 => Instruction::Dup,
+            0x5a => Instruction::DupX1,
+            0x5b => Instruction::DupX2,
+            0x5c => Instruction::Dup2,
+            0x5d => Instruction::Dup2X1,
+            0x5e => Instruction::Dup2X2,
+            0x5f => Instruction::Swap,
+            0x60 => Instruction::Iadd,
+            0x61 => Instruction::Ladd,
+            0x62 => Instruction::Fadd,
+            0x63 => Instruction::Dadd,
+            0x64 => Instruction::Isub,
+            0x65 => Instruction::Lsub,
+            0x66 => Instruction::Fsub,
+            0x67 => Instruction::Dsub,
+            0x68 => Instruction::Imul,
+            0x69 => Instruction::Lmul,
+            0x6a => Instruction::Fmul,
+            0x6b => Instruction::Dmul,
+            0x6c => Instruction::Idiv,
+            0x6d => Instruction::Ldiv,
+            0x6e => Instruction::Fdiv,
+            0x6f => Instruction::Ddiv,
+            0x70 => Instruction::Irem,
+            0x71 => Instruction::Lrem,
+            0x72 => Instruction::Frem,
+            0x73 => Instruction::Drem,
+            0x74 => Instruction::Ineg,
+            0x75 => Instruction::Lneg,
+            0x76 => Instruction::Fneg,
+            0x77 => Instruction::Dneg,
+            0x78 => Instruction::Ishl,
+            0x79 => Instruction::Lshl,
+            0x7a => Instruction::Ishr,
+            0x7b => Instruction::Lshr,
+            0x7c => Instruction::Iushr,
+            0x7d => Instruction::Lushr,
+            0x7e => Instruction::Iand,
+            0x7f => Instruction::Land,
+            0x80 => Instruction::Ior,
+            0x81 => Instruction::Lor,
+            0x82 => Instruction::Ixor,
+            0x83 => Instruction::Lxor,
+            0x84 => Instruction::Iinc {
+                index: r.read_u8()?,
+                value: r.read_i8()?,
+            },
+            0x85 => Instruction::I2l,
+            0x86 => Instruction::I2f,
+            0x87 => Instruction::I2d,
+            0x88 => Instruction::L2i,
+            0x89 => Instruction::L2f,
+            0x8a => Instruction::L2d,
+            0x8b => Instruction::F2i,
+            0x8c => Instruction::F2l,
+            0x8d => Instruction::F2d,
+            0x8e => Instruction::D2i,
+            0x8f => Instruction::D2l,
+            0x90 => Instruction::D2f,
+            0x91 => Instruction::I2b,
+            0x92 => Instruction::I2c,
+            0x93 => Instruction::I2s,
+            0x94 => Instruction::Lcmp,
+            0x95 => Instruction::Fcmpl,
+            0x96 => Instruction::Fcmpg,
+            0x97 => Instruction::Dcmpl,
+            0x98 => Instruction::Dcmpg,
+            0x99 => Instruction::Ifeq(r.read_i16()?),
+            0x9a => Instruction::Ifne(r.read_i16()?),
+            0x9b => Instruction::Iflt(r.read_i16()?),
+            0x9c => Instruction::Ifge(r.read_i16()?),
+            0x9d => Instruction::Ifgt(r.read_i16()?),
+            0x9e => Instruction::Ifle(r.read_i16()?),
+            0x9f => Instruction::IfIcmpeq(r.read_i16()?),
+            0xa0 => Instruction::IfIcmpne(r.read_i16()?),
+            0xa1 => Instruction::IfIcmplt(r.read_i16()?),
+            0xa2 => Instruction::IfIcmpge(r.read_i16()?),
+            0xa3 => Instruction::IfIcmpgt(r.read_i16()?),
+            0xa4 => Instruction::IfIcmple(r.read_i16()?),
+            0xa5 => Instruction::IfAcmpeq(r.read_i16()?),
+            0xa6 => Instruction::IfAcmpne(r.read_i16()?),
+            0xa7 => Instruction::Goto(r.read_i16()?),
+            0xa8 => Instruction::Jsr(r.read_i16()?),
+            0xa9 => Instruction::Ret(r.read_u8()?),
+            0xaa => r.read_tableswitch()?,
+            0xab => r.read_lookupswitch()?,
+            0xac => Instruction::Ireturn,
+            0xad => Instruction::Lreturn,
+            0xae => Instruction::Freturn,
+            0xaf => Instruction::Dreturn,
+            0xb0 => Instruction::Areturn,
+            0xb1 => Instruction::Return,
+            0xb2 => Instruction::Getstatic(r.read_u16()?),
+            0xb3 => Instruction::Putstatic(r.read_u16()?),
+            0xb4 => Instruction::Getfield(r.read_u16()?),
+            0xb5 => Instruction::Putfield(r.read_u16()?),
+            0xb6 => Instruction::Invokevirtual(r.read_u16()?),
+            0xb7 => Instruction::Invokespecial(r.read_u16()?),
+            0xb8 => Instruction::Invokestatic(r.read_u16()?),
+            0xb9 => {
+                let index = r.read_u16()?;
+                let count = r.read_u8()?;
+                r.read_u8()?;
+                Instruction::Invokeinterface { index, count }
+            }
+            0xba => {
+                let index = r.read_u16()?;
+                r.read_u16()?;
+                Instruction::Invokedynamic(index)
+            }
+            0xbb => Instruction::New(r.read_u16()?),
+            0xbc => Instruction::Newarray(r.read_array_type()?),
+            0xbd => Instruction::Anewarray(r.read_u16()?),
+            0xbe => Instruction::Arraylength,
+            0xbf => Instruction::Athrow,
+            0xc0 => Instruction::Checkcast(r.read_u16()?),
+            0xc1 => Instruction::Instanceof(r.read_u16()?),
+            0xc2 => Instruction::Monitorenter,
+            0xc3 => Instruction::Monitorexit,
+            0xc4 => Instruction::Wide(r.read_wide()?),
+            0xc5 => Instruction::Multianewarray {
+                index: r.read_u16()?,
+                dimensions: r.read_u8()?,
+            },
+            0xc6 => Instruction::Ifnull(r.read_i16()?),
+            0xc7 => Instruction::Ifnonnull(r.read_i16()?),
+            0xc8 => Instruction::GotoW(r.read_i32()?),
+            0xc9 => Instruction::JsrW(r.read_i32()?),
+            _ => return Err(ClassFileError::InvalidOpcode(opcode, pc)),
+        };
+
+        Ok((instruction, r.pos as u32))
+    }
+
+    /// Appends the encoded instruction to `code`, where it starts at pc `code.len()`. This is the
+    /// inverse of [`Instruction::decode`]. Fails for a `tableswitch` without offsets or whose
+    /// high key would overflow, in which case `code` is left unchanged.
+    pub fn encode(&self, code: &mut Vec<u8>) -> Result<()> {
+        let pc = code.len();
+        // There is a tableswitch offset for each key from low to high inclusive. The range is
+        // checked before anything is written.
+        let high = match self {
+            Instruction::Tableswitch { low, offsets, .. } => offsets
+                .len()
+                .checked_sub(1)
+                .and_then(|n| i32::try_from(n).ok())
+                .and_then(|n| low.checked_add(n))
+                .ok_or(ClassFileError::InvalidInstruction(pc as u32))?,
+            _ => 0,
+        };
+        code.push(self.opcode());
+        match self {
+            Instruction::Bipush(v) => code.push(*v as u8),
+            Instruction::Sipush(v) => code.extend(v.to_be_bytes()),
+            Instruction::Ldc(index)
+            | Instruction::Iload(index)
+            | Instruction::Lload(index)
+            | Instruction::Fload(index)
+            | Instruction::Dload(index)
+            | Instruction::Aload(index)
+            | Instruction::Istore(index)
+            | Instruction::Lstore(index)
+            | Instruction::Fstore(index)
+            | Instruction::Dstore(index)
+            | Instruction::Astore(index)
+            | Instruction::Ret(index) => code.push(*index),
+            Instruction::LdcW(index)
+            | Instruction::Ldc2W(index)
+            | Instruction::Getstatic(index)
+            | Instruction::Putstatic(index)
+            | Instruction::Getfield(index)
+            | Instruction::Putfield(index)
+            | Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::New(index)
+            | Instruction::Anewarray(index)
+            | Instruction::Checkcast(index)
+            | Instruction::Instanceof(index) => code.extend(index.to_be_bytes()),
+            Instruction::Iinc { index, value } => code.extend([*index, *value as u8]),
+            Instruction::Ifeq(offset)
+            | Instruction::Ifne(offset)
+            | Instruction::Iflt(offset)
+            | Instruction::Ifge(offset)
+            | Instruction::Ifgt(offset)
+            | Instruction::Ifle(offset)
+            | Instruction::IfIcmpeq(offset)
+            | Instruction::IfIcmpne(offset)
+            | Instruction::IfIcmplt(offset)
+            | Instruction::IfIcmpge(offset)
+            | Instruction::IfIcmpgt(offset)
+            | Instruction::IfIcmple(offset)
+            | Instruction::IfAcmpeq(offset)
+            | Instruction::IfAcmpne(offset)
+            | Instruction::Goto(offset)
+            | Instruction::Jsr(offset)
+            | Instruction::Ifnull(offset)
+            | Instruction::Ifnonnull(offset) => code.extend(offset.to_be_bytes()),
+            Instruction::GotoW(offset) | Instruction::JsrW(offset) => {
+                code.extend(offset.to_be_bytes())
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                offsets,
+            } => {
+                // The padding aligns the operands to a multiple of four bytes from the start of
+                // the method.
+                code.resize(code.len() + (3 - pc % 4), 0);
+                code.extend(default.to_be_bytes());
+                code.extend(low.to_be_bytes());
+                code.extend(high.to_be_bytes());
+                for offset in offsets {
+                    code.extend(offset.to_be_bytes());
+                }
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                code.resize(code.len() + (3 - pc % 4), 0);
+                code.extend(default.to_be_bytes());
+                code.extend((pairs.len() as i32).to_be_bytes());
+                for (key, offset) in pairs {
+                    code.extend(key.to_be_bytes());
+                    code.extend(offset.to_be_bytes());
+                }
+            }
+            Instruction::Invokeinterface { index, count } => {
+                code.extend(index.to_be_bytes());
+                code.extend([*count, 0]);
+            }
+            Instruction::Invokedynamic(index) => {
+                code.extend(index.to_be_bytes());
+                code.extend([0, 0]);
+            }
+            Instruction::Newarray(atype) => code.push(*atype as u8),
+            Instruction::Multianewarray { index, dimensions } => {
+                code.extend(index.to_be_bytes());
+                code.push(*dimensions);
+            }
+            Instruction::Wide(wide) => {
+                code.push(wide.opcode());
+                match wide {
+                    WideInstruction::Iinc { index, value } => {
+                        code.extend(index.to_be_bytes());
+                        code.extend(value.to_be_bytes());
+                    }
+                    WideInstruction::Iload(index)
+                    | WideInstruction::Lload(index)
+                    | WideInstruction::Fload(index)
+                    | WideInstruction::Dload(index)
+                    | WideInstruction::Aload(index)
+                    | WideInstruction::Istore(index)
+                    | WideInstruction::Lstore(index)
+                    | WideInstruction::Fstore(index)
+                    | WideInstruction::Dstore(index)
+                    | WideInstruction::Astore(index)
+                    | WideInstruction::Ret(index) => code.extend(index.to_be_bytes()),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Instruction::Nop => 0x00,
+            Instruction::AconstNull => 0x01,
+            Instruction::IconstM1 => 0x02,
+            Instruction::Iconst0 => 0x03,
+            Instruction::Iconst1 => 0x04,
+            Instruction::Iconst2 => 0x05,
+            Instruction::Iconst3 => 0x06,
+            Instruction::Iconst4 => 0x07,
+            Instruction::Iconst5 => 0x08,
+            Instruction::Lconst0 => 0x09,
+            Instruction::Lconst1 => 0x0a,
+            Instruction::Fconst0 => 0x0b,
+            Instruction::Fconst1 => 0x0c,
+            Instruction::Fconst2 => 0x0d,
+            Instruction::Dconst0 => 0x0e,
+            Instruction::Dconst1 => 0x0f,
+            Instruction::Bipush(_) => 0x10,
+            Instruction::Sipush(_) => 0x11,
+            Instruction::Ldc(_) => 0x12,
+            Instruction::LdcW(_) => 0x13,
+            Instruction::Ldc2W(_) => 0x14,
+            Instruction::Iload(_) => 0x15,
+            Instruction::Lload(_) => 0x16,
+            Instruction::Fload(_) => 0x17,
+            Instruction::Dload(_) => 0x18,
+            Instruction::Aload(_) => 0x19,
+            Instruction::Iload0 => 0x1a,
+            Instruction::Iload1 => 0x1b,
+            Instruction::Iload2 => 0x1c,
+            Instruction::Iload3 => 0x1d,
+            Instruction::Lload0 => 0x1e,
+            Instruction::Lload1 => 0x1f,
+            Instruction::Lload2 => 0x20,
+            Instruction::Lload3 => 0x21,
+            Instruction::Fload0 => 0x22,
+            Instruction::Fload1 => 0x23,
+            Instruction::Fload2 => 0x24,
+            Instruction::Fload3 => 0x25,
+            Instruction::Dload0 => 0x26,
+            Instruction::Dload1 => 0x27,
+            Instruction::Dload2 => 0x28,
+            Instruction::Dload3 => 0x29,
+            Instruction::Aload0 => 0x2a,
+            Instruction::Aload1 => 0x2b,
+            Instruction::Aload2 => 0x2c,
+            Instruction::Aload3 => 0x2d,
+            Instruction::Iaload => 0x2e,
+            Instruction::Laload => 0x2f,
+            Instruction::Faload => 0x30,
+            Instruction::Daload => 0x31,
+            Instruction::Aaload => 0x32,
+            Instruction::Baload => 0x33,
+            Instruction::Caload => 0x34,
+            Instruction::Saload => 0x35,
+            Instruction::Istore(_) => 0x36,
+            Instruction::Lstore(_) => 0x37,
+            Instruction::Fstore(_) => 0x38,
+            Instruction::Dstore(_) => 0x39,
+            Instruction::Astore(_) => 0x3a,
+            Instruction::Istore0 => 0x3b,
+            Instruction::Istore1 => 0x3c,
+            Instruction::Istore2 => 0x3d,
+            Instruction::Istore3 => 0x3e,
+            Instruction::Lstore0 => 0x3f,
+            Instruction::Lstore1 => 0x40,
+            Instruction::Lstore2 => 0x41,
+            Instruction::Lstore3 => 0x42,
+            Instruction::Fstore0 => 0x43,
+            Instruction::Fstore1 => 0x44,
+            Instruction::Fstore2 => 0x45,
+            Instruction::Fstore3 => 0x46,
+            Instruction::Dstore0 => 0x47,
+            Instruction::Dstore1 => 0x48,
+            Instruction::Dstore2 => 0x49,
+            Instruction::Dstore3 => 0x4a,
+            Instruction::Astore0 => 0x4b,
+            Instruction::Astore1 => 0x4c,
+            Instruction::Astore2 => 0x4d,
+            Instruction::Astore3 => 0x4e,
+            Instruction::Iastore => 0x4f,
+            Instruction::Lastore => 0x50,
+            Instruction::Fastore => 0x51,
+            Instruction::Dastore => 0x52,
+            Instruction::Aastore => 0x53,
+            Instruction::Bastore => 0x54,
+            Instruction::Castore => 0x55,
+            Instruction::Sastore => 0x56,
+            Instruction::Pop => 0x57,
+            Instruction::Pop2 => 0x58,
+            Instruction::Dup => 0x59,
+            Instruction::DupX1 => 0x5a,
+            Instruction::DupX2 => 0x5b,
+            Instruction::Dup2 => 0x5c,
+            Instruction::Dup2X1 => 0x5d,
+            Instruction::Dup2X2 => 0x5e,
+            Instruction::Swap => 0x5f,
+            Instruction::Iadd => 0x60,
+            Instruction::Ladd => 0x61,
+            Instruction::Fadd => 0x62,
+            Instruction::Dadd => 0x63,
+            Instruction::Isub => 0x64,
+            Instruction::Lsub => 0x65,
+            Instruction::Fsub => 0x66,
+            Instruction::Dsub => 0x67,
+            Instruction::Imul => 0x68,
+            Instruction::Lmul => 0x69,
+            Instruction::Fmul => 0x6a,
+            Instruction::Dmul => 0x6b,
+            Instruction::Idiv => 0x6c,
+            Instruction::Ldiv => 0x6d,
+            Instruction::Fdiv => 0x6e,
+            Instruction::Ddiv => 0x6f,
+            Instruction::Irem => 0x70,
+            Instruction::Lrem => 0x71,
+            Instruction::Frem => 0x72,
+            Instruction::Drem => 0x73,
+            Instruction::Ineg => 0x74,
+            Instruction::Lneg => 0x75,
+            Instruction::Fneg => 0x76,
+            Instruction::Dneg => 0x77,
+            Instruction::Ishl => 0x78,
+            Instruction::Lshl => 0x79,
+            Instruction::Ishr => 0x7a,
+            Instruction::Lshr => 0x7b,
+            Instruction::Iushr => 0x7c,
+            Instruction::Lushr => 0x7d,
+            Instruction::Iand => 0x7e,
+            Instruction::Land => 0x7f,
+            Instruction::Ior => 0x80,
+            Instruction::Lor => 0x81,
+            Instruction::Ixor => 0x82,
+            Instruction::Lxor => 0x83,
+            Instruction::Iinc { .. } => 0x84,
+            Instruction::I2l => 0x85,
+            Instruction::I2f => 0x86,
+            Instruction::I2d => 0x87,
+            Instruction::L2i => 0x88,
+            Instruction::L2f => 0x89,
+            Instruction::L2d => 0x8a,
+            Instruction::F2i => 0x8b,
+            Instruction::F2l => 0x8c,
+            Instruction::F2d => 0x8d,
+            Instruction::D2i => 0x8e,
+            Instruction::D2l => 0x8f,
+            Instruction::D2f => 0x90,
+            Instruction::I2b => 0x91,
+            Instruction::I2c => 0x92,
+            Instruction::I2s => 0x93,
+            Instruction::Lcmp => 0x94,
+            Instruction::Fcmpl => 0x95,
+            Instruction::Fcmpg => 0x96,
+            Instruction::Dcmpl => 0x97,
+            Instruction::Dcmpg => 0x98,
+            Instruction::Ifeq(_) => 0x99,
+            Instruction::Ifne(_) => 0x9a,
+            Instruction::Iflt(_) => 0x9b,
+            Instruction::Ifge(_) => 0x9c,
+            Instruction::Ifgt(_) => 0x9d,
+            Instruction::Ifle(_) => 0x9e,
+            Instruction::IfIcmpeq(_) => 0x9f,
+            Instruction::IfIcmpne(_) => 0xa0,
+            Instruction::IfIcmplt(_) => 0xa1,
+            Instruction::IfIcmpge(_) => 0xa2,
+            Instruction::IfIcmpgt(_) => 0xa3,
+            Instruction::IfIcmple(_) => 0xa4,
+            Instruction::IfAcmpeq(_) => 0xa5,
+            Instruction::IfAcmpne(_) => 0xa6,
+            Instruction::Goto(_) => 0xa7,
+            Instruction::Jsr(_) => 0xa8,
+            Instruction::Ret(_) => 0xa9,
+            Instruction::Tableswitch { .. } => 0xaa,
+            Instruction::Lookupswitch { .. } => 0xab,
+            Instruction::Ireturn => 0xac,
+            Instruction::Lreturn => 0xad,
+            Instruction::Freturn => 0xae,
+            Instruction::Dreturn => 0xaf,
+            Instruction::Areturn => 0xb0,
+            Instruction::Return => 0xb1,
+            Instruction::Getstatic(_) => 0xb2,
+            Instruction::Putstatic(_) => 0xb3,
+            Instruction::Getfield(_) => 0xb4,
+            Instruction::Putfield(_) => 0xb5,
+            Instruction::Invokevirtual(_) => 0xb6,
+            Instruction::Invokespecial(_) => 0xb7,
+            Instruction::Invokestatic(_) => 0xb8,
+            Instruction::Invokeinterface { .. } => 0xb9,
+            Instruction::Invokedynamic(_) => 0xba,
+            Instruction::New(_) => 0xbb,
+            Instruction::Newarray(_) => 0xbc,
+            Instruction::Anewarray(_) => 0xbd,
+            Instruction::Arraylength => 0xbe,
+            Instruction::Athrow => 0xbf,
+            Instruction::Checkcast(_) => 0xc0,
+            Instruction::Instanceof(_) => 0xc1,
+            Instruction::Monitorenter => 0xc2,
+            Instruction::Monitorexit => 0xc3,
+            Instruction::Wide(_) => 0xc4,
+            Instruction::Multianewarray { .. } => 0xc5,
+            Instruction::Ifnull(_) => 0xc6,
+            Instruction::Ifnonnull(_) => 0xc7,
+            Instruction::GotoW(_) => 0xc8,
+            Instruction::JsrW(_) => 0xc9,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Nop => "nop",
+            Instruction::AconstNull => "aconst_null",
+            Instruction::IconstM1 => "iconst_m1",
+            Instruction::Iconst0 => "iconst_0",
+            Instruction::Iconst1 => "iconst_1",
+            Instruction::Iconst2 => "iconst_2",
+            Instruction::Iconst3 => "iconst_3",
+            Instruction::Iconst4 => "iconst_4",
+            Instruction::Iconst5 => "iconst_5",
+            Instruction::Lconst0 => "lconst_0",
+            Instruction::Lconst1 => "lconst_1",
+            Instruction::Fconst0 => "fconst_0",
+            Instruction::Fconst1 => "fconst_1",
+            Instruction::Fconst2 => "fconst_2",
+            Instruction::Dconst0 => "dconst_0",
+            Instruction::Dconst1 => "dconst_1",
+            Instruction::Bipush(_) => "bipush",
+            Instruction::Sipush(_) => "sipush",
+            Instruction::Ldc(_) => "ldc",
+            Instruction::LdcW(_) => "ldc_w",
+            Instruction::Ldc2W(_) => "ldc2_w",
+            Instruction::Iload(_) => "iload",
+            Instruction::Lload(_) => "lload",
+            Instruction::Fload(_) => "fload",
+            Instruction::Dload(_) => "dload",
+            Instruction::Aload(_) => "aload",
+            Instruction::Iload0 => "iload_0",
+            Instruction::Iload1 => "iload_1",
+            Instruction::Iload2 => "iload_2",
+            Instruction::Iload3 => "iload_3",
+            Instruction::Lload0 => "lload_0",
+            Instruction::Lload1 => "lload_1",
+            Instruction::Lload2 => "lload_2",
+            Instruction::Lload3 => "lload_3",
+            Instruction::Fload0 => "fload_0",
+            Instruction::Fload1 => "fload_1",
+            Instruction::Fload2 => "fload_2",
+            Instruction::Fload3 => "fload_3",
+            Instruction::Dload0 => "dload_0",
+            Instruction::Dload1 => "dload_1",
+            Instruction::Dload2 => "dload_2",
+            Instruction::Dload3 => "dload_3",
+            Instruction::Aload0 => "aload_0",
+            Instruction::Aload1 => "aload_1",
+            Instruction::Aload2 => "aload_2",
+            Instruction::Aload3 => "aload_3",
+            Instruction::Iaload => "iaload",
+            Instruction::Laload => "laload",
+            Instruction::Faload => "faload",
+            Instruction::Daload => "daload",
+            Instruction::Aaload => "aaload",
+            Instruction::Baload => "baload",
+            Instruction::Caload => "caload",
+            Instruction::Saload => "saload",
+            Instruction::Istore(_) => "istore",
+            Instruction::Lstore(_) => "lstore",
+            Instruction::Fstore(_) => "fstore",
+            Instruction::Dstore(_) => "dstore",
+            Instruction::Astore(_) => "astore",
+            Instruction::Istore0 => "istore_0",
+            Instruction::Istore1 => "istore_1",
+            Instruction::Istore2 => "istore_2",
+            Instruction::Istore3 => "istore_3",
+            Instruction::Lstore0 => "lstore_0",
+            Instruction::Lstore1 => "lstore_1",
+            Instruction::Lstore2 => "lstore_2",
+            Instruction::Lstore3 => "lstore_3",
+            Instruction::Fstore0 => "fstore_0",
+            Instruction::Fstore1 => "fstore_1",
+            Instruction::Fstore2 => "fstore_2",
+            Instruction::Fstore3 => "fstore_3",
+            Instruction::Dstore0 => "dstore_0",
+            Instruction::Dstore1 => "dstore_1",
+            Instruction::Dstore2 => "dstore_2",
+            Instruction::Dstore3 => "dstore_3",
+            Instruction::Astore0 => "astore_0",
+            Instruction::Astore1 => "astore_1",
+            Instruction::Astore2 => "astore_2",
+            Instruction::Astore3 => "astore_3",
+            Instruction::Iastore => "iastore",
+            Instruction::Lastore => "lastore",
+            Instruction::Fastore => "fastore",
+            Instruction::Dastore => "dastore",
+            Instruction::Aastore => "aastore",
+            Instruction::Bastore => "bastore",
+            Instruction::Castore => "castore",
+            Instruction::Sastore => "sastore",
+            Instruction::Pop => "pop",
+            Instruction::Pop2 => "pop2",
+            Instruction::Dup => "dup",
+            Instruction::DupX1 => "dup_x1",
+            Instruction::DupX2 => "dup_x2",
+            Instruction::Dup2 => "dup2",
+            Instruction::Dup2X1 => "dup2_x1",
+            Instruction::Dup2X2 => "dup2_x2",
+            Instruction::Swap => "swap",
+            Instruction::Iadd => "iadd",
+            Instruction::Ladd => "ladd",
+            Instruction::Fadd => "fadd",
+            Instruction::Dadd => "dadd",
+            Instruction::Isub => "isub",
+            Instruction::Lsub => "lsub",
+            Instruction::Fsub => "fsub",
+            Instruction::Dsub => "dsub",
+            Instruction::Imul => "imul",
+            Instruction::Lmul => "lmul",
+            Instruction::Fmul => "fmul",
+            Instruction::Dmul => "dmul",
+            Instruction::Idiv => "idiv",
+            Instruction::Ldiv => "ldiv",
+            Instruction::Fdiv => "fdiv",
+            Instruction::Ddiv => "ddiv",
+            Instruction::Irem => "irem",
+            Instruction::Lrem => "lrem",
+            Instruction::Frem => "frem",
+            Instruction::Drem => "drem",
+            Instruction::Ineg => "ineg",
+            Instruction::Lneg => "lneg",
+            Instruction::Fneg => "fneg",
+            Instruction::Dneg => "dneg",
+            Instruction::Ishl => "ishl",
+            Instruction::Lshl => "lshl",
+            Instruction::Ishr => "ishr",
+            Instruction::Lshr => "lshr",
+            Instruction::Iushr => "iushr",
+            Instruction::Lushr => "lushr",
+            Instruction::Iand => "iand",
+            Instruction::Land => "land",
+            Instruction::Ior => "ior",
+            Instruction::Lor => "lor",
+            Instruction::Ixor => "ixor",
+            Instruction::Lxor => "lxor",
+            Instruction::Iinc { .. } => "iinc",
+            Instruction::I2l => "i2l",
+            Instruction::I2f => "i2f",
+            Instruction::I2d => "i2d",
+            Instruction::L2i => "l2i",
+            Instruction::L2f => "l2f",
+            Instruction::L2d => "l2d",
+            Instruction::F2i => "f2i",
+            Instruction::F2l => "f2l",
+            Instruction::F2d => "f2d",
+            Instruction::D2i => "d2i",
+            Instruction::D2l => "d2l",
+            Instruction::D2f => "d2f",
+            Instruction::I2b => "i2b",
+            Instruction::I2c => "i2c",
+            Instruction::I2s => "i2s",
+            Instruction::Lcmp => "lcmp",
+            Instruction::Fcmpl => "fcmpl",
+            Instruction::Fcmpg => "fcmpg",
+            Instruction::Dcmpl => "dcmpl",
+            Instruction::Dcmpg => "dcmpg",
+            Instruction::Ifeq(_) => "ifeq",
+            Instruction::Ifne(_) => "ifne",
+            Instruction::Iflt(_) => "iflt",
+            Instruction::Ifge(_) => "ifge",
+            Instruction::Ifgt(_) => "ifgt",
+            Instruction::Ifle(_) => "ifle",
+            Instruction::IfIcmpeq(_) => "if_icmpeq",
+            Instruction::IfIcmpne(_) => "if_icmpne",
+            Instruction::IfIcmplt(_) => "if_icmplt",
+            Instruction::IfIcmpge(_) => "if_icmpge",
+            Instruction::IfIcmpgt(_) => "if_icmpgt",
+            Instruction::IfIcmple(_) => "if_icmple",
+            Instruction::IfAcmpeq(_) => "if_acmpeq",
+            Instruction::IfAcmpne(_) => "if_acmpne",
+            Instruction::Goto(_) => "goto",
+            Instruction::Jsr(_) => "jsr",
+            Instruction::Ret(_) => "ret",
+            Instruction::Tableswitch { .. } => "tableswitch",
+            Instruction::Lookupswitch { .. } => "lookupswitch",
+            Instruction::Ireturn => "ireturn",
+            Instruction::Lreturn => "lreturn",
+            Instruction::Freturn => "freturn",
+            Instruction::Dreturn => "dreturn",
+            Instruction::Areturn => "areturn",
+            Instruction::Return => "return",
+            Instruction::Getstatic(_) => "getstatic",
+            Instruction::Putstatic(_) => "putstatic",
+            Instruction::Getfield(_) => "getfield",
+            Instruction::Putfield(_) => "putfield",
+            Instruction::Invokevirtual(_) => "invokevirtual",
+            Instruction::Invokespecial(_) => "invokespecial",
+            Instruction::Invokestatic(_) => "invokestatic",
+            Instruction::Invokeinterface { .. } => "invokeinterface",
+            Instruction::Invokedynamic(_) => "invokedynamic",
+            Instruction::New(_) => "new",
+            Instruction::Newarray(_) => "newarray",
+            Instruction::Anewarray(_) => "anewarray",
+            Instruction::Arraylength => "arraylength",
+            Instruction::Athrow => "athrow",
+            Instruction::Checkcast(_) => "checkcast",
+            Instruction::Instanceof(_) => "instanceof",
+            Instruction::Monitorenter => "monitorenter",
+            Instruction::Monitorexit => "monitorexit",
+            Instruction::Wide(_) => "wide",
+            Instruction::Multianewarray { .. } => "multianewarray",
+            Instruction::Ifnull(_) => "ifnull",
+            Instruction::Ifnonnull(_) => "ifnonnull",
+            Instruction::GotoW(_) => "goto_w",
+            Instruction::JsrW(_) => "jsr_w",
+        }
+    }
+}
+
+/// The operand of the `wide` instruction, which extends the local variable index of the modified
+/// instruction to two bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WideInstruction {
+    Iload(u16),
+    Lload(u16),
+    Fload(u16),
+    Dload(u16),
+    Aload(u16),
+    Istore(u16),
+    Lstore(u16),
+    Fstore(u16),
+    Dstore(u16),
+    Astore(u16),
+    Ret(u16),
+    Iinc { index: u16, value: i16 },
+}
+impl WideInstruction {
+    pub fn opcode(&self) -> u8 {
+        match self {
+            WideInstruction::Iload(_) => 0x15,
+            WideInstruction::Lload(_) => 0x16,
+            WideInstruction::Fload(_) => 0x17,
+            WideInstruction::Dload(_) => 0x18,
+            WideInstruction::Aload(_) => 0x19,
+            WideInstruction::Istore(_) => 0x36,
+            WideInstruction::Lstore(_) => 0x37,
+            WideInstruction::Fstore(_) => 0x38,
+            WideInstruction::Dstore(_) => 0x39,
+            WideInstruction::Astore(_) => 0x3a,
+            WideInstruction::Ret(_) => 0xa9,
+            WideInstruction::Iinc { .. } => 0x84,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            WideInstruction::Iload(_) => "iload",
+            WideInstruction::Lload(_) => "lload",
+            WideInstruction::Fload(_) => "fload",
+            WideInstruction::Dload(_) => "dload",
+            WideInstruction::Aload(_) => "aload",
+            WideInstruction::Istore(_) => "istore",
+            WideInstruction::Lstore(_) => "lstore",
+            WideInstruction::Fstore(_) => "fstore",
+            WideInstruction::Dstore(_) => "dstore",
+            WideInstruction::Astore(_) => "astore",
+            WideInstruction::Ret(_) => "ret",
+            WideInstruction::Iinc { .. } => "iinc",
+        }
+    }
+}
+
+/// The `atype` operand of the `newarray` instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayType {
+    Boolean = 4,
+    Char = 5,
+    Float = 6,
+    Double = 7,
+    Byte = 8,
+    Short = 9,
+    Int = 10,
+    Long = 11,
+}
+impl TryFrom<u8> for ArrayType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            4 => Ok(ArrayType::Boolean),
+            5 => Ok(ArrayType::Char),
+            6 => Ok(ArrayType::Float),
+            7 => Ok(ArrayType::Double),
+            8 => Ok(ArrayType::Byte),
+            9 => Ok(ArrayType::Short),
+            10 => Ok(ArrayType::Int),
+            11 => Ok(ArrayType::Long),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Iterates over the instructions of a method body, yielding each instruction together with its
+/// pc. Iteration stops after the first error.
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    pc: u32,
+}
+impl<'a> Instructions<'a> {
+    pub fn new(code: &'a [u8]) -> Self {
+        Self { code, pc: 0 }
+    }
+}
+impl Iterator for Instructions<'_> {
+    type Item = Result<(u32, Instruction)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pc as usize >= self.code.len() {
+            return None;
+        }
+
+        let pc = self.pc;
+        match Instruction::decode(self.code, pc) {
+            Ok((instruction, next_pc)) => {
+                self.pc = next_pc;
+                Some(Ok((pc, instruction)))
+            }
+            Err(e) => {
+                self.pc = self.code.len() as u32;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl CodeAttribute {
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions::new(&self.code)
+    }
+}
+
+struct CodeReader<'a> {
+    code: &'a [u8],
+    /// The pc of the instruction being read.
+    pc: u32,
+    pos: usize,
+}
+impl CodeReader<'_> {
+    fn read_bytes(&mut self, n: usize) -> Result<&[u8]> {
+        let bytes = self
+            .code
+            .get(self.pos..self.pos + n)
+            .ok_or(ClassFileError::TruncatedInstruction(self.pc))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(BigEndian::read_u16(self.read_bytes(2)?))
+    }
+
+    fn read_i16(&mut self) -> Result<i16> {
+        Ok(BigEndian::read_i16(self.read_bytes(2)?))
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(BigEndian::read_i32(self.read_bytes(4)?))
+    }
+
+    fn read_array_type(&mut self) -> Result<ArrayType> {
+        ArrayType::try_from(self.read_u8()?)
+            .map_err(|_| ClassFileError::InvalidInstruction(self.pc))
+    }
+
+    /// Skips the 0-3 padding bytes that align the operands of a switch to a multiple of four
+    /// bytes from the start of the method.
+    fn skip_padding(&mut self) -> Result<()> {
+        let padding = (4 - self.pos % 4) % 4;
+        self.read_bytes(padding)?;
+        Ok(())
+    }
+
+    fn read_tableswitch(&mut self) -> Result<Instruction> {
+        self.skip_padding()?;
+        let default = self.read_i32()?;
+        let low = self.read_i32()?;
+        let high = self.read_i32()?;
+        if low > high {
+            return Err(ClassFileError::InvalidInstruction(self.pc));
+        }
+        let offsets = (low..=high)
+            .map(|_| self.read_i32())
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Instruction::Tableswitch {
+            default,
+            low,
+            offsets,
+        })
+    }
+
+    fn read_lookupswitch(&mut self) -> Result<Instruction> {
+        self.skip_padding()?;
+        let default = self.read_i32()?;
+        let npairs = self.read_i32()?;
+        if npairs < 0 {
+            return Err(ClassFileError::InvalidInstruction(self.pc));
+        }
+        let pairs = (0..npairs)
+            .map(|_| Ok((self.read_i32()?, self.read_i32()?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Instruction::Lookupswitch { default, pairs })
+    }
+
+    fn read_wide(&mut self) -> Result<WideInstruction> {
+        let opcode = self.read_u8()?;
+        let instruction = match opcode {
+            0x15 => WideInstruction::Iload(self.read_u16()?),
+            0x16 => WideInstruction::Lload(self.read_u16()?),
+            0x17 => WideInstruction::Fload(self.read_u16()?),
+            0x18 => WideInstruction::Dload(self.read_u16()?),
+            0x19 => WideInstruction::Aload(self.read_u16()?),
+            0x36 => WideInstruction::Istore(self.read_u16()?),
+            0x37 => WideInstruction::Lstore(self.read_u16()?),
+            0x38 => WideInstruction::Fstore(self.read_u16()?),
+            0x39 => WideInstruction::Dstore(self.read_u16()?),
+            0x3a => WideInstruction::Astore(self.read_u16()?),
+            0xa9 => WideInstruction::Ret(self.read_u16()?),
+            0x84 => WideInstruction::Iinc {
+                index: self.read_u16()?,
+                value: self.read_i16()?,
+            },
+            _ => return Err(ClassFileError::InvalidOpcode(opcode, self.pc)),
+        };
+
+        Ok(instruction)
+    }
+}
+
+#[cfg(test)]
+mod decode_tests {
+    use super::*;
+
+    fn decode_all(code: &[u8]) -> Result<Vec<(u32, Instruction)>> {
+        Instructions::new(code).collect()
+    }
+
+    #[test]
+    fn it_should_decode_simple_instructions() {
+        assert_eq!(
+            decode_all(&[0x2a, 0x10, 0xff, 0x11, 0x01, 0x00, 0xb1]).unwrap(),
+            vec![
+                (0, Instruction::Aload0),
+                (1, Instruction::Bipush(-1)),
+                (3, Instruction::Sipush(256)),
+                (6, Instruction::Return),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_decode_branch_offsets_as_signed() {
+        assert_eq!(
+            decode_all(&[0x00, 0xa7, 0xff, 0xff]).unwrap()[1],
+            (1, Instruction::Goto(-1))
+        );
+    }
+
+    #[test]
+    fn it_should_skip_tableswitch_padding() {
+        #[rustfmt::skip]
+        let code = [
+            0x00,
+            0xaa, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x20,
+            0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x02,
+            0x00, 0x00, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x18,
+        ];
+
+        assert_eq!(
+            decode_all(&code).unwrap()[1],
+            (
+                1,
+                Instruction::Tableswitch {
+                    default: 0x20,
+                    low: 1,
+                    offsets: vec![0x10, 0x18],
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_decode_lookupswitch() {
+        #[rustfmt::skip]
+        let code = [
+            0xab, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x01,
+            0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x08,
+        ];
+
+        assert_eq!(
+            decode_all(&code).unwrap(),
+            vec![(
+                0,
+                Instruction::Lookupswitch {
+                    default: 0x10,
+                    pairs: vec![(-1, 8)],
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn it_should_decode_wide_instructions() {
+        assert_eq!(
+            decode_all(&[0xc4, 0x15, 0x01, 0x00, 0xc4, 0x84, 0x00, 0x02, 0xff, 0xfe]).unwrap(),
+            vec![
+                (0, Instruction::Wide(WideInstruction::Iload(256))),
+                (
+                    4,
+                    Instruction::Wide(WideInstruction::Iinc {
+                        index: 2,
+                        value: -2
+                    })
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_decode_invokeinterface_and_invokedynamic() {
+        assert_eq!(
+            decode_all(&[0xb9, 0x00, 0x07, 0x02, 0x00, 0xba, 0x00, 0x08, 0x00, 0x00]).unwrap(),
+            vec![
+                (0, Instruction::Invokeinterface { index: 7, count: 2 }),
+                (5, Instruction::Invokedynamic(8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_fail_on_truncated_instructions() {
+        assert!(matches!(
+            decode_all(&[0x00, 0x11, 0x01]),
+            Err(ClassFileError::TruncatedInstruction(1))
+        ));
+    }
+
+    #[test]
+    fn it_should_fail_on_unknown_opcodes() {
+        assert!(matches!(
+            decode_all(&[0x00, 0xca]),
+            Err(ClassFileError::InvalidOpcode(0xca, 1))
+        ));
+        assert!(matches!(
+            decode_all(&[0xc4, 0x10, 0x00, 0x00]),
+            Err(ClassFileError::InvalidOpcode(0x10, 0))
+        ));
+    }
+
+    #[test]
+    fn it_should_fail_on_invalid_newarray_type() {
+        assert!(matches!(
+            decode_all(&[0xbc, 0x03]),
+            Err(ClassFileError::InvalidInstruction(0))
+        ));
+    }
+
+    #[test]
+    fn it_should_stop_after_an_error() {
+        let mut instructions = Instructions::new(&[0xca, 0x00]);
+
+        assert!(instructions.next().unwrap().is_err());
+        assert!(instructions.next().is_none());
+    }
+}
+
+#[cfg(test)]
+mod encode_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_encode_what_it_decodes() {
+        #[rustfmt::skip]
+        let code = [
+            0x2a,
+            0x10, 0xff,
+            0x11, 0x01, 0x00,
+            0xaa, 0x00,
+            0x00, 0x00, 0x00, 0x20,
+            0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x02,
+            0x00, 0x00, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x18,
+            0xab, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x01,
+            0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x08,
+            0xc4, 0x84, 0x00, 0x02, 0xff, 0xfe,
+            0xb9, 0x00, 0x07, 0x02, 0x00,
+            0xba, 0x00, 0x08, 0x00, 0x00,
+            0xa7, 0xff, 0xe6,
+            0xb1,
+        ];
+
+        let mut encoded = vec![];
+        for instruction in Instructions::new(&code) {
+            instruction.unwrap().1.encode(&mut encoded).unwrap();
+        }
+
+        assert_eq!(code.as_slice(), encoded);
+    }
+
+    #[test]
+    fn it_should_not_encode_a_tableswitch_without_a_valid_range() {
+        for (low, offsets) in [(0, vec![]), (i32::MAX, vec![0, 0])] {
+            let instruction = Instruction::Tableswitch {
+                default: 0,
+                low,
+                offsets,
+            };
+
+            let mut code = vec![0];
+            assert!(matches!(
+                instruction.encode(&mut code),
+                Err(ClassFileError::InvalidInstruction(1))
+            ));
+            assert_eq!(vec![0], code);
+        }
+    }
+}
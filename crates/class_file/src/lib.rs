@@ -1,32 +1,43 @@
 // https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html
 
 mod access_flags;
+pub mod annotation;
 pub mod attributes;
+pub mod bootstrap;
+pub mod borrowed;
+pub mod builder;
+pub mod cfg;
 mod class_file;
-#[macro_use]
-mod constant_pool;
+pub mod constant_pool;
+pub mod dataflow;
+pub mod debug_info;
+pub mod descriptor;
+pub mod disassembler;
 mod error;
+pub mod format_check;
+pub mod instruction;
+pub mod module;
+pub mod mutf8;
 mod parser;
+pub mod signature;
+pub mod verifier;
+mod version;
+pub mod visitor;
+mod writer;
 
-use std::fmt;
-
-pub use self::class_file::ClassFile;
-pub use access_flags::AccessFlags;
+pub use self::class_file::{ClassFile, FieldInfo, MethodInfo};
+pub use access_flags::{
+    ClassAccessFlags, ExportsFlags, FieldAccessFlags, InnerClassAccessFlags, MethodAccessFlags,
+    MethodParameterFlags, ModuleFlags, RequiresFlags,
+};
+pub use attributes::{Attribute, AttributeInfo};
 pub use constant_pool::ConstantPool;
+pub use descriptor::{FieldType, MethodDescriptor};
 pub use error::ClassFileError;
+pub use format_check::Diagnostic;
+pub use instruction::Instruction;
 pub use parser::Parser;
+pub use version::ClassFileVersion;
+pub use writer::Writer;
 
 pub type Result<T, E = ClassFileError> = std::result::Result<T, E>;
-
-pub struct Attribute {
-    pub attribute_name_index: u16,
-    pub info: Vec<u8>,
-}
-impl fmt::Debug for Attribute {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Attribute")
-            .field("attribute_name_index", &self.attribute_name_index)
-            .field("info", &format!("({} bytes)", self.info.len()))
-            .finish()
-    }
-}
@@ -0,0 +1,168 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.25
+//
+// The descriptor of a module, read from the Module, ModulePackages and ModuleMainClass attributes
+// of its `module-info.class`. Module names are dotted, e.g. `java.base`, while package and class
+// names are in internal form, e.g. `java/lang` and `java/lang/Object`, as they are in the class
+// file.
+
+use crate::{
+    attributes::{self, AttributeInfo},
+    constant_pool::CpIndex,
+    ClassFile, ConstantPool, ExportsFlags, ModuleFlags, RequiresFlags, Result,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDescriptor {
+    pub name: String,
+    pub flags: ModuleFlags,
+    pub version: Option<String>,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Exports>,
+    /// The service interfaces the module uses.
+    pub uses: Vec<String>,
+    pub provides: Vec<Provides>,
+    /// All the packages of the module, empty if the ModulePackages attribute is absent.
+    pub packages: Vec<String>,
+    pub main_class: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requires {
+    pub module: String,
+    pub flags: RequiresFlags,
+    /// The version of the module at compile time, if it was recorded.
+    pub version: Option<String>,
+}
+
+/// An `exports` or `opens` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exports {
+    pub package: String,
+    pub flags: ExportsFlags,
+    /// The modules the package is exported or opened to, empty if it is to all modules.
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provides {
+    pub service: String,
+    pub implementations: Vec<String>,
+}
+
+impl ModuleDescriptor {
+    pub fn is_open(&self) -> bool {
+        self.flags.contains(ModuleFlags::OPEN)
+    }
+
+    pub fn parse(
+        module: &attributes::ModuleAttribute,
+        packages: &[u16],
+        main_class_index: Option<u16>,
+        constant_pool: &ConstantPool,
+    ) -> Result<Self> {
+        let module_name = |index: u16| -> Result<String> {
+            Ok(constant_pool.module_name(CpIndex::new(index))?.to_owned())
+        };
+        let package_name = |index: u16| -> Result<String> {
+            Ok(constant_pool.package_name(CpIndex::new(index))?.to_owned())
+        };
+        let class_name = |index: u16| -> Result<String> {
+            Ok(constant_pool.class_name(CpIndex::new(index))?.to_owned())
+        };
+        // A version index of zero means that no version information is present.
+        let version = |index: u16| -> Result<Option<String>> {
+            Ok(match index {
+                0 => None,
+                index => Some(constant_pool.utf8(CpIndex::new(index))?.to_owned()),
+            })
+        };
+        let exports = |package: u16, flags: ExportsFlags, targets: &[u16]| -> Result<Exports> {
+            Ok(Exports {
+                package: package_name(package)?,
+                flags,
+                targets: targets
+                    .iter()
+                    .map(|target| module_name(*target))
+                    .collect::<Result<_>>()?,
+            })
+        };
+
+        Ok(Self {
+            name: module_name(module.module_name_index)?,
+            flags: module.module_flags,
+            version: version(module.module_version_index)?,
+            requires: module
+                .requires
+                .iter()
+                .map(|requires| {
+                    Ok(Requires {
+                        module: module_name(requires.requires_index)?,
+                        flags: requires.requires_flags,
+                        version: version(requires.requires_version_index)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            exports: module
+                .exports
+                .iter()
+                .map(|e| exports(e.exports_index, e.exports_flags, &e.exports_to_index))
+                .collect::<Result<_>>()?,
+            opens: module
+                .opens
+                .iter()
+                .map(|o| exports(o.opens_index, o.opens_flags, &o.opens_to_index))
+                .collect::<Result<_>>()?,
+            uses: module
+                .uses_index
+                .iter()
+                .map(|index| class_name(*index))
+                .collect::<Result<_>>()?,
+            provides: module
+                .provides
+                .iter()
+                .map(|provides| {
+                    Ok(Provides {
+                        service: class_name(provides.provides_index)?,
+                        implementations: provides
+                            .provides_with_index
+                            .iter()
+                            .map(|index| class_name(*index))
+                            .collect::<Result<_>>()?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            packages: packages
+                .iter()
+                .map(|index| package_name(*index))
+                .collect::<Result<_>>()?,
+            main_class: main_class_index.map(class_name).transpose()?,
+        })
+    }
+}
+
+impl ClassFile {
+    /// The descriptor of the module declared by this class file, `None` if it has no Module
+    /// attribute.
+    pub fn module_descriptor(&self) -> Result<Option<ModuleDescriptor>> {
+        let Some(module) = self.attributes.find_map(|info| match info {
+            AttributeInfo::Module(module) => Some(module),
+            _ => None,
+        }) else {
+            return Ok(None);
+        };
+        let packages = self
+            .attributes
+            .find_map(|info| match info {
+                AttributeInfo::ModulePackages(packages) => Some(packages.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let main_class_index = self.attributes.find_map(|info| match info {
+            AttributeInfo::ModuleMainClass { main_class_index } => Some(*main_class_index),
+            _ => None,
+        });
+
+        ModuleDescriptor::parse(module, packages, main_class_index, &self.constant_pool).map(Some)
+    }
+}
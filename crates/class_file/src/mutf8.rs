@@ -0,0 +1,181 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.4.7
+//
+// Modified UTF-8 differs from standard UTF-8 in two ways: the null character is encoded with two
+// bytes (`C0 80`) so that encoded strings never contain a zero byte, and supplementary characters
+// are encoded as surrogate pairs, each surrogate taking three bytes.
+
+use std::borrow::Cow;
+
+use crate::{ClassFileError, Result};
+
+/// Decodes a Modified UTF-8 string. Fails on malformed bytes and on unpaired surrogates, which
+/// can be encoded in a class file but not represented in a Rust string.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    decode_with(bytes, |_| Err(ClassFileError::UnpairedSurrogate))
+}
+
+/// Decodes a Modified UTF-8 string, replacing unpaired surrogates with U+FFFD. Fails on malformed
+/// bytes.
+pub fn decode_lossy(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    decode_with(bytes, |units| Ok(String::from_utf16_lossy(units)))
+}
+
+fn decode_with<'a>(
+    bytes: &'a [u8],
+    unpaired_surrogate: impl FnOnce(&[u16]) -> Result<String>,
+) -> Result<Cow<'a, str>> {
+    // Without a zero byte or a four-byte sequence, valid standard UTF-8 only encodes characters of
+    // the Basic Multilingual Plane other than surrogates and the null character, which Modified
+    // UTF-8 encodes the same way.
+    if !bytes.iter().any(|b| *b == 0 || *b >= 0xF0) {
+        if let Ok(s) = std::str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(s));
+        }
+    }
+
+    let units = decode_utf16_units(bytes)?;
+    match String::from_utf16(&units) {
+        Ok(s) => Ok(Cow::Owned(s)),
+        Err(_) => Ok(Cow::Owned(unpaired_surrogate(&units)?)),
+    }
+}
+
+fn decode_utf16_units(bytes: &[u8]) -> Result<Vec<u16>> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let malformed = ClassFileError::InvalidModifiedUtf8(i);
+        let continuation = |offset: usize| match bytes.get(i + offset) {
+            Some(b) if b & 0xC0 == 0x80 => Ok((b & 0x3F) as u16),
+            _ => Err(ClassFileError::InvalidModifiedUtf8(i)),
+        };
+
+        let (unit, length) = match bytes[i] {
+            0x01..=0x7F => (bytes[i] as u16, 1),
+            0xC0..=0xDF => {
+                let unit = ((bytes[i] & 0x1F) as u16) << 6 | continuation(1)?;
+                // Only the null character may use the two byte form for a value below 0x80.
+                if unit != 0 && unit < 0x80 {
+                    return Err(malformed);
+                }
+                (unit, 2)
+            }
+            0xE0..=0xEF => {
+                let unit =
+                    ((bytes[i] & 0x0F) as u16) << 12 | continuation(1)? << 6 | continuation(2)?;
+                if unit < 0x800 {
+                    return Err(malformed);
+                }
+                (unit, 3)
+            }
+            // Zero bytes, stray continuation bytes and four byte forms never appear.
+            _ => return Err(malformed),
+        };
+
+        units.push(unit);
+        i += length;
+    }
+
+    Ok(units)
+}
+
+/// Encodes a string as Modified UTF-8.
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    if s.bytes().all(|b| (0x01..0x80).contains(&b)) {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() + 8);
+    for unit in s.encode_utf16() {
+        match unit {
+            0x01..=0x7F => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | (unit >> 6 & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    Cow::Owned(bytes)
+}
+
+#[cfg(test)]
+mod mutf8_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_borrow_ascii() {
+        assert!(matches!(
+            decode(b"java/lang/Object"),
+            Ok(Cow::Borrowed("java/lang/Object"))
+        ));
+        assert!(matches!(
+            encode("java/lang/Object"),
+            Cow::Borrowed(b"java/lang/Object")
+        ));
+    }
+
+    #[test]
+    fn it_should_borrow_characters_encoded_as_in_utf8() {
+        let s = "\u{e9}t\u{e9} \u{20AC} \u{4E2D}";
+
+        assert!(matches!(decode(s.as_bytes()), Ok(Cow::Borrowed(d)) if d == s));
+    }
+
+    #[test]
+    fn it_should_decode_the_null_character_as_two_bytes() {
+        assert_eq!("a\0b", decode(&[b'a', 0xC0, 0x80, b'b']).unwrap());
+        assert_eq!(vec![b'a', 0xC0, 0x80, b'b'], encode("a\0b").into_owned());
+    }
+
+    #[test]
+    fn it_should_decode_supplementary_characters_as_surrogate_pairs() {
+        let bytes = [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80];
+
+        assert_eq!("\u{1F600}", decode(&bytes).unwrap());
+        assert_eq!(bytes.to_vec(), encode("\u{1F600}").into_owned());
+    }
+
+    #[test]
+    fn it_should_round_trip_multi_byte_characters() {
+        let s = "\u{e9}t\u{e9} \u{20AC} \u{4E2D}";
+
+        assert_eq!(s.as_bytes(), encode(s).as_ref());
+        assert_eq!(s, decode(&encode(s)).unwrap());
+    }
+
+    #[test]
+    fn it_should_fail_on_malformed_bytes() {
+        for bytes in [
+            &[0x00][..],
+            &[0x80],
+            &[0xC3],
+            &[0xE2, 0x82],
+            &[0xF0, 0x9F, 0x98, 0x80],
+            &[0xC1, 0x81],
+            &[0xE0, 0x81, 0x81],
+        ] {
+            assert!(
+                matches!(decode(bytes), Err(ClassFileError::InvalidModifiedUtf8(0))),
+                "{:?}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_replace_unpaired_surrogates_when_lossy() {
+        let bytes = [b'a', 0xED, 0xA0, 0xBD];
+
+        assert!(matches!(
+            decode(&bytes),
+            Err(ClassFileError::UnpairedSurrogate)
+        ));
+        assert_eq!("a\u{FFFD}", decode_lossy(&bytes).unwrap());
+    }
+}
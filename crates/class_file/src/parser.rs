@@ -1,54 +1,126 @@
-use std::io::{BufReader, Read, Seek};
+mod attributes;
+
+use std::{
+    borrow::Cow,
+    io::{BufReader, Cursor, Read, Seek},
+};
 
 use byteorder::{BigEndian, ReadBytesExt};
 
-use crate::{
-    attributes::{Attributes, CodeAttribute, ExceptionTableEntry},
-    class_file::{FieldInfo, MethodInfo},
-};
+use crate::class_file::{FieldInfo, MethodInfo};
 
-use super::{constant_pool::CpInfo, *};
+use super::{
+    constant_pool::{CpIndex, CpInfo},
+    *,
+};
 
 type Result<T, E = ClassFileError> = std::result::Result<T, E>;
 type Endian = BigEndian;
 
+/// Looks up the names of attributes, the only constant pool entries needed to parse a class file.
+pub(crate) trait AttributeNames {
+    fn attribute_name(&self, index: u16) -> Result<Cow<'_, str>>;
+}
+impl AttributeNames for ConstantPool {
+    fn attribute_name(&self, index: u16) -> Result<Cow<'_, str>> {
+        self.utf8(CpIndex::new(index)).map(Cow::Borrowed)
+    }
+}
+
+/// Checks that a class file of the given version is supported, see [`Parser::max_version`].
+pub(crate) fn check_version(
+    major: u16,
+    minor: u16,
+    max_version: Option<ClassFileVersion>,
+) -> Result<ClassFileVersion> {
+    let version = ClassFileVersion::new(major, minor);
+
+    let too_new = matches!(max_version, Some(max) if major > max.major);
+    if too_new || major < ClassFileVersion::OLDEST.major {
+        return Err(ClassFileError::UnsupportedVersion(version));
+    }
+
+    Ok(version)
+}
+
 pub struct Parser<R> {
     r: BufReader<R>,
+    keep_raw_utf8: bool,
+    max_version: Option<ClassFileVersion>,
+}
+impl<'b> Parser<Cursor<&'b [u8]>> {
+    /// Decodes the cp_info structure at the start of `bytes`, returning it together with the
+    /// number of constant pool slots it takes.
+    pub(crate) fn parse_cp_info_from(
+        bytes: &'b [u8],
+        keep_raw_utf8: bool,
+    ) -> Result<(CpInfo, usize)> {
+        // The buffer has no capacity since the bytes are already in memory.
+        let mut parser = Parser {
+            r: BufReader::with_capacity(0, Cursor::new(bytes)),
+            keep_raw_utf8,
+            max_version: None,
+        };
+        parser.parse_cp_info()
+    }
 }
+
 impl<R: Read + Seek> Parser<R> {
     pub fn new(r: R) -> Self {
         Self {
             r: BufReader::new(r),
+            keep_raw_utf8: false,
+            max_version: Some(ClassFileVersion::LATEST),
         }
     }
 
+    /// Rejects class files with a major version greater than that of `max_version`, or any
+    /// version if `None`. Defaults to [`ClassFileVersion::LATEST`]. Class files older than
+    /// [`ClassFileVersion::OLDEST`] are always rejected.
+    pub fn max_version(mut self, max_version: Option<ClassFileVersion>) -> Self {
+        self.max_version = max_version;
+        self
+    }
+
+    /// Keeps CONSTANT_Utf8 entries with unpaired surrogates as [`CpInfo::RawUtf8`] so that they
+    /// are written back unchanged. By default such surrogates are replaced with U+FFFD.
+    pub fn keep_raw_utf8(mut self, keep_raw_utf8: bool) -> Self {
+        self.keep_raw_utf8 = keep_raw_utf8;
+        self
+    }
+
     pub fn parse(&mut self) -> Result<ClassFile> {
-        let _ = self.parse_magic_identifier()?;
-        let _version = self.parse_version()?;
+        self.parse_magic_identifier()?;
+        let ClassFileVersion {
+            major: major_version,
+            minor: minor_version,
+        } = self.parse_version()?;
 
         let constant_pool = self.parse_constant_pool()?;
-        let access_flags = AccessFlags::from_bits_truncate(self.read_u16()?);
-        let this_class = self.read_u16()?;
-        let super_class = self.read_u16()?;
+        let access_flags = ClassAccessFlags::from_bits_retain(self.read_u16()?);
+        let this_class = self.read_cp_index()?;
+        let super_class = self.read_cp_index()?;
         let interfaces_count = self.read_u16()?;
 
-        let mut interfaces = vec![0u16; interfaces_count as usize];
-        self.r.read_u16_into::<Endian>(&mut interfaces)?;
+        let interfaces = (0..interfaces_count)
+            .map(|_| self.read_cp_index())
+            .collect::<Result<_>>()?;
 
         let fields_count = self.read_u16()?;
         let fields = (0..fields_count)
-            .map(|_| self.parse_field_info())
+            .map(|_| self.parse_field_info(&constant_pool))
             .collect::<Result<Vec<_>>>()?;
 
         let methods_count = self.read_u16()?;
         let methods = (0..methods_count)
-            .map(|_| self.parse_method_info())
+            .map(|_| self.parse_method_info(&constant_pool))
             .collect::<Result<Vec<_>>>()?;
 
-        let attributes_count = self.read_u16()?;
-        let attributes = self.parse_attributes(attributes_count)?;
+        let attributes = self.parse_attributes(&constant_pool)?;
 
         Ok(ClassFile {
+            minor_version,
+            major_version,
             constant_pool,
             access_flags,
             this_class,
@@ -60,12 +132,11 @@ impl<R: Read + Seek> Parser<R> {
         })
     }
 
-    fn parse_field_info(&mut self) -> Result<FieldInfo> {
-        let access_flags = AccessFlags::from_bits_truncate(self.read_u16()?);
-        let name_index = self.read_u16()?;
-        let descriptor_index = self.read_u16()?;
-        let attributes_count = self.read_u16()?;
-        let attributes = self.parse_attributes(attributes_count)?;
+    fn parse_field_info(&mut self, constant_pool: &ConstantPool) -> Result<FieldInfo> {
+        let access_flags = FieldAccessFlags::from_bits_retain(self.read_u16()?);
+        let name_index = self.read_cp_index()?;
+        let descriptor_index = self.read_cp_index()?;
+        let attributes = self.parse_attributes(constant_pool)?;
 
         Ok(FieldInfo {
             access_flags,
@@ -75,12 +146,11 @@ impl<R: Read + Seek> Parser<R> {
         })
     }
 
-    fn parse_method_info(&mut self) -> Result<MethodInfo> {
-        let access_flags = AccessFlags::from_bits_truncate(self.read_u16()?);
-        let name_index = self.read_u16()?;
-        let descriptor_index = self.read_u16()?;
-        let attributes_count = self.read_u16()?;
-        let attributes = self.parse_attributes(attributes_count)?;
+    fn parse_method_info(&mut self, constant_pool: &ConstantPool) -> Result<MethodInfo> {
+        let access_flags = MethodAccessFlags::from_bits_retain(self.read_u16()?);
+        let name_index = self.read_cp_index()?;
+        let descriptor_index = self.read_cp_index()?;
+        let attributes = self.parse_attributes(constant_pool)?;
 
         Ok(MethodInfo {
             access_flags,
@@ -97,23 +167,32 @@ impl<R: Read + Seek> Parser<R> {
         }
     }
 
-    fn parse_version(&mut self) -> Result<(u16, u16)> {
+    fn parse_version(&mut self) -> Result<ClassFileVersion> {
         let minor = self.read_u16()?;
         let major = self.read_u16()?;
-        Ok((major, minor))
+
+        check_version(major, minor, self.max_version)
     }
 
     fn parse_constant_pool(&mut self) -> Result<ConstantPool> {
         let constant_pool_count = self.read_u16()?;
 
-        let mut count = constant_pool_count as usize - 1;
+        // The count is one more than the number of slots, and a Long or Double takes up two.
+        let mut count = (constant_pool_count as usize).checked_sub(1).ok_or(
+            ClassFileError::InvalidConstantPoolCount(constant_pool_count),
+        )?;
         let mut res = Vec::with_capacity(count);
         while count > 0 {
             let (cp_info, slot_size) = self.parse_cp_info()?;
             res.push(cp_info);
             (0..slot_size - 1).for_each(|_| res.push(CpInfo::Unusable));
 
-            count -= slot_size;
+            count =
+                count
+                    .checked_sub(slot_size)
+                    .ok_or(ClassFileError::InvalidConstantPoolCount(
+                        constant_pool_count,
+                    ))?;
         }
         Ok(ConstantPool::new(res))
     }
@@ -125,6 +204,7 @@ impl<R: Read + Seek> Parser<R> {
             3 => (self.parse_integer()?, 1),
             4 => (self.parse_float()?, 1),
             5 => (self.parse_long()?, 2),
+            6 => (self.parse_double()?, 2),
             7 => (self.parse_class_info()?, 1),
             8 => (self.parse_string()?, 1),
             9 => (self.parse_field_ref()?, 1),
@@ -133,7 +213,10 @@ impl<R: Read + Seek> Parser<R> {
             12 => (self.parse_name_and_type_info()?, 1),
             15 => (self.parse_method_handle()?, 1),
             16 => (self.parse_method_type_info()?, 1),
+            17 => (self.parse_dynamic_info()?, 1),
             18 => (self.parse_invoke_dynamic_info()?, 1),
+            19 => (self.parse_module_info()?, 1),
+            20 => (self.parse_package_info()?, 1),
             _ => return Err(ClassFileError::InvalidCpInfoTag(tag)),
         };
 
@@ -142,10 +225,18 @@ impl<R: Read + Seek> Parser<R> {
 
     fn parse_utf8(&mut self) -> Result<CpInfo> {
         let length = self.read_u16()?;
-        let mut bytes = vec![0u8; length as usize];
-        self.r.read_exact(&mut bytes)?;
-
-        Ok(CpInfo::Utf8(String::from_utf8_lossy(&bytes).into()))
+        let bytes = self.read_bytes(length as usize)?;
+
+        match mutf8::decode(&bytes) {
+            Ok(s) => Ok(CpInfo::Utf8(s.into_owned())),
+            Err(ClassFileError::UnpairedSurrogate) if self.keep_raw_utf8 => {
+                Ok(CpInfo::RawUtf8(bytes))
+            }
+            Err(ClassFileError::UnpairedSurrogate) => {
+                Ok(CpInfo::Utf8(mutf8::decode_lossy(&bytes)?.into_owned()))
+            }
+            Err(e) => Err(e),
+        }
     }
 
     fn parse_integer(&mut self) -> Result<CpInfo> {
@@ -154,36 +245,14 @@ impl<R: Read + Seek> Parser<R> {
         Ok(CpInfo::Integer(int))
     }
 
-    // https://docs.oracle.com/javase/specs/jvms/se18/html/jvms-4.html#jvms-4.4.4
+    // https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.4.4
     fn parse_float(&mut self) -> Result<CpInfo> {
+        // The bytes are in IEEE 754 binary32 format, so the value is reinterpreted as is. This
+        // covers infinities and subnormals, and keeps the payload of NaNs so that the constant
+        // can be written back unchanged.
         let bits = self.read_u32()?;
 
-        if bits == 0x7f800000 {
-            // If bits is 0x7f800000, the float value will be positive infinity.
-            todo!();
-        } else if bits == 0xff800000 {
-            // If bits is 0xff800000, the float value will be negative infinity.
-            todo!();
-        } else if (0x7f800001..=0x7fffffff).contains(&bits)
-            || (0xff800001..=0xffffffff).contains(&bits)
-        {
-            // If bits is in the range 0x7f800001 through 0x7fffffff or in the range 0xff800001
-            // through 0xffffffff, the float value will be NaN.
-            todo!();
-        }
-
-        //  In all other cases, let s, e, and m be three values that might be computed from bits:
-        let s: i32 = if (bits >> 31) == 0 { 1 } else { -1 };
-        let e: i32 = (bits >> 23) as i32 & 0xff;
-        let m: i32 = if e == 0 {
-            ((bits & 0x7fffff) as i32) << 1
-        } else {
-            (bits & 0x7fffff) as i32 | 0x800000
-        };
-
-        Ok(CpInfo::Float(
-            s as f32 * m as f32 * 2f32.powf(e as f32 - 150.),
-        ))
+        Ok(CpInfo::Float(f32::from_bits(bits)))
     }
 
     fn parse_long(&mut self) -> Result<CpInfo> {
@@ -193,14 +262,22 @@ impl<R: Read + Seek> Parser<R> {
         Ok(CpInfo::Long(((high_bytes as i64) << 32) + low_bytes as i64))
     }
 
+    // https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.4.5
+    fn parse_double(&mut self) -> Result<CpInfo> {
+        // Reinterpreted as is for the same reasons as CONSTANT_Float.
+        let bits = self.r.read_u64::<Endian>()?;
+
+        Ok(CpInfo::Double(f64::from_bits(bits)))
+    }
+
     fn parse_class_info(&mut self) -> Result<CpInfo> {
-        let name_index = self.read_u16()?;
+        let name_index = self.read_cp_index()?;
 
         Ok(CpInfo::Class(constant_pool::ClassInfo { name_index }))
     }
 
     fn parse_string(&mut self) -> Result<CpInfo> {
-        let string_index = self.read_u16()?;
+        let string_index = self.read_cp_index()?;
 
         Ok(CpInfo::String { string_index })
     }
@@ -224,8 +301,8 @@ impl<R: Read + Seek> Parser<R> {
     }
 
     fn parse_name_and_type_info(&mut self) -> Result<CpInfo> {
-        let name_index = self.read_u16()?;
-        let descriptor_index = self.read_u16()?;
+        let name_index = self.read_cp_index()?;
+        let descriptor_index = self.read_cp_index()?;
 
         Ok(CpInfo::NameAndType(constant_pool::NameAndTypeInfo {
             name_index,
@@ -234,8 +311,9 @@ impl<R: Read + Seek> Parser<R> {
     }
 
     fn parse_method_handle(&mut self) -> Result<CpInfo> {
-        let reference_kind = self.read_u8()?;
-        let reference_index = self.read_u16()?;
+        let reference_kind = constant_pool::ReferenceKind::try_from(self.read_u8()?)
+            .map_err(ClassFileError::InvalidReferenceKind)?;
+        let reference_index = self.read_cp_index()?;
 
         Ok(CpInfo::MethodHandle(constant_pool::MethodHandleInfo {
             reference_kind,
@@ -244,7 +322,7 @@ impl<R: Read + Seek> Parser<R> {
     }
 
     fn parse_method_type_info(&mut self) -> Result<CpInfo> {
-        let descriptor_index = self.read_u16()?;
+        let descriptor_index = self.read_cp_index()?;
 
         Ok(CpInfo::MethodType(constant_pool::MethodTypeInfo {
             descriptor_index,
@@ -253,7 +331,7 @@ impl<R: Read + Seek> Parser<R> {
 
     fn parse_invoke_dynamic_info(&mut self) -> Result<CpInfo> {
         let bootstrap_method_attr_index = self.read_u16()?;
-        let name_and_type_index = self.read_u16()?;
+        let name_and_type_index = self.read_cp_index()?;
 
         Ok(CpInfo::InvokeDynamic(constant_pool::InvokeDynamicInfo {
             bootstrap_method_attr_index,
@@ -261,73 +339,38 @@ impl<R: Read + Seek> Parser<R> {
         }))
     }
 
-    fn parse_ref_info(&mut self) -> Result<constant_pool::RefInfo> {
-        let class_index = self.read_u16()?;
-        let name_and_type_index = self.read_u16()?;
+    fn parse_dynamic_info(&mut self) -> Result<CpInfo> {
+        let bootstrap_method_attr_index = self.read_u16()?;
+        let name_and_type_index = self.read_cp_index()?;
 
-        Ok(constant_pool::RefInfo {
-            class_index,
+        Ok(CpInfo::Dynamic(constant_pool::DynamicInfo {
+            bootstrap_method_attr_index,
             name_and_type_index,
-        })
+        }))
     }
 
-    fn parse_attribute(&mut self) -> Result<Attribute> {
-        let attribute_name_index = self.read_u16()?;
-        let attribute_length = self.read_u32()?;
-        let mut info = vec![0u8; attribute_length as usize];
-        self.r.read_exact(&mut info)?;
+    fn parse_module_info(&mut self) -> Result<CpInfo> {
+        let name_index = self.read_cp_index()?;
 
-        Ok(Attribute {
-            attribute_name_index,
-            info,
-        })
+        Ok(CpInfo::Module(constant_pool::ModuleInfo { name_index }))
     }
 
-    pub fn parse_code_attribute(&mut self) -> Result<CodeAttribute> {
-        let max_stack = self.read_u16()?;
-        let max_locals = self.read_u16()?;
-        let code_length = self.read_u32()?;
-        let mut code = vec![0u8; code_length as usize];
-        self.r.read_exact(&mut code)?;
-        let exception_table_length = self.read_u16()?;
-        let exception_table = (0..exception_table_length)
-            .into_iter()
-            .map(|_| self._parse_exception_table_entry())
-            .collect::<Result<Vec<_>>>()?;
-        let attributes_count = self.read_u16()?;
-        let attributes = self.parse_attributes(attributes_count)?;
-
-        Ok(CodeAttribute {
-            max_stack,
-            max_locals,
-            code,
-            exception_table,
-            attributes,
-        })
+    fn parse_package_info(&mut self) -> Result<CpInfo> {
+        let name_index = self.read_cp_index()?;
+
+        Ok(CpInfo::Package(constant_pool::PackageInfo { name_index }))
     }
 
-    fn _parse_exception_table_entry(&mut self) -> Result<ExceptionTableEntry> {
-        let start_pc = self.read_u16()?;
-        let end_pc = self.read_u16()?;
-        let handler_pc = self.read_u16()?;
-        let catch_type = self.read_u16()?;
+    fn parse_ref_info(&mut self) -> Result<constant_pool::RefInfo> {
+        let class_index = self.read_cp_index()?;
+        let name_and_type_index = self.read_cp_index()?;
 
-        Ok(ExceptionTableEntry {
-            start_pc,
-            end_pc,
-            handler_pc,
-            catch_type,
+        Ok(constant_pool::RefInfo {
+            class_index,
+            name_and_type_index,
         })
     }
 
-    fn parse_attributes(&mut self, attributes_count: u16) -> Result<Attributes> {
-        (0..attributes_count)
-            .into_iter()
-            .map(|_| self.parse_attribute())
-            .collect::<Result<Vec<_>>>()
-            .map(Attributes)
-    }
-
     fn read_u32(&mut self) -> Result<u32> {
         Ok(self.r.read_u32::<Endian>()?)
     }
@@ -340,6 +383,23 @@ impl<R: Read + Seek> Parser<R> {
         Ok(self.r.read_u8()?)
     }
 
+    fn read_cp_index<T: ?Sized>(&mut self) -> Result<CpIndex<T>> {
+        self.read_u16().map(CpIndex::new)
+    }
+
+    fn read_u16_vec(&mut self) -> Result<Vec<u16>> {
+        let count = self.read_u16()?;
+        let mut v = vec![0u16; count as usize];
+        self.r.read_u16_into::<Endian>(&mut v)?;
+        Ok(v)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; length];
+        self.r.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
     fn read_i32(&mut self) -> Result<i32> {
         Ok(self.r.read_i32::<Endian>()?)
     }
@@ -0,0 +1,584 @@
+use std::io::{BufReader, Cursor, Read, Seek};
+
+use crate::{
+    attributes::{
+        Annotation, Attribute, AttributeInfo, Attributes, BootstrapMethod, CodeAttribute,
+        ElementValue, ElementValuePair, EnclosingMethodAttribute, ExceptionTableEntry, Exports,
+        InnerClass, LineNumberTableEntry, LocalVariableTableEntry, LocalVariableTypeTableEntry,
+        LocalvarTargetEntry, MethodParameter, ModuleAttribute, Opens, Provides, RecordComponent,
+        Requires, StackMapFrame, TargetInfo, TypeAnnotation, TypePathEntry, VerificationTypeInfo,
+    },
+    ClassFileError, ExportsFlags, InnerClassAccessFlags, MethodParameterFlags, ModuleFlags,
+    RequiresFlags,
+};
+
+use super::{AttributeNames, Parser, Result};
+
+impl<'b> Parser<Cursor<&'b [u8]>> {
+    /// Decodes the `info` of an attribute named `name`, or returns `None` if the attribute is not
+    /// one of the attributes defined by the JVMS. The body is decoded by a parser of its own so
+    /// that the attribute_length can be checked against what was actually consumed.
+    pub(crate) fn parse_attribute_body(
+        name: &str,
+        info: &'b [u8],
+        constant_pool: &dyn AttributeNames,
+        keep_raw_utf8: bool,
+    ) -> Result<Option<AttributeInfo>> {
+        // The buffer has no capacity since the bytes are already in memory.
+        let mut parser = Parser {
+            r: BufReader::with_capacity(0, Cursor::new(info)),
+            keep_raw_utf8,
+            max_version: None,
+        };
+        let decoded = parser.parse_attribute_info(name, constant_pool)?;
+        if decoded.is_some() && parser.r.get_ref().position() != info.len() as u64 {
+            return Err(ClassFileError::InvalidAttributeLength(name.to_owned()));
+        }
+        Ok(decoded)
+    }
+}
+
+impl<R: Read + Seek> Parser<R> {
+    pub(super) fn parse_attributes(
+        &mut self,
+        constant_pool: &dyn AttributeNames,
+    ) -> Result<Attributes> {
+        let attributes_count = self.read_u16()?;
+        (0..attributes_count)
+            .map(|_| self.parse_attribute(constant_pool))
+            .collect::<Result<Vec<_>>>()
+            .map(Attributes)
+    }
+
+    fn parse_attribute(&mut self, constant_pool: &dyn AttributeNames) -> Result<Attribute> {
+        let attribute_name_index = self.read_u16()?;
+        let attribute_length = self.read_u32()?;
+        let info = self.read_bytes(attribute_length as usize)?;
+        let name = constant_pool.attribute_name(attribute_name_index)?;
+        let decoded =
+            Parser::parse_attribute_body(&name, &info, constant_pool, self.keep_raw_utf8)?;
+        let info = decoded.unwrap_or(AttributeInfo::Unknown(info));
+
+        Ok(Attribute {
+            attribute_name_index,
+            info,
+        })
+    }
+
+    /// Returns `None` if the attribute is not one of the attributes defined by the JVMS.
+    fn parse_attribute_info(
+        &mut self,
+        name: &str,
+        constant_pool: &dyn AttributeNames,
+    ) -> Result<Option<AttributeInfo>> {
+        let info = match name {
+            "ConstantValue" => AttributeInfo::ConstantValue {
+                constantvalue_index: self.read_u16()?,
+            },
+            "Code" => AttributeInfo::Code(self.parse_code_attribute(constant_pool)?),
+            "StackMapTable" => {
+                let number_of_entries = self.read_u16()?;
+                AttributeInfo::StackMapTable(
+                    (0..number_of_entries)
+                        .map(|_| self.parse_stack_map_frame())
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "Exceptions" => AttributeInfo::Exceptions(self.read_u16_vec()?),
+            "InnerClasses" => {
+                let number_of_classes = self.read_u16()?;
+                AttributeInfo::InnerClasses(
+                    (0..number_of_classes)
+                        .map(|_| self.parse_inner_class())
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "EnclosingMethod" => AttributeInfo::EnclosingMethod(EnclosingMethodAttribute {
+                class_index: self.read_u16()?,
+                method_index: self.read_u16()?,
+            }),
+            "Synthetic" => AttributeInfo::Synthetic,
+            "Signature" => AttributeInfo::Signature {
+                signature_index: self.read_u16()?,
+            },
+            "SourceFile" => AttributeInfo::SourceFile {
+                sourcefile_index: self.read_u16()?,
+            },
+            "SourceDebugExtension" => {
+                let mut debug_extension = vec![];
+                self.r.read_to_end(&mut debug_extension)?;
+                AttributeInfo::SourceDebugExtension(debug_extension)
+            }
+            "LineNumberTable" => {
+                let line_number_table_length = self.read_u16()?;
+                AttributeInfo::LineNumberTable(
+                    (0..line_number_table_length)
+                        .map(|_| {
+                            Ok(LineNumberTableEntry {
+                                start_pc: self.read_u16()?,
+                                line_number: self.read_u16()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "LocalVariableTable" => {
+                let local_variable_table_length = self.read_u16()?;
+                AttributeInfo::LocalVariableTable(
+                    (0..local_variable_table_length)
+                        .map(|_| {
+                            Ok(LocalVariableTableEntry {
+                                start_pc: self.read_u16()?,
+                                length: self.read_u16()?,
+                                name_index: self.read_u16()?,
+                                descriptor_index: self.read_u16()?,
+                                index: self.read_u16()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "LocalVariableTypeTable" => {
+                let local_variable_type_table_length = self.read_u16()?;
+                AttributeInfo::LocalVariableTypeTable(
+                    (0..local_variable_type_table_length)
+                        .map(|_| {
+                            Ok(LocalVariableTypeTableEntry {
+                                start_pc: self.read_u16()?,
+                                length: self.read_u16()?,
+                                name_index: self.read_u16()?,
+                                signature_index: self.read_u16()?,
+                                index: self.read_u16()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "Deprecated" => AttributeInfo::Deprecated,
+            "RuntimeVisibleAnnotations" => {
+                AttributeInfo::RuntimeVisibleAnnotations(self.parse_annotations()?)
+            }
+            "RuntimeInvisibleAnnotations" => {
+                AttributeInfo::RuntimeInvisibleAnnotations(self.parse_annotations()?)
+            }
+            "RuntimeVisibleParameterAnnotations" => {
+                AttributeInfo::RuntimeVisibleParameterAnnotations(
+                    self.parse_parameter_annotations()?,
+                )
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                AttributeInfo::RuntimeInvisibleParameterAnnotations(
+                    self.parse_parameter_annotations()?,
+                )
+            }
+            "RuntimeVisibleTypeAnnotations" => {
+                AttributeInfo::RuntimeVisibleTypeAnnotations(self.parse_type_annotations()?)
+            }
+            "RuntimeInvisibleTypeAnnotations" => {
+                AttributeInfo::RuntimeInvisibleTypeAnnotations(self.parse_type_annotations()?)
+            }
+            "AnnotationDefault" => AttributeInfo::AnnotationDefault(self.parse_element_value()?),
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = self.read_u16()?;
+                AttributeInfo::BootstrapMethods(
+                    (0..num_bootstrap_methods)
+                        .map(|_| {
+                            Ok(BootstrapMethod {
+                                bootstrap_method_ref: self.read_u16()?,
+                                bootstrap_arguments: self.read_u16_vec()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "MethodParameters" => {
+                let parameters_count = self.read_u8()?;
+                AttributeInfo::MethodParameters(
+                    (0..parameters_count)
+                        .map(|_| {
+                            Ok(MethodParameter {
+                                name_index: self.read_u16()?,
+                                access_flags: MethodParameterFlags::from_bits_retain(
+                                    self.read_u16()?,
+                                ),
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "Module" => AttributeInfo::Module(self.parse_module_attribute()?),
+            "ModulePackages" => AttributeInfo::ModulePackages(self.read_u16_vec()?),
+            "ModuleMainClass" => AttributeInfo::ModuleMainClass {
+                main_class_index: self.read_u16()?,
+            },
+            "NestHost" => AttributeInfo::NestHost {
+                host_class_index: self.read_u16()?,
+            },
+            "NestMembers" => AttributeInfo::NestMembers(self.read_u16_vec()?),
+            "Record" => {
+                let components_count = self.read_u16()?;
+                AttributeInfo::Record(
+                    (0..components_count)
+                        .map(|_| {
+                            Ok(RecordComponent {
+                                name_index: self.read_u16()?,
+                                descriptor_index: self.read_u16()?,
+                                attributes: self.parse_attributes(constant_pool)?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "PermittedSubclasses" => AttributeInfo::PermittedSubclasses(self.read_u16_vec()?),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(info))
+    }
+
+    fn parse_code_attribute(
+        &mut self,
+        constant_pool: &dyn AttributeNames,
+    ) -> Result<CodeAttribute> {
+        let max_stack = self.read_u16()?;
+        let max_locals = self.read_u16()?;
+        let code_length = self.read_u32()?;
+        let code = self.read_bytes(code_length as usize)?;
+        let exception_table_length = self.read_u16()?;
+        let exception_table = (0..exception_table_length)
+            .map(|_| self.parse_exception_table_entry())
+            .collect::<Result<Vec<_>>>()?;
+        let attributes = self.parse_attributes(constant_pool)?;
+
+        Ok(CodeAttribute {
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
+        })
+    }
+
+    fn parse_exception_table_entry(&mut self) -> Result<ExceptionTableEntry> {
+        let start_pc = self.read_u16()?;
+        let end_pc = self.read_u16()?;
+        let handler_pc = self.read_u16()?;
+        let catch_type = self.read_u16()?;
+
+        Ok(ExceptionTableEntry {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+        })
+    }
+
+    fn parse_stack_map_frame(&mut self) -> Result<StackMapFrame> {
+        let frame_type = self.read_u8()?;
+        let frame = match frame_type {
+            0..=63 => StackMapFrame::Same {
+                offset_delta: frame_type as u16,
+            },
+            64..=127 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: frame_type as u16 - 64,
+                stack: self.parse_verification_type_info()?,
+            },
+            247 => StackMapFrame::SameLocals1StackItemExtended {
+                offset_delta: self.read_u16()?,
+                stack: self.parse_verification_type_info()?,
+            },
+            248..=250 => StackMapFrame::Chop {
+                k: 251 - frame_type,
+                offset_delta: self.read_u16()?,
+            },
+            251 => StackMapFrame::SameExtended {
+                offset_delta: self.read_u16()?,
+            },
+            252..=254 => StackMapFrame::Append {
+                offset_delta: self.read_u16()?,
+                locals: (0..frame_type - 251)
+                    .map(|_| self.parse_verification_type_info())
+                    .collect::<Result<Vec<_>>>()?,
+            },
+            255 => {
+                let offset_delta = self.read_u16()?;
+                let number_of_locals = self.read_u16()?;
+                let locals = (0..number_of_locals)
+                    .map(|_| self.parse_verification_type_info())
+                    .collect::<Result<Vec<_>>>()?;
+                let number_of_stack_items = self.read_u16()?;
+                let stack = (0..number_of_stack_items)
+                    .map(|_| self.parse_verification_type_info())
+                    .collect::<Result<Vec<_>>>()?;
+                StackMapFrame::Full {
+                    offset_delta,
+                    locals,
+                    stack,
+                }
+            }
+            _ => return Err(ClassFileError::InvalidStackMapFrameType(frame_type)),
+        };
+
+        Ok(frame)
+    }
+
+    fn parse_verification_type_info(&mut self) -> Result<VerificationTypeInfo> {
+        let tag = self.read_u8()?;
+        let info = match tag {
+            0 => VerificationTypeInfo::Top,
+            1 => VerificationTypeInfo::Integer,
+            2 => VerificationTypeInfo::Float,
+            3 => VerificationTypeInfo::Double,
+            4 => VerificationTypeInfo::Long,
+            5 => VerificationTypeInfo::Null,
+            6 => VerificationTypeInfo::UninitializedThis,
+            7 => VerificationTypeInfo::Object {
+                cpool_index: self.read_u16()?,
+            },
+            8 => VerificationTypeInfo::Uninitialized {
+                offset: self.read_u16()?,
+            },
+            _ => return Err(ClassFileError::InvalidVerificationTypeTag(tag)),
+        };
+
+        Ok(info)
+    }
+
+    fn parse_inner_class(&mut self) -> Result<InnerClass> {
+        let inner_class_info_index = self.read_u16()?;
+        let outer_class_info_index = self.read_u16()?;
+        let inner_name_index = self.read_u16()?;
+        let inner_class_access_flags = InnerClassAccessFlags::from_bits_retain(self.read_u16()?);
+
+        Ok(InnerClass {
+            inner_class_info_index,
+            outer_class_info_index,
+            inner_name_index,
+            inner_class_access_flags,
+        })
+    }
+
+    fn parse_annotations(&mut self) -> Result<Vec<Annotation>> {
+        let num_annotations = self.read_u16()?;
+        (0..num_annotations)
+            .map(|_| self.parse_annotation())
+            .collect()
+    }
+
+    fn parse_parameter_annotations(&mut self) -> Result<Vec<Vec<Annotation>>> {
+        let num_parameters = self.read_u8()?;
+        (0..num_parameters)
+            .map(|_| self.parse_annotations())
+            .collect()
+    }
+
+    fn parse_annotation(&mut self) -> Result<Annotation> {
+        let type_index = self.read_u16()?;
+        let element_value_pairs = self.parse_element_value_pairs()?;
+
+        Ok(Annotation {
+            type_index,
+            element_value_pairs,
+        })
+    }
+
+    fn parse_element_value_pairs(&mut self) -> Result<Vec<ElementValuePair>> {
+        let num_element_value_pairs = self.read_u16()?;
+        (0..num_element_value_pairs)
+            .map(|_| {
+                Ok(ElementValuePair {
+                    element_name_index: self.read_u16()?,
+                    value: self.parse_element_value()?,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_element_value(&mut self) -> Result<ElementValue> {
+        let tag = self.read_u8()?;
+        let value = match tag {
+            b'B' => ElementValue::Byte {
+                const_value_index: self.read_u16()?,
+            },
+            b'C' => ElementValue::Char {
+                const_value_index: self.read_u16()?,
+            },
+            b'D' => ElementValue::Double {
+                const_value_index: self.read_u16()?,
+            },
+            b'F' => ElementValue::Float {
+                const_value_index: self.read_u16()?,
+            },
+            b'I' => ElementValue::Int {
+                const_value_index: self.read_u16()?,
+            },
+            b'J' => ElementValue::Long {
+                const_value_index: self.read_u16()?,
+            },
+            b'S' => ElementValue::Short {
+                const_value_index: self.read_u16()?,
+            },
+            b'Z' => ElementValue::Boolean {
+                const_value_index: self.read_u16()?,
+            },
+            b's' => ElementValue::String {
+                const_value_index: self.read_u16()?,
+            },
+            b'e' => ElementValue::Enum {
+                type_name_index: self.read_u16()?,
+                const_name_index: self.read_u16()?,
+            },
+            b'c' => ElementValue::Class {
+                class_info_index: self.read_u16()?,
+            },
+            b'@' => ElementValue::Annotation(self.parse_annotation()?),
+            b'[' => {
+                let num_values = self.read_u16()?;
+                ElementValue::Array(
+                    (0..num_values)
+                        .map(|_| self.parse_element_value())
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            _ => return Err(ClassFileError::InvalidElementValueTag(tag)),
+        };
+
+        Ok(value)
+    }
+
+    fn parse_type_annotations(&mut self) -> Result<Vec<TypeAnnotation>> {
+        let num_annotations = self.read_u16()?;
+        (0..num_annotations)
+            .map(|_| self.parse_type_annotation())
+            .collect()
+    }
+
+    fn parse_type_annotation(&mut self) -> Result<TypeAnnotation> {
+        let target_type = self.read_u8()?;
+        let target_info = match target_type {
+            0x00 | 0x01 => TargetInfo::TypeParameter {
+                type_parameter_index: self.read_u8()?,
+            },
+            0x10 => TargetInfo::Supertype {
+                supertype_index: self.read_u16()?,
+            },
+            0x11 | 0x12 => TargetInfo::TypeParameterBound {
+                type_parameter_index: self.read_u8()?,
+                bound_index: self.read_u8()?,
+            },
+            0x13..=0x15 => TargetInfo::Empty,
+            0x16 => TargetInfo::FormalParameter {
+                formal_parameter_index: self.read_u8()?,
+            },
+            0x17 => TargetInfo::Throws {
+                throws_type_index: self.read_u16()?,
+            },
+            0x40 | 0x41 => {
+                let table_length = self.read_u16()?;
+                TargetInfo::Localvar(
+                    (0..table_length)
+                        .map(|_| {
+                            Ok(LocalvarTargetEntry {
+                                start_pc: self.read_u16()?,
+                                length: self.read_u16()?,
+                                index: self.read_u16()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            0x42 => TargetInfo::Catch {
+                exception_table_index: self.read_u16()?,
+            },
+            0x43..=0x46 => TargetInfo::Offset {
+                offset: self.read_u16()?,
+            },
+            0x47..=0x4B => TargetInfo::TypeArgument {
+                offset: self.read_u16()?,
+                type_argument_index: self.read_u8()?,
+            },
+            _ => return Err(ClassFileError::InvalidTargetType(target_type)),
+        };
+        let path_length = self.read_u8()?;
+        let target_path = (0..path_length)
+            .map(|_| {
+                Ok(TypePathEntry {
+                    type_path_kind: self.read_u8()?,
+                    type_argument_index: self.read_u8()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let type_index = self.read_u16()?;
+        let element_value_pairs = self.parse_element_value_pairs()?;
+
+        Ok(TypeAnnotation {
+            target_type,
+            target_info,
+            target_path,
+            type_index,
+            element_value_pairs,
+        })
+    }
+
+    fn parse_module_attribute(&mut self) -> Result<ModuleAttribute> {
+        let module_name_index = self.read_u16()?;
+        let module_flags = ModuleFlags::from_bits_retain(self.read_u16()?);
+        let module_version_index = self.read_u16()?;
+
+        let requires_count = self.read_u16()?;
+        let requires = (0..requires_count)
+            .map(|_| {
+                Ok(Requires {
+                    requires_index: self.read_u16()?,
+                    requires_flags: RequiresFlags::from_bits_retain(self.read_u16()?),
+                    requires_version_index: self.read_u16()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let exports_count = self.read_u16()?;
+        let exports = (0..exports_count)
+            .map(|_| {
+                Ok(Exports {
+                    exports_index: self.read_u16()?,
+                    exports_flags: ExportsFlags::from_bits_retain(self.read_u16()?),
+                    exports_to_index: self.read_u16_vec()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let opens_count = self.read_u16()?;
+        let opens = (0..opens_count)
+            .map(|_| {
+                Ok(Opens {
+                    opens_index: self.read_u16()?,
+                    opens_flags: ExportsFlags::from_bits_retain(self.read_u16()?),
+                    opens_to_index: self.read_u16_vec()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let uses_index = self.read_u16_vec()?;
+
+        let provides_count = self.read_u16()?;
+        let provides = (0..provides_count)
+            .map(|_| {
+                Ok(Provides {
+                    provides_index: self.read_u16()?,
+                    provides_with_index: self.read_u16_vec()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ModuleAttribute {
+            module_name_index,
+            module_flags,
+            module_version_index,
+            requires,
+            exports,
+            opens,
+            uses_index,
+            provides,
+        })
+    }
+}
@@ -0,0 +1,674 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.9.1
+//
+// Signatures are displayed in Java source syntax with fully qualified class names, e.g.
+// `java.util.Map<K, ? extends java.util.List<V>>`. The alternate form (`{:#}`) leaves out package
+// names, e.g. `Map<K, ? extends List<V>>`.
+
+use std::fmt;
+
+use crate::{
+    class_file::{FieldInfo, MethodInfo},
+    constant_pool::CpIndex,
+    descriptor::BaseType,
+    ClassFile, ClassFileError, Result,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JavaTypeSignature {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReferenceTypeSignature {
+    Class(ClassTypeSignature),
+    TypeVariable(String),
+    Array(Box<JavaTypeSignature>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassTypeSignature {
+    /// The package in internal form, e.g. `java/util`. Empty for the unnamed package.
+    pub package: String,
+    pub class: SimpleClassTypeSignature,
+    /// Inner classes, outermost first.
+    pub inner: Vec<SimpleClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeArgument {
+    /// The unbounded wildcard `?`.
+    Any,
+    Exact(ReferenceTypeSignature),
+    Extends(ReferenceTypeSignature),
+    Super(ReferenceTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeParameter {
+    pub name: String,
+    pub class_bound: Option<ReferenceTypeSignature>,
+    pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub superclass: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub params: Vec<JavaTypeSignature>,
+    /// `None` if the method returns `void`.
+    pub return_type: Option<JavaTypeSignature>,
+    /// Either class types or type variables.
+    pub throws: Vec<ReferenceTypeSignature>,
+}
+
+pub type FieldSignature = ReferenceTypeSignature;
+
+impl ClassSignature {
+    pub fn parse(signature: &str) -> Result<Self> {
+        let mut r = SignatureReader::new(signature);
+        let type_parameters = r.read_type_parameters()?;
+        let superclass = r.read_class_type()?;
+        let mut interfaces = vec![];
+        while !r.is_at_end() {
+            interfaces.push(r.read_class_type()?);
+        }
+
+        Ok(ClassSignature {
+            type_parameters,
+            superclass,
+            interfaces,
+        })
+    }
+}
+
+impl MethodSignature {
+    pub fn parse(signature: &str) -> Result<Self> {
+        let mut r = SignatureReader::new(signature);
+        let type_parameters = r.read_type_parameters()?;
+        r.expect('(')?;
+        let mut params = vec![];
+        while !r.eat(')') {
+            params.push(r.read_java_type()?);
+        }
+        let return_type = if r.eat('V') {
+            None
+        } else {
+            Some(r.read_java_type()?)
+        };
+        let mut throws = vec![];
+        while r.eat('^') {
+            throws.push(match r.peek() {
+                Some('L') => ReferenceTypeSignature::Class(r.read_class_type()?),
+                Some('T') => r.read_reference_type()?,
+                _ => return Err(r.error()),
+            });
+        }
+        r.expect_end()?;
+
+        Ok(MethodSignature {
+            type_parameters,
+            params,
+            return_type,
+            throws,
+        })
+    }
+}
+
+impl ReferenceTypeSignature {
+    /// Parses a field signature.
+    pub fn parse(signature: &str) -> Result<Self> {
+        let mut r = SignatureReader::new(signature);
+        let reference_type = r.read_reference_type()?;
+        r.expect_end()?;
+        Ok(reference_type)
+    }
+}
+
+impl ClassFile {
+    pub fn class_signature(&self) -> Result<Option<ClassSignature>> {
+        self.signature(self.attributes.signature_index(), ClassSignature::parse)
+    }
+
+    pub fn field_signature(&self, field: &FieldInfo) -> Result<Option<FieldSignature>> {
+        self.signature(field.attributes.signature_index(), FieldSignature::parse)
+    }
+
+    pub fn method_signature(&self, method: &MethodInfo) -> Result<Option<MethodSignature>> {
+        self.signature(method.attributes.signature_index(), MethodSignature::parse)
+    }
+
+    fn signature<T>(
+        &self,
+        signature_index: Option<u16>,
+        parse: impl FnOnce(&str) -> Result<T>,
+    ) -> Result<Option<T>> {
+        signature_index
+            .map(|index| parse(self.constant_pool.utf8(CpIndex::new(index))?))
+            .transpose()
+    }
+}
+
+struct SignatureReader<'a> {
+    signature: &'a str,
+    rest: &'a str,
+}
+impl<'a> SignatureReader<'a> {
+    fn new(signature: &'a str) -> Self {
+        Self {
+            signature,
+            rest: signature,
+        }
+    }
+
+    fn error(&self) -> ClassFileError {
+        ClassFileError::InvalidSignature(self.signature.to_owned())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    /// Consumes `c` if it is the next character.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn read_identifier(&mut self) -> Result<String> {
+        let end = self
+            .rest
+            .find(['.', ';', '[', '/', '<', '>', ':'])
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(self.error());
+        }
+
+        let (identifier, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(identifier.to_owned())
+    }
+
+    fn read_type_parameters(&mut self) -> Result<Vec<TypeParameter>> {
+        let mut type_parameters = vec![];
+        if !self.eat('<') {
+            return Ok(type_parameters);
+        }
+
+        loop {
+            let name = self.read_identifier()?;
+            self.expect(':')?;
+            let class_bound = match self.peek() {
+                Some(':') => None,
+                _ => Some(self.read_reference_type()?),
+            };
+            let mut interface_bounds = vec![];
+            while self.eat(':') {
+                interface_bounds.push(self.read_reference_type()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+
+            if self.eat('>') {
+                return Ok(type_parameters);
+            }
+        }
+    }
+
+    fn read_java_type(&mut self) -> Result<JavaTypeSignature> {
+        match self.peek().and_then(BaseType::from_char) {
+            Some(base_type) => {
+                self.next();
+                Ok(JavaTypeSignature::Base(base_type))
+            }
+            None => Ok(JavaTypeSignature::Reference(self.read_reference_type()?)),
+        }
+    }
+
+    fn read_reference_type(&mut self) -> Result<ReferenceTypeSignature> {
+        match self.peek() {
+            Some('L') => Ok(ReferenceTypeSignature::Class(self.read_class_type()?)),
+            Some('T') => {
+                self.next();
+                let name = self.read_identifier()?;
+                self.expect(';')?;
+                Ok(ReferenceTypeSignature::TypeVariable(name))
+            }
+            Some('[') => {
+                self.next();
+                Ok(ReferenceTypeSignature::Array(Box::new(
+                    self.read_java_type()?,
+                )))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn read_class_type(&mut self) -> Result<ClassTypeSignature> {
+        self.expect('L')?;
+
+        let mut package = String::new();
+        let mut name = self.read_identifier()?;
+        while self.eat('/') {
+            if !package.is_empty() {
+                package.push('/');
+            }
+            package.push_str(&name);
+            name = self.read_identifier()?;
+        }
+        let class = self.read_simple_class_type(name)?;
+
+        let mut inner = vec![];
+        while self.eat('.') {
+            let name = self.read_identifier()?;
+            inner.push(self.read_simple_class_type(name)?);
+        }
+        self.expect(';')?;
+
+        Ok(ClassTypeSignature {
+            package,
+            class,
+            inner,
+        })
+    }
+
+    fn read_simple_class_type(&mut self, name: String) -> Result<SimpleClassTypeSignature> {
+        let mut type_arguments = vec![];
+        if self.eat('<') {
+            while !self.eat('>') {
+                type_arguments.push(self.read_type_argument()?);
+            }
+            if type_arguments.is_empty() {
+                return Err(self.error());
+            }
+        }
+
+        Ok(SimpleClassTypeSignature {
+            name,
+            type_arguments,
+        })
+    }
+
+    fn read_type_argument(&mut self) -> Result<TypeArgument> {
+        Ok(match self.peek() {
+            Some('*') => {
+                self.next();
+                TypeArgument::Any
+            }
+            Some('+') => {
+                self.next();
+                TypeArgument::Extends(self.read_reference_type()?)
+            }
+            Some('-') => {
+                self.next();
+                TypeArgument::Super(self.read_reference_type()?)
+            }
+            _ => TypeArgument::Exact(self.read_reference_type()?),
+        })
+    }
+}
+
+/// Writes `value`, propagating the alternate flag.
+fn write_nested(f: &mut fmt::Formatter<'_>, value: &impl fmt::Display) -> fmt::Result {
+    if f.alternate() {
+        write!(f, "{:#}", value)
+    } else {
+        write!(f, "{}", value)
+    }
+}
+
+fn write_separated<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    values: &[T],
+    separator: &str,
+) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        write_nested(f, value)?;
+    }
+    Ok(())
+}
+
+fn write_type_parameters(
+    f: &mut fmt::Formatter<'_>,
+    type_parameters: &[TypeParameter],
+) -> fmt::Result {
+    if !type_parameters.is_empty() {
+        f.write_str("<")?;
+        write_separated(f, type_parameters, ", ")?;
+        f.write_str(">")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for JavaTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JavaTypeSignature::Base(base_type) => f.write_str(base_type.java_name()),
+            JavaTypeSignature::Reference(reference_type) => write_nested(f, reference_type),
+        }
+    }
+}
+
+impl fmt::Display for ReferenceTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceTypeSignature::Class(class_type) => write_nested(f, class_type),
+            ReferenceTypeSignature::TypeVariable(name) => f.write_str(name),
+            ReferenceTypeSignature::Array(component_type) => {
+                write_nested(f, component_type.as_ref())?;
+                f.write_str("[]")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() && !self.package.is_empty() {
+            write!(f, "{}.", self.package.replace('/', "."))?;
+        }
+        write_nested(f, &self.class)?;
+        for inner in &self.inner {
+            f.write_str(".")?;
+            write_nested(f, inner)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SimpleClassTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.type_arguments.is_empty() {
+            f.write_str("<")?;
+            write_separated(f, &self.type_arguments, ", ")?;
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TypeArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeArgument::Any => f.write_str("?"),
+            TypeArgument::Exact(t) => write_nested(f, t),
+            TypeArgument::Extends(t) => {
+                f.write_str("? extends ")?;
+                write_nested(f, t)
+            }
+            TypeArgument::Super(t) => {
+                f.write_str("? super ")?;
+                write_nested(f, t)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TypeParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+
+        // A lone Object bound is what `<T>` compiles to, so it is left out like in source.
+        let bounds = self
+            .class_bound
+            .iter()
+            .chain(&self.interface_bounds)
+            .filter(|bound| !is_object(bound) || !self.interface_bounds.is_empty())
+            .collect::<Vec<_>>();
+        if !bounds.is_empty() {
+            f.write_str(" extends ")?;
+            write_separated(f, &bounds, " & ")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ClassSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        if !self.type_parameters.is_empty() {
+            f.write_str(" ")?;
+        }
+        f.write_str("extends ")?;
+        write_nested(f, &self.superclass)?;
+        if !self.interfaces.is_empty() {
+            f.write_str(" implements ")?;
+            write_separated(f, &self.interfaces, ", ")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_type_parameters(f, &self.type_parameters)?;
+        if !self.type_parameters.is_empty() {
+            f.write_str(" ")?;
+        }
+        match &self.return_type {
+            Some(return_type) => write_nested(f, return_type)?,
+            None => f.write_str("void")?,
+        }
+        f.write_str("(")?;
+        write_separated(f, &self.params, ", ")?;
+        f.write_str(")")?;
+        if !self.throws.is_empty() {
+            f.write_str(" throws ")?;
+            write_separated(f, &self.throws, ", ")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_object(reference_type: &ReferenceTypeSignature) -> bool {
+    matches!(
+        reference_type,
+        ReferenceTypeSignature::Class(ClassTypeSignature { package, class, inner })
+            if package == "java/lang"
+                && class.name == "Object"
+                && class.type_arguments.is_empty()
+                && inner.is_empty()
+    )
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    fn class_type(
+        package: &str,
+        name: &str,
+        type_arguments: Vec<TypeArgument>,
+    ) -> ClassTypeSignature {
+        ClassTypeSignature {
+            package: package.into(),
+            class: SimpleClassTypeSignature {
+                name: name.into(),
+                type_arguments,
+            },
+            inner: vec![],
+        }
+    }
+
+    #[test]
+    fn it_should_parse_field_signatures() {
+        assert_eq!(
+            ReferenceTypeSignature::Class(class_type(
+                "java/util",
+                "List",
+                vec![TypeArgument::Extends(ReferenceTypeSignature::TypeVariable(
+                    "T".into()
+                ))]
+            )),
+            ReferenceTypeSignature::parse("Ljava/util/List<+TT;>;").unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_parse_class_signatures() {
+        let signature = ClassSignature::parse(
+            "<T::Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;Ljava/io/Serializable;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![TypeParameter {
+                name: "T".into(),
+                class_bound: None,
+                interface_bounds: vec![ReferenceTypeSignature::Class(class_type(
+                    "java/lang",
+                    "Comparable",
+                    vec![TypeArgument::Exact(ReferenceTypeSignature::TypeVariable(
+                        "T".into()
+                    ))]
+                ))],
+            }],
+            signature.type_parameters
+        );
+        assert_eq!(
+            class_type("java/lang", "Object", vec![]),
+            signature.superclass
+        );
+        assert_eq!(
+            vec![class_type("java/io", "Serializable", vec![])],
+            signature.interfaces
+        );
+    }
+
+    #[test]
+    fn it_should_parse_method_signatures() {
+        let signature =
+            MethodSignature::parse("<E:Ljava/lang/Exception;>([TE;I)V^TE;^Ljava/io/IOException;")
+                .unwrap();
+
+        assert_eq!(1, signature.type_parameters.len());
+        assert_eq!(
+            vec![
+                JavaTypeSignature::Reference(ReferenceTypeSignature::Array(Box::new(
+                    JavaTypeSignature::Reference(ReferenceTypeSignature::TypeVariable("E".into()))
+                ))),
+                JavaTypeSignature::Base(BaseType::Int),
+            ],
+            signature.params
+        );
+        assert_eq!(None, signature.return_type);
+        assert_eq!(
+            vec![
+                ReferenceTypeSignature::TypeVariable("E".into()),
+                ReferenceTypeSignature::Class(class_type("java/io", "IOException", vec![])),
+            ],
+            signature.throws
+        );
+    }
+
+    #[test]
+    fn it_should_parse_inner_class_type_arguments() {
+        let signature = ReferenceTypeSignature::parse("LOuter<TK;>.Inner<*>;").unwrap();
+
+        assert_eq!("Outer<K>.Inner<?>", signature.to_string());
+    }
+
+    #[test]
+    fn it_should_display_in_java_syntax() {
+        let signature =
+            ReferenceTypeSignature::parse("Ljava/util/Map<TK;+Ljava/util/List<TV;>;>;").unwrap();
+
+        assert_eq!(
+            "java.util.Map<K, ? extends java.util.List<V>>",
+            signature.to_string()
+        );
+        assert_eq!("Map<K, ? extends List<V>>", format!("{:#}", signature));
+    }
+
+    #[test]
+    fn it_should_display_class_and_method_signatures() {
+        assert_eq!(
+            "<T extends Comparable<T>, U> extends Object implements Iterable<U>",
+            format!(
+                "{:#}",
+                ClassSignature::parse(
+                    "<T::Ljava/lang/Comparable<TT;>;U:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Iterable<TU;>;"
+                )
+                .unwrap()
+            )
+        );
+        assert_eq!(
+            "<T> T[](int, List<? super T>) throws E",
+            format!(
+                "{:#}",
+                MethodSignature::parse("<T:Ljava/lang/Object;>(ILjava/util/List<-TT;>;)[TT;^TE;")
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_fail_on_invalid_signatures() {
+        for signature in [
+            "",
+            "I",
+            "Ljava/util/List<>;",
+            "Ljava/util/List",
+            "TT",
+            "La//b;",
+        ] {
+            assert!(
+                ReferenceTypeSignature::parse(signature).is_err(),
+                "{}",
+                signature
+            );
+        }
+        for signature in ["()", "(I)VV", "()V^I", "<>()V", "<T>()V"] {
+            assert!(MethodSignature::parse(signature).is_err(), "{}", signature);
+        }
+    }
+}
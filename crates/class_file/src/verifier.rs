@@ -0,0 +1,366 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.10
+
+mod frame;
+mod instructions;
+mod stack_map;
+mod type_checker;
+mod type_inference;
+mod types;
+
+use std::{collections::HashSet, fmt};
+
+pub use self::{
+    frame::Frame,
+    stack_map::ComputedFrames,
+    types::{ClassHierarchy, ResolvedClass, VerificationType},
+};
+pub(crate) use self::{
+    instructions::{branch_targets, falls_through, is_store, local_index},
+    type_inference::is_wide_local,
+};
+use crate::{
+    attributes::{CodeAttribute, ExceptionTableEntry},
+    class_file::MethodInfo,
+    constant_pool::{CpInfo, RefInfo},
+    instruction::Instruction,
+    ClassAccessFlags, ClassFile, MethodAccessFlags, MethodDescriptor,
+};
+
+/// The reason a method failed verification, like the `VerifyError` the Java Virtual Machine throws.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    /// The class, name and descriptor of the method, e.g. `my/MyClass.add(I)F`.
+    pub method: String,
+    /// The pc of the offending instruction, if the error is caused by one.
+    pub pc: Option<u32>,
+    pub message: String,
+}
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pc {
+            Some(pc) => write!(f, "{} @{}: {}", self.method, pc, self.message),
+            None => write!(f, "{}: {}", self.method, self.message),
+        }
+    }
+}
+impl std::error::Error for VerifyError {}
+
+/// The result of an operation that fails with the message of a [`VerifyError`].
+type Verified<T = ()> = std::result::Result<T, String>;
+
+/// The message of a [`VerifyError`] and the pc of the instruction that caused it, if any.
+type MethodError = (Option<u32>, String);
+
+impl ClassFile {
+    /// Verifies the code of every method, stopping at the first method that fails. The class
+    /// hierarchy resolves the classes other than this one that the code refers to.
+    ///
+    /// Class files of version 50 and above are verified by type checking against their
+    /// `StackMapTable` attributes (JVMS §4.10.1), older class files by type inference
+    /// (JVMS §4.10.2), which also supports the `jsr` and `ret` instructions. Accesses to
+    /// protected members (JVMS §4.10.1.8) are not checked, as that needs the members of other
+    /// classes.
+    pub fn verify(&self, hierarchy: &impl ClassHierarchy) -> Result<(), VerifyError> {
+        for method in &self.methods {
+            self.verify_method(method, hierarchy)?;
+        }
+        Ok(())
+    }
+
+    /// Verifies the code of a single method. Methods without code always pass.
+    pub fn verify_method(
+        &self,
+        method: &MethodInfo,
+        hierarchy: &impl ClassHierarchy,
+    ) -> Result<(), VerifyError> {
+        let Some(code) = method.attributes.code_attribute() else {
+            return Ok(());
+        };
+        let context = Context::new(self, method, code, hierarchy)?;
+
+        let result = match self.major_version {
+            // Like HotSpot, fall back to type inference if type checking a version 50 class file
+            // fails.
+            50 => type_checker::check(&context)
+                .or_else(|_| type_inference::infer(&context).map(|_| ())),
+            51.. => type_checker::check(&context),
+            _ => type_inference::infer(&context).map(|_| ()),
+        };
+        result.map_err(|(pc, message)| context.error(pc, message))
+    }
+}
+
+/// What the verification of a method needs to know about its class and itself.
+pub(crate) struct Context<'a> {
+    pub class_file: &'a ClassFile,
+    pub class_name: &'a str,
+    pub super_class: Option<&'a str>,
+    pub hierarchy: &'a dyn ClassHierarchy,
+    pub method_name: &'a str,
+    pub method_descriptor: &'a str,
+    pub descriptor: MethodDescriptor,
+    pub access_flags: MethodAccessFlags,
+    pub code: &'a CodeAttribute,
+}
+impl<'a> Context<'a> {
+    pub fn new(
+        class_file: &'a ClassFile,
+        method: &'a MethodInfo,
+        code: &'a CodeAttribute,
+        hierarchy: &'a dyn ClassHierarchy,
+    ) -> Result<Self, VerifyError> {
+        let method_error = |message: String| VerifyError {
+            method: format!("#{}", method.name_index),
+            pc: None,
+            message,
+        };
+        let class_name = class_file
+            .class_name()
+            .map_err(|e| method_error(e.to_string()))?;
+        let super_class = class_file
+            .super_class()
+            .map_err(|e| method_error(e.to_string()))?;
+        let method_name = class_file
+            .method_name(method)
+            .map_err(|e| method_error(e.to_string()))?;
+        let method_descriptor = class_file
+            .method_descriptor(method)
+            .map_err(|e| method_error(e.to_string()))?;
+
+        let descriptor = MethodDescriptor::parse(method_descriptor).map_err(|e| VerifyError {
+            method: format!("{}.{}{}", class_name, method_name, method_descriptor),
+            pc: None,
+            message: e.to_string(),
+        })?;
+
+        Ok(Self {
+            class_file,
+            class_name,
+            super_class,
+            hierarchy,
+            method_name,
+            method_descriptor,
+            descriptor,
+            access_flags: method.access_flags,
+            code,
+        })
+    }
+
+    pub fn error(&self, pc: Option<u32>, message: String) -> VerifyError {
+        VerifyError {
+            method: format!(
+                "{}.{}{}",
+                self.class_name, self.method_name, self.method_descriptor
+            ),
+            pc,
+            message,
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::STATIC)
+    }
+
+    pub fn is_constructor(&self) -> bool {
+        self.method_name == "<init>"
+    }
+
+    /// The frame on entry to the method, with `this` and the arguments in the local variables.
+    pub fn initial_frame(&self) -> Verified<Frame> {
+        let mut locals = vec![];
+        if !self.is_static() {
+            locals.push(
+                if self.is_constructor() && self.class_name != "java/lang/Object" {
+                    VerificationType::UninitializedThis
+                } else {
+                    VerificationType::Reference(self.class_name.to_owned())
+                },
+            );
+        }
+        for param in &self.descriptor.params {
+            let t = VerificationType::from_field_type(param);
+            if t.is_category2() {
+                locals.extend([t, VerificationType::Top]);
+            } else {
+                locals.push(t);
+            }
+        }
+
+        let max_locals = self.code.max_locals as usize;
+        if locals.len() > max_locals {
+            return Err("Arguments can't fit into locals".to_owned());
+        }
+        locals.resize(max_locals, VerificationType::Top);
+
+        Ok(Frame {
+            locals,
+            stack: vec![],
+        })
+    }
+
+    /// The instructions of the method together with their pcs.
+    pub fn instructions(&self) -> Result<Vec<(u32, Instruction)>, MethodError> {
+        self.code
+            .instructions()
+            .collect::<crate::Result<_>>()
+            .map_err(|e| (None, e.to_string()))
+    }
+
+    /// Checks that the exception handlers protect and start at instructions, given the pcs of the
+    /// instructions, and that they catch subclasses of `Throwable`.
+    pub fn check_exception_table(&self, starts: &HashSet<u32>) -> Result<(), MethodError> {
+        let code_length = self.code.code.len();
+        for handler in &self.code.exception_table {
+            let is_boundary = |pc: u16| starts.contains(&(pc as u32)) || pc as usize == code_length;
+            if handler.start_pc >= handler.end_pc
+                || !starts.contains(&(handler.start_pc as u32))
+                || !is_boundary(handler.end_pc)
+            {
+                return Err((None, "Illegal exception table range".to_owned()));
+            }
+            if !starts.contains(&(handler.handler_pc as u32)) {
+                return Err((None, "Illegal exception table handler".to_owned()));
+            }
+
+            let catch_type = self.catch_type(handler).map_err(|e| (None, e))?;
+            if !self.is_assignable(&catch_type, &throwable()) {
+                return Err((
+                    None,
+                    format!("Catch type {} is not a subclass of Throwable", catch_type),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The type of the exceptions the handler catches.
+    pub fn catch_type(&self, handler: &ExceptionTableEntry) -> Verified<VerificationType> {
+        Ok(match handler.catch_type {
+            0 => throwable(),
+            index => VerificationType::Reference(self.class_ref(index)?.to_owned()),
+        })
+    }
+
+    pub fn cp_info(&self, index: u16) -> Verified<&'a CpInfo> {
+        self.class_file
+            .constant_pool
+            .get(index)
+            .ok_or_else(|| format!("Invalid constant pool index #{}", index))
+    }
+
+    pub fn utf8(&self, index: u16) -> Verified<&'a str> {
+        match self.cp_info(index)? {
+            CpInfo::Utf8(s) => Ok(s),
+            cp_info => Err(format!("Expected Utf8 at #{}, found {:?}", index, cp_info)),
+        }
+    }
+
+    /// The name of the Class entry at `index`.
+    pub fn class_ref(&self, index: u16) -> Verified<&'a str> {
+        match self.cp_info(index)? {
+            CpInfo::Class(c) => self.utf8(c.name_index.get()),
+            cp_info => Err(format!("Expected Class at #{}, found {:?}", index, cp_info)),
+        }
+    }
+
+    /// The name and descriptor of the NameAndType entry at `index`.
+    pub fn name_and_type(&self, index: u16) -> Verified<(&'a str, &'a str)> {
+        match self.cp_info(index)? {
+            CpInfo::NameAndType(nat) => Ok((
+                self.utf8(nat.name_index.get())?,
+                self.utf8(nat.descriptor_index.get())?,
+            )),
+            cp_info => Err(format!(
+                "Expected NameAndType at #{}, found {:?}",
+                index, cp_info
+            )),
+        }
+    }
+
+    /// The class, name and descriptor of a field or method reference.
+    pub fn member_ref(&self, r: &RefInfo) -> Verified<(&'a str, &'a str, &'a str)> {
+        let (name, descriptor) = self.name_and_type(r.name_and_type_index.get())?;
+        Ok((self.class_ref(r.class_index.get())?, name, descriptor))
+    }
+
+    fn resolve(&self, class_name: &str) -> Option<ResolvedClass> {
+        if class_name == self.class_name {
+            Some(ResolvedClass {
+                super_class: self.super_class.map(str::to_owned),
+                is_interface: self
+                    .class_file
+                    .access_flags
+                    .contains(ClassAccessFlags::INTERFACE),
+            })
+        } else {
+            self.hierarchy.resolve(class_name)
+        }
+    }
+
+    /// Whether a value of type `from` may be used where `to` is expected (JVMS §4.10.1.2).
+    pub fn is_assignable(&self, from: &VerificationType, to: &VerificationType) -> bool {
+        match (from, to) {
+            _ if from == to => true,
+            (_, VerificationType::Top) => true,
+            (VerificationType::Null, VerificationType::Reference(_)) => true,
+            (VerificationType::Reference(from), VerificationType::Reference(to)) => {
+                self.is_java_assignable(from, to)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the class or array type `from` is a subtype of `to`. Like the Java Virtual Machine,
+    /// the verifier treats interfaces as `java/lang/Object`.
+    fn is_java_assignable(&self, from: &str, to: &str) -> bool {
+        if from == to || to == "java/lang/Object" {
+            return true;
+        }
+
+        match (from.strip_prefix('['), to.strip_prefix('[')) {
+            (Some(from), Some(to)) => {
+                return match (reference_name(from), reference_name(to)) {
+                    (Some(from), Some(to)) => self.is_java_assignable(from, to),
+                    _ => from == to,
+                };
+            }
+            (None, Some(_)) => return false,
+            // Arrays implement no other interfaces, even though the verifier treats interfaces as
+            // `java/lang/Object`.
+            (Some(_), None) => return to == "java/lang/Cloneable" || to == "java/io/Serializable",
+            (None, None) => {}
+        }
+
+        if self.resolve(to).is_some_and(|class| class.is_interface) {
+            return true;
+        }
+
+        let mut seen = HashSet::new();
+        let mut class_name = from.to_owned();
+        while seen.insert(class_name.clone()) {
+            match self
+                .resolve(&class_name)
+                .and_then(|class| class.super_class)
+            {
+                Some(super_class) if super_class == to => return true,
+                Some(super_class) => class_name = super_class,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+fn throwable() -> VerificationType {
+    VerificationType::Reference("java/lang/Throwable".to_owned())
+}
+
+/// The name of the reference type of a component descriptor, i.e. the class name of an object
+/// type or the descriptor of an array type. `None` for primitive types.
+pub(crate) fn reference_name(descriptor: &str) -> Option<&str> {
+    if descriptor.starts_with('[') {
+        Some(descriptor)
+    } else {
+        descriptor.strip_prefix('L')?.strip_suffix(';')
+    }
+}
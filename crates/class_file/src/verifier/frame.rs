@@ -0,0 +1,50 @@
+use super::VerificationType;
+
+/// The types of the local variables and the operand stack before an instruction executes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Frame {
+    /// One entry per local variable, up to `max_locals`.
+    pub locals: Vec<VerificationType>,
+    /// One entry per value, bottom first.
+    pub stack: Vec<VerificationType>,
+}
+impl Frame {
+    /// The number of words on the operand stack, counting long and double values twice.
+    pub fn stack_size(&self) -> u16 {
+        self.stack.iter().map(VerificationType::size).sum()
+    }
+
+    /// Whether `this` is still uninitialized, which the type checker tracks as the `flagThisUninit`
+    /// flag.
+    pub fn this_uninitialized(&self) -> bool {
+        self.locals.contains(&VerificationType::UninitializedThis)
+    }
+
+    /// The locals as a `StackMapTable` declares them, with one entry for a long or double and
+    /// without trailing `Top`s.
+    pub(crate) fn declared_locals(&self) -> Vec<VerificationType> {
+        let mut declared = vec![];
+        let mut i = 0;
+        while i < self.locals.len() {
+            declared.push(self.locals[i].clone());
+            i += self.locals[i].size() as usize;
+        }
+        while declared.last() == Some(&VerificationType::Top) {
+            declared.pop();
+        }
+        declared
+    }
+
+    /// Replaces every occurrence of an uninitialized type once its constructor has been called.
+    pub(crate) fn initialize(
+        &mut self,
+        uninitialized: &VerificationType,
+        initialized: VerificationType,
+    ) {
+        for t in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if t == uninitialized {
+                *t = initialized.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,916 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.10.1.9
+
+use super::{reference_name, throwable, Context, Frame, VerificationType, Verified};
+use crate::{
+    constant_pool::CpInfo,
+    descriptor::FieldType,
+    instruction::{ArrayType, Instruction, WideInstruction},
+    MethodDescriptor,
+};
+
+use VerificationType::{Double, Float, Integer, Long, Null, Top, UninitializedThis};
+
+/// Where control goes after an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Flow {
+    /// The branch targets, which start with the frame after the instruction.
+    pub targets: Vec<u32>,
+    /// Whether the next instruction may follow this one.
+    pub falls_through: bool,
+}
+impl Flow {
+    fn next() -> Self {
+        Self {
+            targets: vec![],
+            falls_through: true,
+        }
+    }
+
+    fn branch(targets: Vec<u32>) -> Self {
+        Self {
+            targets,
+            falls_through: true,
+        }
+    }
+
+    fn jump(targets: Vec<u32>) -> Self {
+        Self {
+            targets,
+            falls_through: false,
+        }
+    }
+
+    fn end() -> Self {
+        Self::jump(vec![])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvokeKind {
+    Virtual,
+    Special,
+    Static,
+    Interface,
+    Dynamic,
+}
+
+/// The local variable index of a load, store, `iinc` or `ret` instruction.
+pub(crate) fn local_index(instruction: &Instruction) -> Option<u16> {
+    use Instruction::*;
+
+    Some(match instruction {
+        Iload0 | Lload0 | Fload0 | Dload0 | Aload0 | Istore0 | Lstore0 | Fstore0 | Dstore0
+        | Astore0 => 0,
+        Iload1 | Lload1 | Fload1 | Dload1 | Aload1 | Istore1 | Lstore1 | Fstore1 | Dstore1
+        | Astore1 => 1,
+        Iload2 | Lload2 | Fload2 | Dload2 | Aload2 | Istore2 | Lstore2 | Fstore2 | Dstore2
+        | Astore2 => 2,
+        Iload3 | Lload3 | Fload3 | Dload3 | Aload3 | Istore3 | Lstore3 | Fstore3 | Dstore3
+        | Astore3 => 3,
+        Iload(n) | Lload(n) | Fload(n) | Dload(n) | Aload(n) | Istore(n) | Lstore(n)
+        | Fstore(n) | Dstore(n) | Astore(n) | Ret(n) => *n as u16,
+        Iinc { index, .. } => *index as u16,
+        Wide(
+            WideInstruction::Iload(n)
+            | WideInstruction::Lload(n)
+            | WideInstruction::Fload(n)
+            | WideInstruction::Dload(n)
+            | WideInstruction::Aload(n)
+            | WideInstruction::Istore(n)
+            | WideInstruction::Lstore(n)
+            | WideInstruction::Fstore(n)
+            | WideInstruction::Dstore(n)
+            | WideInstruction::Astore(n)
+            | WideInstruction::Ret(n)
+            | WideInstruction::Iinc { index: n, .. },
+        ) => *n,
+        _ => return None,
+    })
+}
+
+/// Whether the instruction stores a value in the local variable of its `local_index`. `iinc` both
+/// loads and stores it.
+pub(crate) fn is_store(instruction: &Instruction) -> bool {
+    use Instruction::*;
+
+    matches!(
+        instruction,
+        Istore0
+            | Lstore0
+            | Fstore0
+            | Dstore0
+            | Astore0
+            | Istore1
+            | Lstore1
+            | Fstore1
+            | Dstore1
+            | Astore1
+            | Istore2
+            | Lstore2
+            | Fstore2
+            | Dstore2
+            | Astore2
+            | Istore3
+            | Lstore3
+            | Fstore3
+            | Dstore3
+            | Astore3
+            | Istore(_)
+            | Lstore(_)
+            | Fstore(_)
+            | Dstore(_)
+            | Astore(_)
+            | Iinc { .. }
+            | Wide(
+                WideInstruction::Istore(_)
+                    | WideInstruction::Lstore(_)
+                    | WideInstruction::Fstore(_)
+                    | WideInstruction::Dstore(_)
+                    | WideInstruction::Astore(_)
+                    | WideInstruction::Iinc { .. }
+            )
+    )
+}
+
+/// The pc a branch with the offset jumps to.
+pub(crate) fn branch_target(pc: u32, offset: i32, code_length: usize) -> Verified<u32> {
+    let target = pc as i64 + offset as i64;
+    if target < 0 || target >= code_length as i64 {
+        return Err(format!("Illegal target of jump or branch: {}", target));
+    }
+    Ok(target as u32)
+}
+
+/// The branch targets of an instruction, not including the instruction that follows.
+pub(crate) fn branch_targets(
+    pc: u32,
+    instruction: &Instruction,
+    code_length: usize,
+) -> Verified<Vec<u32>> {
+    use Instruction::*;
+
+    let target = |offset: i32| branch_target(pc, offset, code_length);
+    Ok(match instruction {
+        Ifeq(offset) | Ifne(offset) | Iflt(offset) | Ifge(offset) | Ifgt(offset) | Ifle(offset)
+        | IfIcmpeq(offset) | IfIcmpne(offset) | IfIcmplt(offset) | IfIcmpge(offset)
+        | IfIcmpgt(offset) | IfIcmple(offset) | IfAcmpeq(offset) | IfAcmpne(offset)
+        | Ifnull(offset) | Ifnonnull(offset) | Goto(offset) | Jsr(offset) => {
+            vec![target(*offset as i32)?]
+        }
+        GotoW(offset) | JsrW(offset) => vec![target(*offset)?],
+        Tableswitch {
+            default, offsets, ..
+        } => std::iter::once(default)
+            .chain(offsets)
+            .map(|offset| target(*offset))
+            .collect::<Verified<_>>()?,
+        Lookupswitch { default, pairs } => std::iter::once(default)
+            .chain(pairs.iter().map(|(_, offset)| offset))
+            .map(|offset| target(*offset))
+            .collect::<Verified<_>>()?,
+        _ => vec![],
+    })
+}
+
+/// Whether the instruction that follows an instruction may be executed after it.
+pub(crate) fn falls_through(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction,
+        Instruction::Goto(_)
+            | Instruction::GotoW(_)
+            | Instruction::Tableswitch { .. }
+            | Instruction::Lookupswitch { .. }
+            | Instruction::Ireturn
+            | Instruction::Lreturn
+            | Instruction::Freturn
+            | Instruction::Dreturn
+            | Instruction::Areturn
+            | Instruction::Return
+            | Instruction::Athrow
+    )
+}
+
+impl Context<'_> {
+    fn pop(&self, frame: &mut Frame) -> Verified<VerificationType> {
+        frame
+            .stack
+            .pop()
+            .ok_or_else(|| "Operand stack underflow".to_owned())
+    }
+
+    /// Pops a value that must be assignable to `expected`.
+    fn pop_expecting(
+        &self,
+        frame: &mut Frame,
+        expected: &VerificationType,
+    ) -> Verified<VerificationType> {
+        let actual = self.pop(frame)?;
+        if !self.is_assignable(&actual, expected) {
+            return Err(format!(
+                "Bad type on operand stack: {} is not assignable to {}",
+                actual, expected
+            ));
+        }
+        Ok(actual)
+    }
+
+    /// Pops a reference to an object, which may be uninitialized.
+    fn pop_reference(&self, frame: &mut Frame) -> Verified<VerificationType> {
+        let actual = self.pop(frame)?;
+        if !actual.is_reference() {
+            return Err(format!(
+                "Bad type on operand stack: expected a reference, found {}",
+                actual
+            ));
+        }
+        Ok(actual)
+    }
+
+    /// Pops an array, returning the descriptor of its component type or `None` if it is null.
+    fn pop_array(&self, frame: &mut Frame) -> Verified<Option<String>> {
+        match self.pop(frame)? {
+            Null => Ok(None),
+            actual => match actual.component_descriptor() {
+                Some(component) => Ok(Some(component.to_owned())),
+                None => Err(format!(
+                    "Bad type on operand stack: expected an array, found {}",
+                    actual
+                )),
+            },
+        }
+    }
+
+    /// Pops the values taking the top `words` of the operand stack, bottom first.
+    fn pop_words(&self, frame: &mut Frame, words: u16) -> Verified<Vec<VerificationType>> {
+        let mut values = vec![];
+        let mut size = 0;
+        while size < words {
+            let value = self.pop(frame)?;
+            size += value.size();
+            values.insert(0, value);
+        }
+        if size != words {
+            return Err("Bad type on operand stack: a long or double would be split".to_owned());
+        }
+        Ok(values)
+    }
+
+    pub(crate) fn push(&self, frame: &mut Frame, t: VerificationType) -> Verified {
+        frame.stack.push(t);
+        if frame.stack_size() > self.code.max_stack {
+            return Err("Operand stack overflow".to_owned());
+        }
+        Ok(())
+    }
+
+    fn push_all(&self, frame: &mut Frame, values: &[VerificationType]) -> Verified {
+        for value in values {
+            self.push(frame, value.clone())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn local(&self, frame: &Frame, index: u16) -> Verified<VerificationType> {
+        frame
+            .locals
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| format!("Illegal local variable number {}", index))
+    }
+
+    /// Pushes the local variable, which must be assignable to `expected`.
+    fn load(&self, frame: &mut Frame, index: u16, expected: VerificationType) -> Verified {
+        let actual = self.local(frame, index)?;
+        if !self.is_assignable(&actual, &expected)
+            || (expected.is_category2() && self.local(frame, index + 1)? != Top)
+        {
+            return Err(format!(
+                "Bad local variable type: {} is not assignable to {}",
+                actual, expected
+            ));
+        }
+        self.push(frame, expected)
+    }
+
+    pub(crate) fn store(&self, frame: &mut Frame, index: u16, t: VerificationType) -> Verified {
+        let index = index as usize;
+        if index + t.size() as usize > frame.locals.len() {
+            return Err(format!("Illegal local variable number {}", index));
+        }
+
+        // Overwriting the second word of a long or double invalidates it.
+        if index > 0 && frame.locals[index - 1].is_category2() {
+            frame.locals[index - 1] = Top;
+        }
+        if t.is_category2() {
+            frame.locals[index + 1] = Top;
+        }
+        frame.locals[index] = t;
+        Ok(())
+    }
+
+    fn field_type(&self, descriptor: &str) -> Verified<VerificationType> {
+        FieldType::parse(descriptor)
+            .map(|field_type| VerificationType::from_field_type(&field_type))
+            .map_err(|e| e.to_string())
+    }
+
+    /// The class named by the `new` instruction at the offset.
+    pub(crate) fn new_class(&self, offset: u16) -> Verified<&str> {
+        match Instruction::decode(&self.code.code, offset as u32) {
+            Ok((Instruction::New(index), _)) => self.class_ref(index),
+            _ => Err(format!("Expecting new at {}", offset)),
+        }
+    }
+
+    /// Executes the instruction at `pc` on the frame, checking the types of its operands.
+    pub(crate) fn execute(
+        &self,
+        frame: &mut Frame,
+        pc: u32,
+        instruction: &Instruction,
+    ) -> Verified<Flow> {
+        use Instruction::*;
+
+        let code_length = self.code.code.len();
+        let index = local_index(instruction);
+
+        match instruction {
+            Nop => {}
+            AconstNull => self.push(frame, Null)?,
+            IconstM1 | Iconst0 | Iconst1 | Iconst2 | Iconst3 | Iconst4 | Iconst5 | Bipush(_)
+            | Sipush(_) => self.push(frame, Integer)?,
+            Lconst0 | Lconst1 => self.push(frame, Long)?,
+            Fconst0 | Fconst1 | Fconst2 => self.push(frame, Float)?,
+            Dconst0 | Dconst1 => self.push(frame, Double)?,
+            Ldc(i) => self.ldc(frame, *i as u16, false)?,
+            LdcW(i) => self.ldc(frame, *i, false)?,
+            Ldc2W(i) => self.ldc(frame, *i, true)?,
+
+            Iload(_) | Iload0 | Iload1 | Iload2 | Iload3 | Wide(WideInstruction::Iload(_)) => {
+                self.load(frame, index.unwrap(), Integer)?
+            }
+            Lload(_) | Lload0 | Lload1 | Lload2 | Lload3 | Wide(WideInstruction::Lload(_)) => {
+                self.load(frame, index.unwrap(), Long)?
+            }
+            Fload(_) | Fload0 | Fload1 | Fload2 | Fload3 | Wide(WideInstruction::Fload(_)) => {
+                self.load(frame, index.unwrap(), Float)?
+            }
+            Dload(_) | Dload0 | Dload1 | Dload2 | Dload3 | Wide(WideInstruction::Dload(_)) => {
+                self.load(frame, index.unwrap(), Double)?
+            }
+            Aload(_) | Aload0 | Aload1 | Aload2 | Aload3 | Wide(WideInstruction::Aload(_)) => {
+                let actual = self.local(frame, index.unwrap())?;
+                if !actual.is_reference() {
+                    return Err(format!(
+                        "Bad local variable type: expected a reference, found {}",
+                        actual
+                    ));
+                }
+                self.push(frame, actual)?
+            }
+
+            Iaload | Baload | Caload | Saload | Laload | Faload | Daload => {
+                let (components, t): (&[&str], _) = match instruction {
+                    Iaload => (&["I"], Integer),
+                    Baload => (&["B", "Z"], Integer),
+                    Caload => (&["C"], Integer),
+                    Saload => (&["S"], Integer),
+                    Laload => (&["J"], Long),
+                    Faload => (&["F"], Float),
+                    _ => (&["D"], Double),
+                };
+                self.pop_expecting(frame, &Integer)?;
+                self.check_primitive_array(frame, components)?;
+                self.push(frame, t)?
+            }
+            Aaload => {
+                self.pop_expecting(frame, &Integer)?;
+                let t = match self.pop_array(frame)? {
+                    None => Null,
+                    Some(component) => match reference_name(&component) {
+                        Some(name) => VerificationType::Reference(name.to_owned()),
+                        None => {
+                            return Err(format!(
+                                "Bad type on operand stack in aaload: [{}",
+                                component
+                            ))
+                        }
+                    },
+                };
+                self.push(frame, t)?
+            }
+
+            Istore(_)
+            | Istore0
+            | Istore1
+            | Istore2
+            | Istore3
+            | Wide(WideInstruction::Istore(_)) => {
+                self.pop_expecting(frame, &Integer)?;
+                self.store(frame, index.unwrap(), Integer)?
+            }
+            Lstore(_)
+            | Lstore0
+            | Lstore1
+            | Lstore2
+            | Lstore3
+            | Wide(WideInstruction::Lstore(_)) => {
+                self.pop_expecting(frame, &Long)?;
+                self.store(frame, index.unwrap(), Long)?
+            }
+            Fstore(_)
+            | Fstore0
+            | Fstore1
+            | Fstore2
+            | Fstore3
+            | Wide(WideInstruction::Fstore(_)) => {
+                self.pop_expecting(frame, &Float)?;
+                self.store(frame, index.unwrap(), Float)?
+            }
+            Dstore(_)
+            | Dstore0
+            | Dstore1
+            | Dstore2
+            | Dstore3
+            | Wide(WideInstruction::Dstore(_)) => {
+                self.pop_expecting(frame, &Double)?;
+                self.store(frame, index.unwrap(), Double)?
+            }
+            Astore(_)
+            | Astore0
+            | Astore1
+            | Astore2
+            | Astore3
+            | Wide(WideInstruction::Astore(_)) => {
+                // Subroutines store their return address with astore.
+                let t = match self.pop(frame)? {
+                    t @ VerificationType::ReturnAddress(_) => t,
+                    t if t.is_reference() => t,
+                    t => {
+                        return Err(format!(
+                            "Bad type on operand stack: expected a reference, found {}",
+                            t
+                        ))
+                    }
+                };
+                self.store(frame, index.unwrap(), t)?
+            }
+
+            Iastore | Bastore | Castore | Sastore | Lastore | Fastore | Dastore => {
+                let (components, t): (&[&str], _) = match instruction {
+                    Iastore => (&["I"], Integer),
+                    Bastore => (&["B", "Z"], Integer),
+                    Castore => (&["C"], Integer),
+                    Sastore => (&["S"], Integer),
+                    Lastore => (&["J"], Long),
+                    Fastore => (&["F"], Float),
+                    _ => (&["D"], Double),
+                };
+                self.pop_expecting(frame, &t)?;
+                self.pop_expecting(frame, &Integer)?;
+                self.check_primitive_array(frame, components)?;
+            }
+            Aastore => {
+                self.pop_reference(frame)?;
+                self.pop_expecting(frame, &Integer)?;
+                if let Some(component) = self.pop_array(frame)? {
+                    if reference_name(&component).is_none() {
+                        return Err(format!(
+                            "Bad type on operand stack in aastore: [{}",
+                            component
+                        ));
+                    }
+                }
+            }
+
+            Pop => {
+                self.pop_words(frame, 1)?;
+            }
+            Pop2 => {
+                self.pop_words(frame, 2)?;
+            }
+            Dup => {
+                let v1 = self.pop_words(frame, 1)?;
+                self.push_all(frame, &v1)?;
+                self.push_all(frame, &v1)?
+            }
+            DupX1 | DupX2 | Dup2X1 | Dup2X2 => {
+                let (top, below) = match instruction {
+                    DupX1 => (1, 1),
+                    DupX2 => (1, 2),
+                    Dup2X1 => (2, 1),
+                    _ => (2, 2),
+                };
+                let v1 = self.pop_words(frame, top)?;
+                let v2 = self.pop_words(frame, below)?;
+                self.push_all(frame, &v1)?;
+                self.push_all(frame, &v2)?;
+                self.push_all(frame, &v1)?
+            }
+            Dup2 => {
+                let v1 = self.pop_words(frame, 2)?;
+                self.push_all(frame, &v1)?;
+                self.push_all(frame, &v1)?
+            }
+            Swap => {
+                let v1 = self.pop_words(frame, 1)?;
+                let v2 = self.pop_words(frame, 1)?;
+                self.push_all(frame, &v1)?;
+                self.push_all(frame, &v2)?
+            }
+
+            Iadd | Isub | Imul | Idiv | Irem | Ishl | Ishr | Iushr | Iand | Ior | Ixor => {
+                self.binary(frame, Integer, Integer, Integer)?
+            }
+            Ladd | Lsub | Lmul | Ldiv | Lrem | Land | Lor | Lxor => {
+                self.binary(frame, Long, Long, Long)?
+            }
+            Lshl | Lshr | Lushr => self.binary(frame, Long, Integer, Long)?,
+            Fadd | Fsub | Fmul | Fdiv | Frem => self.binary(frame, Float, Float, Float)?,
+            Dadd | Dsub | Dmul | Ddiv | Drem => self.binary(frame, Double, Double, Double)?,
+            Lcmp => self.binary(frame, Long, Long, Integer)?,
+            Fcmpl | Fcmpg => self.binary(frame, Float, Float, Integer)?,
+            Dcmpl | Dcmpg => self.binary(frame, Double, Double, Integer)?,
+            Ineg | I2b | I2c | I2s => self.unary(frame, Integer, Integer)?,
+            Lneg => self.unary(frame, Long, Long)?,
+            Fneg => self.unary(frame, Float, Float)?,
+            Dneg => self.unary(frame, Double, Double)?,
+            I2l => self.unary(frame, Integer, Long)?,
+            I2f => self.unary(frame, Integer, Float)?,
+            I2d => self.unary(frame, Integer, Double)?,
+            L2i => self.unary(frame, Long, Integer)?,
+            L2f => self.unary(frame, Long, Float)?,
+            L2d => self.unary(frame, Long, Double)?,
+            F2i => self.unary(frame, Float, Integer)?,
+            F2l => self.unary(frame, Float, Long)?,
+            F2d => self.unary(frame, Float, Double)?,
+            D2i => self.unary(frame, Double, Integer)?,
+            D2l => self.unary(frame, Double, Long)?,
+            D2f => self.unary(frame, Double, Float)?,
+            Iinc { .. } | Wide(WideInstruction::Iinc { .. }) => {
+                let actual = self.local(frame, index.unwrap())?;
+                if actual != Integer {
+                    return Err(format!(
+                        "Bad local variable type: {} is not assignable to {}",
+                        actual, Integer
+                    ));
+                }
+            }
+
+            Ifeq(_) | Ifne(_) | Iflt(_) | Ifge(_) | Ifgt(_) | Ifle(_) => {
+                self.pop_expecting(frame, &Integer)?;
+                return Ok(Flow::branch(branch_targets(pc, instruction, code_length)?));
+            }
+            IfIcmpeq(_) | IfIcmpne(_) | IfIcmplt(_) | IfIcmpge(_) | IfIcmpgt(_) | IfIcmple(_) => {
+                self.pop_expecting(frame, &Integer)?;
+                self.pop_expecting(frame, &Integer)?;
+                return Ok(Flow::branch(branch_targets(pc, instruction, code_length)?));
+            }
+            IfAcmpeq(_) | IfAcmpne(_) => {
+                self.pop_reference(frame)?;
+                self.pop_reference(frame)?;
+                return Ok(Flow::branch(branch_targets(pc, instruction, code_length)?));
+            }
+            Ifnull(_) | Ifnonnull(_) => {
+                self.pop_reference(frame)?;
+                return Ok(Flow::branch(branch_targets(pc, instruction, code_length)?));
+            }
+            Goto(_) | GotoW(_) => {
+                return Ok(Flow::jump(branch_targets(pc, instruction, code_length)?));
+            }
+            Tableswitch { low, offsets, .. } => {
+                if (*low as i64) + offsets.len() as i64 - 1 > i32::MAX as i64 {
+                    return Err("Bad tableswitch range".to_owned());
+                }
+                self.pop_expecting(frame, &Integer)?;
+                return Ok(Flow::jump(branch_targets(pc, instruction, code_length)?));
+            }
+            Lookupswitch { pairs, .. } => {
+                if pairs.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                    return Err("Bad lookupswitch instruction: keys are not sorted".to_owned());
+                }
+                self.pop_expecting(frame, &Integer)?;
+                return Ok(Flow::jump(branch_targets(pc, instruction, code_length)?));
+            }
+            // Stack map frames can't describe return addresses, so only type inference handles
+            // subroutines, before instructions get here.
+            Jsr(_) | JsrW(_) | Ret(_) | Wide(WideInstruction::Ret(_)) => {
+                return Err(format!(
+                    "{} is not allowed in class files of version {}",
+                    instruction.mnemonic(),
+                    self.class_file.version()
+                ));
+            }
+
+            Ireturn | Lreturn | Freturn | Dreturn | Areturn | Return => {
+                self.return_value(frame, instruction)?;
+                return Ok(Flow::end());
+            }
+            Athrow => {
+                self.pop_expecting(frame, &throwable())?;
+                return Ok(Flow::end());
+            }
+
+            Getstatic(i) | Putstatic(i) | Getfield(i) | Putfield(i) => {
+                self.field_access(frame, instruction, *i)?
+            }
+            Invokevirtual(i) => self.invoke(frame, InvokeKind::Virtual, *i, None)?,
+            Invokespecial(i) => self.invoke(frame, InvokeKind::Special, *i, None)?,
+            Invokestatic(i) => self.invoke(frame, InvokeKind::Static, *i, None)?,
+            Invokeinterface { index, count } => {
+                self.invoke(frame, InvokeKind::Interface, *index, Some(*count))?
+            }
+            Invokedynamic(i) => self.invoke(frame, InvokeKind::Dynamic, *i, None)?,
+
+            New(i) => {
+                let class_name = self.class_ref(*i)?;
+                if class_name.starts_with('[') {
+                    return Err(format!("Illegal new instruction for array {}", class_name));
+                }
+                // An uninitialized object from an earlier execution of the instruction can't be
+                // used anymore.
+                let t = VerificationType::Uninitialized(pc as u16);
+                if frame.stack.contains(&t) {
+                    return Err(format!("{} is already on the operand stack", t));
+                }
+                frame.initialize(&t, Top);
+                self.push(frame, t)?
+            }
+            Newarray(array_type) => {
+                let component = match array_type {
+                    ArrayType::Boolean => 'Z',
+                    ArrayType::Char => 'C',
+                    ArrayType::Float => 'F',
+                    ArrayType::Double => 'D',
+                    ArrayType::Byte => 'B',
+                    ArrayType::Short => 'S',
+                    ArrayType::Int => 'I',
+                    ArrayType::Long => 'J',
+                };
+                self.unary(
+                    frame,
+                    Integer,
+                    VerificationType::Reference(format!("[{}", component)),
+                )?
+            }
+            Anewarray(i) => {
+                let class_name = self.class_ref(*i)?;
+                let array = if class_name.starts_with('[') {
+                    format!("[{}", class_name)
+                } else {
+                    format!("[L{};", class_name)
+                };
+                self.unary(frame, Integer, VerificationType::Reference(array))?
+            }
+            Multianewarray { index, dimensions } => {
+                let class_name = self.class_ref(*index)?;
+                let array_dimensions = class_name.bytes().take_while(|b| *b == b'[').count();
+                if *dimensions == 0 || array_dimensions < *dimensions as usize {
+                    return Err(format!(
+                        "Illegal dimension {} in multianewarray of {}",
+                        dimensions, class_name
+                    ));
+                }
+                for _ in 0..*dimensions {
+                    self.pop_expecting(frame, &Integer)?;
+                }
+                self.push(frame, VerificationType::Reference(class_name.to_owned()))?
+            }
+            Arraylength => {
+                self.pop_array(frame)?;
+                self.push(frame, Integer)?
+            }
+            Checkcast(i) => {
+                let class_name = self.class_ref(*i)?;
+                self.pop_expecting(frame, &VerificationType::object())?;
+                self.push(frame, VerificationType::Reference(class_name.to_owned()))?
+            }
+            Instanceof(i) => {
+                self.class_ref(*i)?;
+                self.pop_expecting(frame, &VerificationType::object())?;
+                self.push(frame, Integer)?
+            }
+            Monitorenter | Monitorexit => {
+                self.pop_reference(frame)?;
+            }
+        }
+
+        Ok(Flow::next())
+    }
+
+    fn unary(
+        &self,
+        frame: &mut Frame,
+        operand: VerificationType,
+        result: VerificationType,
+    ) -> Verified {
+        self.pop_expecting(frame, &operand)?;
+        self.push(frame, result)
+    }
+
+    fn binary(
+        &self,
+        frame: &mut Frame,
+        left: VerificationType,
+        right: VerificationType,
+        result: VerificationType,
+    ) -> Verified {
+        self.pop_expecting(frame, &right)?;
+        self.pop_expecting(frame, &left)?;
+        self.push(frame, result)
+    }
+
+    fn check_primitive_array(&self, frame: &mut Frame, components: &[&str]) -> Verified {
+        match self.pop_array(frame)? {
+            Some(component) if !components.contains(&component.as_str()) => Err(format!(
+                "Bad type on operand stack: [{} is not an array of {}",
+                component,
+                components.join(" or ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn ldc(&self, frame: &mut Frame, index: u16, wide: bool) -> Verified {
+        let t = match self.cp_info(index)? {
+            CpInfo::Integer(_) => Integer,
+            CpInfo::Float(_) => Float,
+            CpInfo::Long(_) => Long,
+            CpInfo::Double(_) => Double,
+            CpInfo::String { .. } => VerificationType::Reference("java/lang/String".to_owned()),
+            CpInfo::Class(_) => VerificationType::Reference("java/lang/Class".to_owned()),
+            CpInfo::MethodType(_) => {
+                VerificationType::Reference("java/lang/invoke/MethodType".to_owned())
+            }
+            CpInfo::MethodHandle(_) => {
+                VerificationType::Reference("java/lang/invoke/MethodHandle".to_owned())
+            }
+            CpInfo::Dynamic(d) => {
+                self.field_type(self.name_and_type(d.name_and_type_index.get())?.1)?
+            }
+            cp_info => return Err(format!("Invalid index #{} in ldc: {:?}", index, cp_info)),
+        };
+        if t.is_category2() != wide {
+            return Err(format!("Invalid index #{} in ldc", index));
+        }
+        self.push(frame, t)
+    }
+
+    fn return_value(&self, frame: &mut Frame, instruction: &Instruction) -> Verified {
+        let return_type = self
+            .descriptor
+            .return_type
+            .as_ref()
+            .map(VerificationType::from_field_type);
+        match (instruction, return_type) {
+            (Instruction::Return, None) => {
+                if self.is_constructor() && frame.this_uninitialized() {
+                    return Err("Constructor must call super() or this() before return".to_owned());
+                }
+                Ok(())
+            }
+            (Instruction::Ireturn, Some(t @ Integer))
+            | (Instruction::Lreturn, Some(t @ Long))
+            | (Instruction::Freturn, Some(t @ Float))
+            | (Instruction::Dreturn, Some(t @ Double))
+            | (Instruction::Areturn, Some(t @ VerificationType::Reference(_))) => {
+                self.pop_expecting(frame, &t).map(|_| ())
+            }
+            _ => Err("Method expects a return value of a different type".to_owned()),
+        }
+    }
+
+    fn field_access(&self, frame: &mut Frame, instruction: &Instruction, index: u16) -> Verified {
+        let r = match self.cp_info(index)? {
+            CpInfo::FieldRef(r) => r,
+            cp_info => {
+                return Err(format!(
+                    "Expected FieldRef at #{}, found {:?}",
+                    index, cp_info
+                ))
+            }
+        };
+        let (class_name, name, descriptor) = self.member_ref(r)?;
+        let t = self.field_type(descriptor)?;
+        let class = VerificationType::Reference(class_name.to_owned());
+
+        match instruction {
+            Instruction::Getstatic(_) => self.push(frame, t),
+            Instruction::Putstatic(_) => self.pop_expecting(frame, &t).map(|_| ()),
+            Instruction::Getfield(_) => {
+                self.pop_expecting(frame, &class)?;
+                self.push(frame, t)
+            }
+            _ => {
+                self.pop_expecting(frame, &t)?;
+                // A constructor may assign the fields declared by its class before calling the
+                // superclass constructor.
+                if frame.stack.last() == Some(&UninitializedThis)
+                    && class_name == self.class_name
+                    && self.declares_field(name, descriptor)
+                {
+                    self.pop(frame)?;
+                    return Ok(());
+                }
+                self.pop_expecting(frame, &class).map(|_| ())
+            }
+        }
+    }
+
+    fn declares_field(&self, name: &str, descriptor: &str) -> bool {
+        self.class_file.fields.iter().any(|field| {
+            self.class_file.field_name(field).ok() == Some(name)
+                && self.class_file.field_descriptor(field).ok() == Some(descriptor)
+        })
+    }
+
+    fn invoke(
+        &self,
+        frame: &mut Frame,
+        kind: InvokeKind,
+        index: u16,
+        count: Option<u8>,
+    ) -> Verified {
+        let (class_name, name, descriptor) = match (kind, self.cp_info(index)?) {
+            (InvokeKind::Dynamic, CpInfo::InvokeDynamic(i)) => {
+                let (name, descriptor) = self.name_and_type(i.name_and_type_index.get())?;
+                ("", name, descriptor)
+            }
+            (InvokeKind::Virtual, CpInfo::MethodRef(r))
+            | (InvokeKind::Interface, CpInfo::InterfaceMethodRef(r))
+            | (
+                InvokeKind::Special | InvokeKind::Static,
+                CpInfo::MethodRef(r) | CpInfo::InterfaceMethodRef(r),
+            ) => self.member_ref(r)?,
+            (_, cp_info) => {
+                return Err(format!(
+                    "Invalid method reference #{}: {:?}",
+                    index, cp_info
+                ))
+            }
+        };
+        let descriptor = MethodDescriptor::parse(descriptor).map_err(|e| e.to_string())?;
+
+        if name.starts_with('<') && !(kind == InvokeKind::Special && name == "<init>") {
+            return Err(format!("Illegal call to internal method {}", name));
+        }
+        if let Some(count) = count {
+            if count as u16 != descriptor.parameter_slots() + 1 {
+                return Err(format!(
+                    "Inconsistent args count operand {} in invokeinterface",
+                    count
+                ));
+            }
+        }
+
+        for param in descriptor.params.iter().rev() {
+            self.pop_expecting(frame, &VerificationType::from_field_type(param))?;
+        }
+
+        let class = VerificationType::Reference(class_name.to_owned());
+        match kind {
+            InvokeKind::Static | InvokeKind::Dynamic => {}
+            InvokeKind::Special if name == "<init>" => {
+                if descriptor.return_type.is_some() {
+                    return Err("<init> must return void".to_owned());
+                }
+                let receiver = self.pop(frame)?;
+                let initialized = match receiver {
+                    UninitializedThis
+                        if class_name == self.class_name
+                            || Some(class_name) == self.super_class =>
+                    {
+                        VerificationType::Reference(self.class_name.to_owned())
+                    }
+                    VerificationType::Uninitialized(offset)
+                        if self.new_class(offset)? == class_name =>
+                    {
+                        class
+                    }
+                    receiver => {
+                        return Err(format!(
+                            "Bad type on operand stack: {} can't be initialized by {}.<init>",
+                            receiver, class_name
+                        ))
+                    }
+                };
+                frame.initialize(&receiver, initialized);
+            }
+            InvokeKind::Special => {
+                self.pop_expecting(
+                    frame,
+                    &VerificationType::Reference(self.class_name.to_owned()),
+                )?;
+            }
+            InvokeKind::Virtual | InvokeKind::Interface => {
+                self.pop_expecting(frame, &class)?;
+            }
+        }
+
+        if let Some(return_type) = &descriptor.return_type {
+            self.push(frame, VerificationType::from_field_type(return_type))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.7.4
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    instructions::{branch_targets, falls_through, local_index},
+    type_inference, ClassHierarchy, Context, Frame, VerificationType, VerifyError,
+};
+use crate::{
+    attributes::{Attribute, AttributeInfo, StackMapFrame, VerificationTypeInfo},
+    instruction::{Instruction, WideInstruction},
+    ClassFile, ConstantPool, MethodAccessFlags, MethodInfo, Result,
+};
+
+/// The `max_stack`, `max_locals` and stack map frames of a method's code, computed by type
+/// inference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedFrames {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    /// The frames needed by the type checker: at branch targets, at exception handlers and after
+    /// instructions that don't fall through.
+    pub frames: BTreeMap<u32, Frame>,
+    initial_frame: Frame,
+}
+impl ComputedFrames {
+    /// Encodes the frames as a `StackMapTable`, each frame as compact as its difference to the
+    /// previous one allows. The constant pool gets the Class entries the frames refer to.
+    pub fn stack_map_table(&self, constant_pool: &mut ConstantPool) -> Result<Vec<StackMapFrame>> {
+        let mut stack_map_table = vec![];
+        let mut previous_locals = self.initial_frame.declared_locals();
+        let mut previous_pc = None;
+
+        for (pc, frame) in &self.frames {
+            let offset_delta = match previous_pc {
+                Some(previous_pc) => (pc - previous_pc - 1) as u16,
+                None => *pc as u16,
+            };
+            previous_pc = Some(*pc);
+
+            let locals = frame.declared_locals();
+            let mut encode = |types: &[VerificationType]| {
+                types
+                    .iter()
+                    .map(|t| verification_type_info(t, constant_pool))
+                    .collect::<Result<Vec<_>>>()
+            };
+
+            let stack_map_frame = match frame.stack.as_slice() {
+                [] if locals == previous_locals => {
+                    if offset_delta < 64 {
+                        StackMapFrame::Same { offset_delta }
+                    } else {
+                        StackMapFrame::SameExtended { offset_delta }
+                    }
+                }
+                [stack] if locals == previous_locals => {
+                    let stack = verification_type_info(stack, constant_pool)?;
+                    if offset_delta < 64 {
+                        StackMapFrame::SameLocals1StackItem {
+                            offset_delta,
+                            stack,
+                        }
+                    } else {
+                        StackMapFrame::SameLocals1StackItemExtended {
+                            offset_delta,
+                            stack,
+                        }
+                    }
+                }
+                [] if locals.len() < previous_locals.len()
+                    && previous_locals.len() - locals.len() <= 3
+                    && previous_locals.starts_with(&locals) =>
+                {
+                    StackMapFrame::Chop {
+                        k: (previous_locals.len() - locals.len()) as u8,
+                        offset_delta,
+                    }
+                }
+                [] if locals.len() > previous_locals.len()
+                    && locals.len() - previous_locals.len() <= 3
+                    && locals.starts_with(&previous_locals) =>
+                {
+                    StackMapFrame::Append {
+                        offset_delta,
+                        locals: encode(&locals[previous_locals.len()..])?,
+                    }
+                }
+                stack => StackMapFrame::Full {
+                    offset_delta,
+                    locals: encode(&locals)?,
+                    stack: encode(stack)?,
+                },
+            };
+
+            stack_map_table.push(stack_map_frame);
+            previous_locals = locals;
+        }
+
+        Ok(stack_map_table)
+    }
+}
+
+fn verification_type_info(
+    t: &VerificationType,
+    constant_pool: &mut ConstantPool,
+) -> Result<VerificationTypeInfo> {
+    Ok(match t {
+        VerificationType::Top => VerificationTypeInfo::Top,
+        VerificationType::Integer => VerificationTypeInfo::Integer,
+        VerificationType::Float => VerificationTypeInfo::Float,
+        VerificationType::Long => VerificationTypeInfo::Long,
+        VerificationType::Double => VerificationTypeInfo::Double,
+        VerificationType::Null => VerificationTypeInfo::Null,
+        VerificationType::UninitializedThis => VerificationTypeInfo::UninitializedThis,
+        VerificationType::Uninitialized(offset) => {
+            VerificationTypeInfo::Uninitialized { offset: *offset }
+        }
+        VerificationType::Reference(name) => VerificationTypeInfo::Object {
+            cpool_index: constant_pool.find_or_insert_class(name)?.get(),
+        },
+        // Code with subroutines is rejected before its frames are computed.
+        VerificationType::ReturnAddress(_) => unreachable!("return addresses have no frames"),
+    })
+}
+
+impl ClassFile {
+    /// Computes the `max_stack`, `max_locals` and stack map frames of the method's code by type
+    /// inference, ignoring the values in its Code attribute. The class hierarchy resolves the
+    /// common superclasses of the types merged where control flow joins. Code that uses `jsr` or
+    /// `ret`, or that has unreachable instructions, can't be described by stack map frames.
+    pub fn compute_frames(
+        &self,
+        method: &MethodInfo,
+        hierarchy: &impl ClassHierarchy,
+    ) -> std::result::Result<ComputedFrames, VerifyError> {
+        let Some(code) = method.attributes.code_attribute() else {
+            return Err(VerifyError {
+                method: format!("#{}", method.name_index),
+                pc: None,
+                message: "Method has no code".to_owned(),
+            });
+        };
+
+        // The variables of the parameters and those the instructions access. An invalid descriptor
+        // or instruction is reported by the context or the inference.
+        let is_static = method.access_flags.contains(MethodAccessFlags::STATIC);
+        let parameter_slots = self
+            .method_type(method)
+            .map_or(0, |descriptor| descriptor.parameter_slots());
+        let instructions = code
+            .instructions()
+            .map_while(|instruction| instruction.ok())
+            .collect::<Vec<_>>();
+        let mut max_locals = parameter_slots + u16::from(!is_static);
+        for (_, instruction) in &instructions {
+            if let Some(index) = local_index(instruction) {
+                let size = if type_inference::is_wide_local(instruction) {
+                    2
+                } else {
+                    1
+                };
+                max_locals = max_locals.max(index.saturating_add(size));
+            }
+        }
+
+        // Analyze the code with enough room for any operand stack.
+        let mut unbounded = code.clone();
+        unbounded.max_stack = u16::MAX;
+        unbounded.max_locals = max_locals;
+        let context = Context::new(self, method, &unbounded, hierarchy)?;
+        let error = |(pc, message)| context.error(pc, message);
+
+        for (pc, instruction) in &instructions {
+            if matches!(
+                instruction,
+                Instruction::Jsr(_)
+                    | Instruction::JsrW(_)
+                    | Instruction::Ret(_)
+                    | Instruction::Wide(WideInstruction::Ret(_))
+            ) {
+                return Err(error((
+                    Some(*pc),
+                    format!(
+                        "{} can't be described by stack map frames",
+                        instruction.mnemonic()
+                    ),
+                )));
+            }
+        }
+        let inferred = type_inference::infer(&context).map_err(error)?;
+
+        let mut needed = code
+            .exception_table
+            .iter()
+            .map(|handler| handler.handler_pc as u32)
+            .collect::<BTreeSet<_>>();
+        for (i, (pc, instruction, _)) in inferred.iter().enumerate() {
+            needed.extend(
+                branch_targets(*pc, instruction, code.code.len())
+                    .map_err(|e| error((Some(*pc), e)))?,
+            );
+            if !falls_through(instruction) {
+                if let Some((next_pc, ..)) = inferred.get(i + 1) {
+                    needed.insert(*next_pc);
+                }
+            }
+        }
+
+        let mut frames = BTreeMap::new();
+        let mut max_stack = 0;
+        for (pc, _, frame) in inferred {
+            let Some(frame) = frame else {
+                return Err(error((Some(pc), "Unreachable code".to_owned())));
+            };
+            max_stack = max_stack.max(frame.stack_size());
+            frames.insert(pc, frame);
+        }
+        frames.retain(|pc, _| needed.contains(pc));
+
+        Ok(ComputedFrames {
+            max_stack,
+            max_locals,
+            frames,
+            initial_frame: context.initial_frame().map_err(|e| error((None, e)))?,
+        })
+    }
+
+    /// Replaces the `max_stack`, `max_locals` and `StackMapTable` of the code of the method at
+    /// `method_index` by computed values, adding the constant pool entries they need.
+    pub fn update_frames(
+        &mut self,
+        method_index: usize,
+        hierarchy: &impl ClassHierarchy,
+    ) -> std::result::Result<(), VerifyError> {
+        let computed = self.compute_frames(&self.methods[method_index], hierarchy)?;
+        let cp_error = |e: crate::ClassFileError| VerifyError {
+            method: format!("#{}", self.methods[method_index].name_index),
+            pc: None,
+            message: e.to_string(),
+        };
+        let mut constant_pool = std::mem::take(&mut self.constant_pool);
+        let result = computed
+            .stack_map_table(&mut constant_pool)
+            .and_then(|stack_map_table| {
+                let name_index = if stack_map_table.is_empty() {
+                    0
+                } else {
+                    constant_pool.find_or_insert_utf8("StackMapTable")?.get()
+                };
+                Ok((stack_map_table, name_index))
+            });
+        self.constant_pool = constant_pool;
+        let (stack_map_table, attribute_name_index) = result.map_err(cp_error)?;
+
+        let code = self.methods[method_index]
+            .attributes
+            .0
+            .iter_mut()
+            .find_map(|attribute| match &mut attribute.info {
+                AttributeInfo::Code(code) => Some(code),
+                _ => None,
+            })
+            .expect("compute_frames fails without code");
+        code.max_stack = computed.max_stack;
+        code.max_locals = computed.max_locals;
+        code.attributes
+            .0
+            .retain(|attribute| !matches!(attribute.info, AttributeInfo::StackMapTable(_)));
+        if !stack_map_table.is_empty() {
+            code.attributes.0.push(Attribute {
+                attribute_name_index,
+                info: AttributeInfo::StackMapTable(stack_map_table),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.10.1
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::{Context, Frame, MethodError, VerificationType, Verified};
+use crate::attributes::{StackMapFrame, VerificationTypeInfo};
+
+/// Checks that every instruction of the method is type safe given the frames declared by its
+/// `StackMapTable`.
+pub(super) fn check(context: &Context) -> Result<(), MethodError> {
+    let instructions = context.instructions()?;
+    let starts = instructions
+        .iter()
+        .map(|(pc, _)| *pc)
+        .collect::<HashSet<_>>();
+
+    let initial_frame = context.initial_frame().map_err(|e| (None, e))?;
+    let frames = stack_map_frames(context, &initial_frame)?;
+    if let Some(pc) = frames.keys().find(|pc| !starts.contains(pc)) {
+        return Err((Some(*pc), "StackMapTable error: bad offset".to_owned()));
+    }
+
+    context.check_exception_table(&starts)?;
+
+    let mut current = Some(initial_frame);
+    for (pc, instruction) in &instructions {
+        let pc = *pc;
+        let at = |message: String| (Some(pc), message);
+
+        if let Some(stack_map_frame) = frames.get(&pc) {
+            if let Some(frame) = &current {
+                check_assignable(context, frame, stack_map_frame).map_err(at)?;
+            }
+            current = Some(stack_map_frame.clone());
+        }
+        let Some(mut frame) = current.take() else {
+            return Err(at("Expecting a stackmap frame at branch target".to_owned()));
+        };
+
+        check_handlers(context, &frames, pc, &frame).map_err(at)?;
+
+        let flow = context.execute(&mut frame, pc, instruction).map_err(at)?;
+        for target in &flow.targets {
+            let target_frame = frames.get(target).ok_or_else(|| {
+                at(format!(
+                    "Expecting a stackmap frame at branch target {}",
+                    target
+                ))
+            })?;
+            check_assignable(context, &frame, target_frame).map_err(at)?;
+        }
+        if flow.falls_through {
+            current = Some(frame);
+        }
+    }
+
+    if current.is_some() {
+        return Err((None, "Falling off the end of the code".to_owned()));
+    }
+    Ok(())
+}
+
+/// Checks that the exception handlers covering `pc` accept the locals before the instruction.
+fn check_handlers(
+    context: &Context,
+    frames: &BTreeMap<u32, Frame>,
+    pc: u32,
+    frame: &Frame,
+) -> Verified {
+    for handler in &context.code.exception_table {
+        if !(handler.start_pc as u32..handler.end_pc as u32).contains(&pc) {
+            continue;
+        }
+
+        let exception_frame = Frame {
+            locals: frame.locals.clone(),
+            stack: vec![context.catch_type(handler)?],
+        };
+        let handler_frame = frames.get(&(handler.handler_pc as u32)).ok_or_else(|| {
+            format!(
+                "Expecting a stackmap frame at exception handler {}",
+                handler.handler_pc
+            )
+        })?;
+        check_assignable(context, &exception_frame, handler_frame)
+            .map_err(|e| format!("Bad exception handler {}: {}", handler.handler_pc, e))?;
+    }
+    Ok(())
+}
+
+/// Checks that the frame may flow into a frame declared by the `StackMapTable`.
+fn check_assignable(context: &Context, from: &Frame, to: &Frame) -> Verified {
+    if from.stack.len() != to.stack.len() {
+        return Err(format!(
+            "Inconsistent stack height {} != {}",
+            from.stack.len(),
+            to.stack.len()
+        ));
+    }
+    for (i, (from, to)) in from.locals.iter().zip(&to.locals).enumerate() {
+        if !context.is_assignable(from, to) {
+            return Err(format!(
+                "Type {} (current frame, locals[{}]) is not assignable to {} (stack map, locals[{}])",
+                from, i, to, i
+            ));
+        }
+    }
+    for (i, (from, to)) in from.stack.iter().zip(&to.stack).enumerate() {
+        if !context.is_assignable(from, to) {
+            return Err(format!(
+                "Type {} (current frame, stack[{}]) is not assignable to {} (stack map, stack[{}])",
+                from, i, to, i
+            ));
+        }
+    }
+    if from.this_uninitialized() && !to.this_uninitialized() {
+        return Err("Uninitialized this in current frame is not in stack map".to_owned());
+    }
+    Ok(())
+}
+
+/// Expands the `StackMapTable` into the full frame at each pc it declares one for.
+fn stack_map_frames(
+    context: &Context,
+    initial_frame: &Frame,
+) -> Result<BTreeMap<u32, Frame>, MethodError> {
+    let mut frames = BTreeMap::new();
+    let Some(stack_map_table) = context.code.attributes.stack_map_table() else {
+        return Ok(frames);
+    };
+
+    // The locals as declared by the frames, with one entry for a long or double.
+    let mut locals = initial_frame.declared_locals();
+    let mut previous_pc: Option<u32> = None;
+    for stack_map_frame in stack_map_table {
+        let pc = match previous_pc {
+            Some(previous_pc) => previous_pc + stack_map_frame.offset_delta() as u32 + 1,
+            None => stack_map_frame.offset_delta() as u32,
+        };
+        previous_pc = Some(pc);
+        let at = |message: String| (Some(pc), format!("StackMapTable error: {}", message));
+
+        let stack = match stack_map_frame {
+            StackMapFrame::Same { .. } | StackMapFrame::SameExtended { .. } => vec![],
+            StackMapFrame::SameLocals1StackItem { stack, .. }
+            | StackMapFrame::SameLocals1StackItemExtended { stack, .. } => {
+                vec![verification_type(context, stack).map_err(at)?]
+            }
+            StackMapFrame::Chop { k, .. } => {
+                let k = *k as usize;
+                if k > locals.len() {
+                    return Err(at("chop frame removes too many locals".to_owned()));
+                }
+                locals.truncate(locals.len() - k);
+                vec![]
+            }
+            StackMapFrame::Append {
+                locals: appended, ..
+            } => {
+                for t in appended {
+                    locals.push(verification_type(context, t).map_err(at)?);
+                }
+                vec![]
+            }
+            StackMapFrame::Full {
+                locals: full_locals,
+                stack,
+                ..
+            } => {
+                locals = full_locals
+                    .iter()
+                    .map(|t| verification_type(context, t))
+                    .collect::<Verified<_>>()
+                    .map_err(at)?;
+                stack
+                    .iter()
+                    .map(|t| verification_type(context, t))
+                    .collect::<Verified<_>>()
+                    .map_err(at)?
+            }
+        };
+
+        let mut expanded = vec![];
+        for t in &locals {
+            expanded.push(t.clone());
+            if t.is_category2() {
+                expanded.push(VerificationType::Top);
+            }
+        }
+        if expanded.len() > context.code.max_locals as usize {
+            return Err(at("local variable table overflow".to_owned()));
+        }
+        expanded.resize(context.code.max_locals as usize, VerificationType::Top);
+
+        let frame = Frame {
+            locals: expanded,
+            stack,
+        };
+        if frame.stack_size() > context.code.max_stack {
+            return Err(at("operand stack overflow".to_owned()));
+        }
+        frames.insert(pc, frame);
+    }
+
+    Ok(frames)
+}
+
+fn verification_type(context: &Context, t: &VerificationTypeInfo) -> Verified<VerificationType> {
+    Ok(match t {
+        VerificationTypeInfo::Top => VerificationType::Top,
+        VerificationTypeInfo::Integer => VerificationType::Integer,
+        VerificationTypeInfo::Float => VerificationType::Float,
+        VerificationTypeInfo::Double => VerificationType::Double,
+        VerificationTypeInfo::Long => VerificationType::Long,
+        VerificationTypeInfo::Null => VerificationType::Null,
+        VerificationTypeInfo::UninitializedThis => VerificationType::UninitializedThis,
+        VerificationTypeInfo::Object { cpool_index } => {
+            VerificationType::Reference(context.class_ref(*cpool_index)?.to_owned())
+        }
+        VerificationTypeInfo::Uninitialized { offset } => {
+            context.new_class(*offset)?;
+            VerificationType::Uninitialized(*offset)
+        }
+    })
+}
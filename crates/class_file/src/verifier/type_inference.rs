@@ -0,0 +1,402 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.10.2
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{
+    instructions::{branch_targets, local_index},
+    Context, Frame, MethodError, VerificationType, Verified,
+};
+use crate::instruction::{Instruction, WideInstruction};
+
+/// What is known about the frame before an instruction.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    frame: Frame,
+    /// Whether `this` may still be uninitialized, even if merging has replaced it by `Top`.
+    this_uninitialized: bool,
+}
+
+/// The data-flow analysis of a method, which infers the frame before each instruction by merging
+/// the frames of its predecessors until nothing changes.
+struct Inference<'c, 'a> {
+    context: &'c Context<'a>,
+    instructions: Vec<(u32, Instruction)>,
+    /// The index into `instructions` of each pc.
+    indices: HashMap<u32, usize>,
+    states: Vec<Option<State>>,
+    changed: BTreeSet<usize>,
+    /// The indices of the `jsr` instructions calling each subroutine.
+    callers: HashMap<u32, BTreeSet<usize>>,
+    /// The indices of the `ret` instructions that have returned from each subroutine.
+    returns: HashMap<u32, BTreeSet<usize>>,
+    /// The local variables each subroutine accesses or modifies.
+    subroutine_locals: HashMap<u32, HashSet<u16>>,
+}
+
+/// Verifies the method by type inference, as required for class files older than version 50,
+/// returning each instruction with the frame before it. Unreachable instructions have no frame.
+pub(super) fn infer(
+    context: &Context,
+) -> Result<Vec<(u32, Instruction, Option<Frame>)>, MethodError> {
+    let instructions = context.instructions()?;
+    let indices = instructions
+        .iter()
+        .enumerate()
+        .map(|(i, (pc, _))| (*pc, i))
+        .collect::<HashMap<_, _>>();
+    context.check_exception_table(&indices.keys().copied().collect())?;
+
+    let initial_frame = context.initial_frame().map_err(|e| (None, e))?;
+    let mut inference = Inference {
+        context,
+        states: vec![None; instructions.len()],
+        instructions,
+        indices,
+        changed: BTreeSet::new(),
+        callers: HashMap::new(),
+        returns: HashMap::new(),
+        subroutine_locals: HashMap::new(),
+    };
+    let this_uninitialized = initial_frame.this_uninitialized();
+    inference
+        .merge_into(0, initial_frame, this_uninitialized)
+        .map_err(|e| (Some(0), e))?;
+
+    while let Some(i) = inference.changed.pop_first() {
+        let pc = inference.instructions[i].0;
+        inference.step(i).map_err(|e| (Some(pc), e))?;
+    }
+
+    Ok(inference
+        .instructions
+        .into_iter()
+        .zip(inference.states)
+        .map(|((pc, instruction), state)| (pc, instruction, state.map(|state| state.frame)))
+        .collect())
+}
+
+impl Inference<'_, '_> {
+    fn index(&self, pc: u32) -> Verified<usize> {
+        self.indices
+            .get(&pc)
+            .copied()
+            .ok_or_else(|| format!("Illegal target of jump or branch: {}", pc))
+    }
+
+    /// Executes the instruction at index `i` and merges the resulting frames into its successors.
+    fn step(&mut self, i: usize) -> Verified {
+        let context = self.context;
+        let (pc, instruction) = self.instructions[i].clone();
+        let State {
+            mut frame,
+            this_uninitialized,
+        } = self.states[i]
+            .clone()
+            .expect("changed instructions have a state");
+
+        for handler in &context.code.exception_table {
+            if (handler.start_pc as u32..handler.end_pc as u32).contains(&pc) {
+                let exception_frame = Frame {
+                    locals: frame.locals.clone(),
+                    stack: vec![context.catch_type(handler)?],
+                };
+                let handler_index = self.index(handler.handler_pc as u32)?;
+                self.merge_into(handler_index, exception_frame, this_uninitialized)?;
+            }
+        }
+
+        let code_length = context.code.code.len();
+        match instruction {
+            Instruction::Jsr(_) | Instruction::JsrW(_) => {
+                let target = branch_targets(pc, &instruction, code_length)?[0];
+                self.callers.entry(target).or_default().insert(i);
+                context.push(&mut frame, VerificationType::ReturnAddress(target))?;
+                self.merge_into(self.index(target)?, frame, this_uninitialized)?;
+
+                // The subroutine may already have returned to its other callers.
+                for &r in self.returns.get(&target).into_iter().flatten() {
+                    self.changed.insert(r);
+                }
+            }
+            Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_)) => {
+                let index = local_index(&instruction).unwrap();
+                let target = match context.local(&frame, index)? {
+                    VerificationType::ReturnAddress(target) => target,
+                    t => return Err(format!("Bad local variable type in ret: {}", t)),
+                };
+                self.returns.entry(target).or_default().insert(i);
+                self.return_from(target, &frame, this_uninitialized)?;
+            }
+            instruction => {
+                if instruction == Instruction::Return
+                    && context.is_constructor()
+                    && this_uninitialized
+                {
+                    return Err("Constructor must call super() or this() before return".to_owned());
+                }
+
+                let calls_constructor = matches!(instruction, Instruction::Invokespecial(_))
+                    && frame.stack.contains(&VerificationType::UninitializedThis);
+                let flow = context.execute(&mut frame, pc, &instruction)?;
+                let this_uninitialized = this_uninitialized
+                    && !(calls_constructor
+                        && !frame.stack.contains(&VerificationType::UninitializedThis)
+                        && !frame.this_uninitialized());
+                for target in flow.targets {
+                    self.merge_into(self.index(target)?, frame.clone(), this_uninitialized)?;
+                }
+                if flow.falls_through {
+                    if i + 1 == self.instructions.len() {
+                        return Err("Falling off the end of the code".to_owned());
+                    }
+                    self.merge_into(i + 1, frame, this_uninitialized)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges the frame at a `ret` into the instructions following the `jsr`s that call the
+    /// subroutine. Local variables the subroutine doesn't use keep their types from before the
+    /// `jsr`.
+    fn return_from(&mut self, target: u32, frame: &Frame, this_uninitialized: bool) -> Verified {
+        let used = self.subroutine_locals(target)?;
+        let callers = self.callers.get(&target).cloned().unwrap_or_default();
+        for caller in callers {
+            if caller + 1 == self.instructions.len() {
+                return Err("Falling off the end of the code".to_owned());
+            }
+            let Some(State {
+                frame: caller_frame,
+                ..
+            }) = &self.states[caller]
+            else {
+                continue;
+            };
+
+            let locals = (0..frame.locals.len())
+                .map(|index| {
+                    if used.contains(&(index as u16)) {
+                        frame.locals[index].clone()
+                    } else {
+                        caller_frame.locals[index].clone()
+                    }
+                })
+                .collect();
+            let returned = Frame {
+                locals,
+                stack: frame.stack.clone(),
+            };
+            self.merge_into(caller + 1, returned, this_uninitialized)?;
+        }
+        Ok(())
+    }
+
+    /// The local variables that the subroutine starting at `target`, including the subroutines it
+    /// calls, accesses or modifies.
+    fn subroutine_locals(&mut self, target: u32) -> Verified<HashSet<u16>> {
+        if let Some(used) = self.subroutine_locals.get(&target) {
+            return Ok(used.clone());
+        }
+
+        let code_length = self.context.code.code.len();
+        let mut used = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.index(target)?];
+        while let Some(i) = pending.pop() {
+            if !visited.insert(i) {
+                continue;
+            }
+
+            let (pc, instruction) = &self.instructions[i];
+            if let Some(index) = local_index(instruction) {
+                used.insert(index);
+                if is_wide_local(instruction) {
+                    used.insert(index + 1);
+                }
+            }
+
+            match instruction {
+                Instruction::Ret(_)
+                | Instruction::Wide(WideInstruction::Ret(_))
+                | Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Freturn
+                | Instruction::Dreturn
+                | Instruction::Areturn
+                | Instruction::Return
+                | Instruction::Athrow => continue,
+                Instruction::Goto(_)
+                | Instruction::GotoW(_)
+                | Instruction::Tableswitch { .. }
+                | Instruction::Lookupswitch { .. } => {}
+                // Nested subroutines are followed into and return to the next instruction.
+                _ if i + 1 < self.instructions.len() => pending.push(i + 1),
+                _ => {}
+            }
+            for target in branch_targets(*pc, instruction, code_length)? {
+                pending.push(self.index(target)?);
+            }
+        }
+
+        self.subroutine_locals.insert(target, used.clone());
+        Ok(used)
+    }
+
+    /// Merges the frame into the state before the instruction at index `i`, marking it as changed
+    /// if that adds anything.
+    fn merge_into(&mut self, i: usize, frame: Frame, this_uninitialized: bool) -> Verified {
+        let merged = match &self.states[i] {
+            None => State {
+                frame,
+                this_uninitialized,
+            },
+            Some(state) => State {
+                frame: self.context.merge(&state.frame, &frame)?,
+                this_uninitialized: state.this_uninitialized || this_uninitialized,
+            },
+        };
+
+        if self.states[i].as_ref() != Some(&merged) {
+            self.states[i] = Some(merged);
+            self.changed.insert(i);
+        }
+        Ok(())
+    }
+}
+
+/// Whether the load or store instruction accesses a long or double, which takes two local
+/// variables.
+pub(crate) fn is_wide_local(instruction: &Instruction) -> bool {
+    use Instruction::*;
+
+    matches!(
+        instruction,
+        Lload(_)
+            | Lload0
+            | Lload1
+            | Lload2
+            | Lload3
+            | Dload(_)
+            | Dload0
+            | Dload1
+            | Dload2
+            | Dload3
+            | Lstore(_)
+            | Lstore0
+            | Lstore1
+            | Lstore2
+            | Lstore3
+            | Dstore(_)
+            | Dstore0
+            | Dstore1
+            | Dstore2
+            | Dstore3
+            | Wide(
+                WideInstruction::Lload(_)
+                    | WideInstruction::Dload(_)
+                    | WideInstruction::Lstore(_)
+                    | WideInstruction::Dstore(_)
+            )
+    )
+}
+
+impl Context<'_> {
+    /// Merges two frames flowing into the same instruction. Local variables of different types
+    /// become unusable, while the operand stacks must have the same shape.
+    pub(crate) fn merge(&self, a: &Frame, b: &Frame) -> Verified<Frame> {
+        if a.stack.len() != b.stack.len() {
+            return Err(format!(
+                "Inconsistent stack height {} != {}",
+                a.stack.len(),
+                b.stack.len()
+            ));
+        }
+
+        let locals = a
+            .locals
+            .iter()
+            .zip(&b.locals)
+            .map(|(a, b)| self.merge_types(a, b).unwrap_or(VerificationType::Top))
+            .collect();
+        let stack = a
+            .stack
+            .iter()
+            .zip(&b.stack)
+            .map(|(a, b)| {
+                self.merge_types(a, b)
+                    .ok_or_else(|| format!("Mismatched stack types {} and {}", a, b))
+            })
+            .collect::<Verified<_>>()?;
+
+        Ok(Frame { locals, stack })
+    }
+
+    /// The most specific type both types are assignable to, or `None` if that is `Top`.
+    fn merge_types(&self, a: &VerificationType, b: &VerificationType) -> Option<VerificationType> {
+        match (a, b) {
+            _ if a == b => Some(a.clone()),
+            (VerificationType::Null, VerificationType::Reference(_)) => Some(b.clone()),
+            (VerificationType::Reference(_), VerificationType::Null) => Some(a.clone()),
+            (VerificationType::Reference(a), VerificationType::Reference(b)) => {
+                Some(VerificationType::Reference(self.common_super_class(a, b)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The most specific class or array type that both reference types are assignable to.
+    /// Interfaces are treated as `java/lang/Object`.
+    pub(crate) fn common_super_class(&self, a: &str, b: &str) -> String {
+        if self.is_java_assignable(a, b) && !self.is_interface(b) {
+            return b.to_owned();
+        }
+        if self.is_java_assignable(b, a) && !self.is_interface(a) {
+            return a.to_owned();
+        }
+
+        match (a.strip_prefix('['), b.strip_prefix('[')) {
+            (Some(a), Some(b)) => {
+                return match (super::reference_name(a), super::reference_name(b)) {
+                    (Some(a), Some(b)) => {
+                        let component = self.common_super_class(a, b);
+                        if component.starts_with('[') {
+                            format!("[{}", component)
+                        } else {
+                            format!("[L{};", component)
+                        }
+                    }
+                    _ => "java/lang/Object".to_owned(),
+                };
+            }
+            (None, None) => {}
+            _ => return "java/lang/Object".to_owned(),
+        }
+
+        let super_classes = self.super_classes(a);
+        self.super_classes(b)
+            .into_iter()
+            .find(|class_name| super_classes.contains(class_name))
+            .unwrap_or_else(|| "java/lang/Object".to_owned())
+    }
+
+    fn is_interface(&self, class_name: &str) -> bool {
+        self.resolve(class_name)
+            .is_some_and(|class| class.is_interface)
+    }
+
+    /// The class and its superclasses, as far as they can be resolved.
+    fn super_classes(&self, class_name: &str) -> Vec<String> {
+        let mut super_classes = vec![class_name.to_owned()];
+        while let Some(super_class) = self
+            .resolve(super_classes.last().unwrap())
+            .and_then(|class| class.super_class)
+        {
+            if super_classes.contains(&super_class) {
+                break;
+            }
+            super_classes.push(super_class);
+        }
+        super_classes
+    }
+}
@@ -0,0 +1,122 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.10.1.2
+
+use std::fmt;
+
+use crate::{
+    descriptor::{BaseType, FieldType},
+    ClassAccessFlags, ClassFile, Result,
+};
+
+/// The types the verifier tracks in local variables and on the operand stack. Long and double
+/// values take two local variables, of which the second is `Top`, but a single operand stack entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    /// The `this` of an instance initialization method before the superclass constructor is called.
+    UninitializedThis,
+    /// An object created by the `new` instruction at the given offset, before its constructor is
+    /// called.
+    Uninitialized(u16),
+    /// A class or interface named in internal form, or an array named by its descriptor.
+    Reference(String),
+    /// The return address pushed by `jsr`, identified by the pc of the subroutine it calls. Only
+    /// class files verified by type inference use subroutines.
+    ReturnAddress(u32),
+}
+impl VerificationType {
+    pub fn object() -> Self {
+        Self::Reference("java/lang/Object".to_owned())
+    }
+
+    /// The type of a value of the field type. Booleans, bytes, chars and shorts are ints.
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(BaseType::Long) => Self::Long,
+            FieldType::Base(BaseType::Float) => Self::Float,
+            FieldType::Base(BaseType::Double) => Self::Double,
+            FieldType::Base(_) => Self::Integer,
+            FieldType::Object(class_name) => Self::Reference(class_name.clone()),
+            array => Self::Reference(array.to_string()),
+        }
+    }
+
+    pub fn is_category2(&self) -> bool {
+        matches!(self, Self::Long | Self::Double)
+    }
+
+    /// The number of local variables or operand stack words the type takes.
+    pub fn size(&self) -> u16 {
+        if self.is_category2() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Whether the type is a reference to an object, including uninitialized objects.
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            Self::Null | Self::UninitializedThis | Self::Uninitialized(_) | Self::Reference(_)
+        )
+    }
+
+    /// The descriptor of the component type if this is an array type.
+    pub(crate) fn component_descriptor(&self) -> Option<&str> {
+        match self {
+            Self::Reference(name) => name.strip_prefix('['),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for VerificationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Top => f.write_str("top"),
+            Self::Integer => f.write_str("integer"),
+            Self::Float => f.write_str("float"),
+            Self::Long => f.write_str("long"),
+            Self::Double => f.write_str("double"),
+            Self::Null => f.write_str("null"),
+            Self::UninitializedThis => f.write_str("uninitializedThis"),
+            Self::Uninitialized(offset) => write!(f, "uninitialized({})", offset),
+            Self::Reference(name) => write!(f, "'{}'", name),
+            Self::ReturnAddress(pc) => write!(f, "returnAddress({})", pc),
+        }
+    }
+}
+
+/// What the verifier needs to know about a class it is not verifying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedClass {
+    /// The direct superclass in internal form, `None` for `java/lang/Object`.
+    pub super_class: Option<String>,
+    pub is_interface: bool,
+}
+impl ResolvedClass {
+    pub fn from_class_file(class_file: &ClassFile) -> Result<Self> {
+        Ok(Self {
+            super_class: class_file.super_class()?.map(str::to_owned),
+            is_interface: class_file
+                .access_flags
+                .contains(ClassAccessFlags::INTERFACE),
+        })
+    }
+}
+
+/// Resolves the classes that the verifier needs to check assignability between reference types.
+/// Any `Fn(&str) -> Option<ResolvedClass>` is a class hierarchy.
+pub trait ClassHierarchy {
+    /// Resolves the class or interface named in internal form, or returns `None` if it is unknown.
+    fn resolve(&self, class_name: &str) -> Option<ResolvedClass>;
+}
+impl<F: Fn(&str) -> Option<ResolvedClass>> ClassHierarchy for F {
+    fn resolve(&self, class_name: &str) -> Option<ResolvedClass> {
+        self(class_name)
+    }
+}
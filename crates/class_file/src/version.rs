@@ -0,0 +1,100 @@
+// https://docs.oracle.com/javase/specs/jvms/se19/html/jvms-4.html#jvms-4.1-200-B.2
+
+use std::fmt;
+
+use crate::ClassFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassFileVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+impl ClassFileVersion {
+    /// The oldest version a Java Virtual Machine may support, produced by JDK 1.0.2 and 1.1.
+    pub const OLDEST: Self = Self::new(45, 0);
+    /// The newest version the parser accepts by default, produced by Java SE 21.
+    pub const LATEST: Self = Self::new(65, 0);
+
+    /// A class file depends on the preview features of its Java SE release if the minor version
+    /// has all bits set.
+    const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// The version of class files produced by a Java SE release, e.g. 52.0 for 8 and 46.0 for 1.2.
+    pub fn for_java_release(release: u16) -> Self {
+        Self::new(release.max(1) + 44, 0)
+    }
+
+    /// The Java SE release that introduced this major version, with 1.1 through 1.4 numbered 1
+    /// through 4. `None` if the major version predates Java.
+    pub fn java_release(&self) -> Option<u16> {
+        self.major.checked_sub(44).filter(|release| *release > 0)
+    }
+
+    pub fn is_preview(&self) -> bool {
+        // Preview features only exist from Java SE 12 (56) onwards.
+        self.major >= 56 && self.minor == Self::PREVIEW_MINOR_VERSION
+    }
+}
+impl fmt::Display for ClassFileVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        match self.java_release() {
+            Some(release @ 1..=4) => write!(f, " (Java 1.{})", release)?,
+            Some(release) if self.is_preview() => write!(f, " (Java {} preview)", release)?,
+            Some(release) => write!(f, " (Java {})", release)?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+impl ClassFile {
+    pub fn version(&self) -> ClassFileVersion {
+        ClassFileVersion::new(self.major_version, self.minor_version)
+    }
+}
+
+#[cfg(test)]
+mod version_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_map_major_versions_to_java_releases() {
+        assert_eq!(None, ClassFileVersion::new(44, 0).java_release());
+        assert_eq!(Some(1), ClassFileVersion::new(45, 3).java_release());
+        assert_eq!(Some(8), ClassFileVersion::new(52, 0).java_release());
+        assert_eq!(Some(21), ClassFileVersion::new(65, 0).java_release());
+        assert_eq!(Some(22), ClassFileVersion::new(66, 0).java_release());
+        assert_eq!(
+            ClassFileVersion::new(61, 0),
+            ClassFileVersion::for_java_release(17)
+        );
+    }
+
+    #[test]
+    fn it_should_detect_preview_versions() {
+        assert!(ClassFileVersion::new(61, 0xFFFF).is_preview());
+        assert!(!ClassFileVersion::new(61, 0).is_preview());
+        assert!(!ClassFileVersion::new(45, 0xFFFF).is_preview());
+    }
+
+    #[test]
+    fn it_should_display_the_java_release() {
+        assert_eq!("45.3 (Java 1.1)", ClassFileVersion::new(45, 3).to_string());
+        assert_eq!("49.0 (Java 5)", ClassFileVersion::new(49, 0).to_string());
+        assert_eq!(
+            "65.65535 (Java 21 preview)",
+            ClassFileVersion::new(65, 0xFFFF).to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_order_by_major_then_minor() {
+        assert!(ClassFileVersion::new(45, 3) < ClassFileVersion::new(46, 0));
+        assert!(ClassFileVersion::new(61, 0) < ClassFileVersion::new(61, 0xFFFF));
+    }
+}
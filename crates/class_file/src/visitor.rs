@@ -0,0 +1,314 @@
+// A streaming API in the style of ASM: a `ClassReader` calls a `ClassVisitor` for each part of a
+// class file, without building a `ClassFile`. Each visitor may forward its calls to a delegate,
+// so that transformations are written as adapters between a reader and a `ClassWriter`, which
+// only override the calls they change.
+
+mod writer;
+
+use std::borrow::Cow;
+
+use bitflags::bitflags;
+
+pub use self::writer::ClassWriter;
+use crate::{
+    attributes::ExceptionTableEntry,
+    borrowed::{self, Attribute, ConstantPool},
+    ClassAccessFlags, ClassFileVersion, FieldAccessFlags, Instruction, MethodAccessFlags, Result,
+};
+
+bitflags! {
+    /// The parts of a class file a [`ClassReader`] does not visit.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ReaderFlags: u8 {
+        /// The Code attributes of methods.
+        const SKIP_CODE = 0x01;
+        /// The SourceFile, SourceDebugExtension, LineNumberTable, LocalVariableTable and
+        /// LocalVariableTypeTable attributes.
+        const SKIP_DEBUG = 0x02;
+        /// The StackMapTable attributes.
+        const SKIP_FRAMES = 0x04;
+    }
+}
+
+/// The class declared by a class file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassHeader<'a> {
+    pub version: ClassFileVersion,
+    pub access_flags: ClassAccessFlags,
+    pub name: Cow<'a, str>,
+    /// The name of the direct superclass, `None` for `java/lang/Object`.
+    pub super_name: Option<Cow<'a, str>>,
+    pub interfaces: Vec<Cow<'a, str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldHeader<'a> {
+    pub access_flags: FieldAccessFlags,
+    pub name: Cow<'a, str>,
+    pub descriptor: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodHeader<'a> {
+    pub access_flags: MethodAccessFlags,
+    pub name: Cow<'a, str>,
+    pub descriptor: Cow<'a, str>,
+}
+
+/// Visits a class. The calls are made in this order: `visit`, then `visit_field` and
+/// `visit_method` for each member, then `visit_attribute` for each attribute of the class and
+/// finally `visit_end`.
+///
+/// The constant pool indices in attributes and instructions refer to the constant pool passed to
+/// `visit`. The names in the headers are resolved, so that adapters can change them freely.
+pub trait ClassVisitor {
+    /// The visitor every call is forwarded to unless it is overridden, `None` to ignore the calls.
+    fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+        None
+    }
+
+    fn visit(&mut self, header: &ClassHeader, constant_pool: &ConstantPool) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit(header, constant_pool),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the visitor of the field, or `None` to skip it.
+    fn visit_field(&mut self, header: &FieldHeader) -> Result<Option<Box<dyn FieldVisitor + '_>>> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_field(header),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the visitor of the method, or `None` to skip it.
+    fn visit_method(
+        &mut self,
+        header: &MethodHeader,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_method(header),
+            None => Ok(None),
+        }
+    }
+
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(attribute),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Visits a field: `visit_attribute` for each of its attributes, then `visit_end`.
+pub trait FieldVisitor {
+    /// The visitor every call is forwarded to unless it is overridden, `None` to ignore the calls.
+    fn delegate(&mut self) -> Option<&mut dyn FieldVisitor> {
+        None
+    }
+
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(attribute),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Visits a method: `visit_attribute` for each of its attributes other than Code, and where the
+/// Code attribute is, `visit_code` followed by the instructions, exception handlers and
+/// attributes of the code and `visit_maxs`. Finally `visit_end`.
+pub trait MethodVisitor {
+    /// The visitor every call is forwarded to unless it is overridden, `None` to ignore the calls.
+    fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+        None
+    }
+
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(attribute),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts the code of the method. Returns whether to visit it, `false` to skip the code.
+    fn visit_code(&mut self) -> Result<bool> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_code(),
+            None => Ok(false),
+        }
+    }
+
+    /// Visits the instruction at `pc` in the original code.
+    fn visit_instruction(&mut self, pc: u32, instruction: &Instruction) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_instruction(pc, instruction),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_exception_handler(&mut self, handler: &ExceptionTableEntry) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_exception_handler(handler),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits an attribute of the Code attribute, e.g. a LineNumberTable.
+    fn visit_code_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_code_attribute(attribute),
+            None => Ok(()),
+        }
+    }
+
+    /// Ends the code of the method.
+    fn visit_maxs(&mut self, max_stack: u16, max_locals: u16) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_maxs(max_stack, max_locals),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a class file and makes a [`ClassVisitor`] visit it. Like the borrowed
+/// [`borrowed::ClassFile`] it is based on, the reader only decodes what is visited.
+pub struct ClassReader<'a> {
+    class_file: borrowed::ClassFile<'a>,
+}
+impl<'a> ClassReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        Ok(Self {
+            class_file: borrowed::ClassFile::parse(bytes)?,
+        })
+    }
+
+    pub fn class_file(&self) -> &borrowed::ClassFile<'a> {
+        &self.class_file
+    }
+
+    pub fn accept(&self, visitor: &mut dyn ClassVisitor, flags: ReaderFlags) -> Result<()> {
+        let class_file = &self.class_file;
+        let constant_pool = &class_file.constant_pool;
+
+        let header = ClassHeader {
+            version: ClassFileVersion::new(class_file.major_version, class_file.minor_version),
+            access_flags: class_file.access_flags,
+            name: class_file.class_name()?,
+            super_name: class_file.super_class()?,
+            interfaces: class_file
+                .interfaces
+                .iter()
+                .map(|interface| constant_pool.class_name(*interface))
+                .collect::<Result<_>>()?,
+        };
+        visitor.visit(&header, constant_pool)?;
+
+        for field in &class_file.fields {
+            let header = FieldHeader {
+                access_flags: field.access_flags,
+                name: class_file.field_name(field)?,
+                descriptor: class_file.field_descriptor(field)?,
+            };
+            let Some(mut field_visitor) = visitor.visit_field(&header)? else {
+                continue;
+            };
+            for attribute in field.attributes.iter() {
+                if !self.skips(attribute, flags)? {
+                    field_visitor.visit_attribute(attribute)?;
+                }
+            }
+            field_visitor.visit_end()?;
+        }
+
+        for method in &class_file.methods {
+            let header = MethodHeader {
+                access_flags: method.access_flags,
+                name: class_file.method_name(method)?,
+                descriptor: class_file.method_descriptor(method)?,
+            };
+            let Some(mut method_visitor) = visitor.visit_method(&header)? else {
+                continue;
+            };
+            for attribute in method.attributes.iter() {
+                if attribute.name(constant_pool)? == "Code" {
+                    if !flags.contains(ReaderFlags::SKIP_CODE) && method_visitor.visit_code()? {
+                        self.accept_code(attribute, method_visitor.as_mut(), flags)?;
+                    }
+                } else if !self.skips(attribute, flags)? {
+                    method_visitor.visit_attribute(attribute)?;
+                }
+            }
+            method_visitor.visit_end()?;
+        }
+
+        for attribute in class_file.attributes.iter() {
+            if !self.skips(attribute, flags)? {
+                visitor.visit_attribute(attribute)?;
+            }
+        }
+        visitor.visit_end()
+    }
+
+    fn accept_code(
+        &self,
+        attribute: &Attribute<'a>,
+        visitor: &mut dyn MethodVisitor,
+        flags: ReaderFlags,
+    ) -> Result<()> {
+        let code = borrowed::CodeAttribute::parse(attribute.info)?;
+        for instruction in code.instructions() {
+            let (pc, instruction) = instruction?;
+            visitor.visit_instruction(pc, &instruction)?;
+        }
+        for handler in &code.exception_table {
+            visitor.visit_exception_handler(handler)?;
+        }
+        for attribute in code.attributes.iter() {
+            if !self.skips(attribute, flags)? {
+                visitor.visit_code_attribute(attribute)?;
+            }
+        }
+        visitor.visit_maxs(code.max_stack, code.max_locals)
+    }
+
+    fn skips(&self, attribute: &Attribute, flags: ReaderFlags) -> Result<bool> {
+        if !flags.intersects(ReaderFlags::SKIP_DEBUG | ReaderFlags::SKIP_FRAMES) {
+            return Ok(false);
+        }
+
+        Ok(
+            match attribute.name(&self.class_file.constant_pool)?.as_ref() {
+                "SourceFile"
+                | "SourceDebugExtension"
+                | "LineNumberTable"
+                | "LocalVariableTable"
+                | "LocalVariableTypeTable" => flags.contains(ReaderFlags::SKIP_DEBUG),
+                "StackMapTable" => flags.contains(ReaderFlags::SKIP_FRAMES),
+                _ => false,
+            },
+        )
+    }
+}
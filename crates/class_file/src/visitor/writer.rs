@@ -0,0 +1,254 @@
+use crate::{
+    attributes::ExceptionTableEntry,
+    borrowed::{Attribute, ConstantPool},
+    constant_pool::{ClassInfo, CpIndex},
+    ClassAccessFlags, ClassFileError, FieldAccessFlags, Instruction, MethodAccessFlags, Result,
+    Writer,
+};
+
+use super::{ClassHeader, ClassVisitor, FieldHeader, FieldVisitor, MethodHeader, MethodVisitor};
+
+/// A [`ClassVisitor`] that writes the class it visits. The constant pool passed to `visit` is
+/// copied, so an unmodified class is written back byte for byte, and entries are added for the
+/// names in the headers that are not in it.
+///
+/// The instructions of a method are encoded one after the other, ignoring their original pcs, so
+/// an adapter that changes the length of the code must also adjust the branch offsets and the
+/// attributes that refer to pcs.
+pub struct ClassWriter {
+    minor_version: u16,
+    major_version: u16,
+    constant_pool: crate::ConstantPool,
+    access_flags: ClassAccessFlags,
+    this_class: CpIndex<ClassInfo>,
+    super_class: CpIndex<ClassInfo>,
+    interfaces: Vec<CpIndex<ClassInfo>>,
+    fields: Encoded,
+    methods: Encoded,
+    attributes: Encoded,
+}
+impl ClassWriter {
+    pub fn new() -> Self {
+        Self {
+            minor_version: 0,
+            major_version: 0,
+            constant_pool: crate::ConstantPool::default(),
+            access_flags: ClassAccessFlags::empty(),
+            this_class: CpIndex::new(0),
+            super_class: CpIndex::new(0),
+            interfaces: vec![],
+            fields: Encoded::default(),
+            methods: Encoded::default(),
+            attributes: Encoded::default(),
+        }
+    }
+
+    /// The class file written so far, complete once the class has been visited.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut w = Writer::new(vec![]);
+        w.write_u32(0xCAFEBABE)?;
+        w.write_u16(self.minor_version)?;
+        w.write_u16(self.major_version)?;
+        w.write_constant_pool(&self.constant_pool)?;
+        w.write_u16(self.access_flags.bits())?;
+        w.write_u16(self.this_class.get())?;
+        w.write_u16(self.super_class.get())?;
+        w.write_length(self.interfaces.len(), "interfaces")?;
+        for interface in &self.interfaces {
+            w.write_u16(interface.get())?;
+        }
+        self.fields.write_to(&mut w, "fields")?;
+        self.methods.write_to(&mut w, "methods")?;
+        self.attributes.write_to(&mut w, "attributes")?;
+
+        Ok(w.into_inner())
+    }
+}
+impl Default for ClassWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ClassVisitor for ClassWriter {
+    fn visit(&mut self, header: &ClassHeader, constant_pool: &ConstantPool) -> Result<()> {
+        self.minor_version = header.version.minor;
+        self.major_version = header.version.major;
+        self.constant_pool = constant_pool.to_raw_constant_pool()?;
+        self.access_flags = header.access_flags;
+        self.this_class = self.constant_pool.find_or_insert_class(&header.name)?;
+        self.super_class = match &header.super_name {
+            Some(super_name) => self.constant_pool.find_or_insert_class(super_name)?,
+            None => CpIndex::new(0),
+        };
+        self.interfaces = header
+            .interfaces
+            .iter()
+            .map(|interface| self.constant_pool.find_or_insert_class(interface))
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    fn visit_field(&mut self, header: &FieldHeader) -> Result<Option<Box<dyn FieldVisitor + '_>>> {
+        let name_index = self.constant_pool.find_or_insert_utf8(&header.name)?;
+        let descriptor_index = self.constant_pool.find_or_insert_utf8(&header.descriptor)?;
+
+        Ok(Some(Box::new(FieldWriter {
+            class: self,
+            access_flags: header.access_flags,
+            name_index,
+            descriptor_index,
+            attributes: Encoded::default(),
+        })))
+    }
+
+    fn visit_method(
+        &mut self,
+        header: &MethodHeader,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        let name_index = self.constant_pool.find_or_insert_utf8(&header.name)?;
+        let descriptor_index = self.constant_pool.find_or_insert_utf8(&header.descriptor)?;
+
+        Ok(Some(Box::new(MethodWriter {
+            class: self,
+            access_flags: header.access_flags,
+            name_index,
+            descriptor_index,
+            attributes: Encoded::default(),
+            code: None,
+        })))
+    }
+
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        self.attributes.push_attribute(attribute)
+    }
+}
+
+/// Encoded structures of the same kind, which are preceded by their count in the class file.
+#[derive(Default)]
+struct Encoded {
+    count: usize,
+    bytes: Vec<u8>,
+}
+impl Encoded {
+    fn push_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        Writer::new(&mut self.bytes)
+            .write_raw_attribute(attribute.attribute_name_index, attribute.info)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn write_to(&self, w: &mut Writer<Vec<u8>>, what: &'static str) -> Result<()> {
+        w.write_length(self.count, what)?;
+        w.write_bytes(&self.bytes)
+    }
+}
+
+struct FieldWriter<'w> {
+    class: &'w mut ClassWriter,
+    access_flags: FieldAccessFlags,
+    name_index: CpIndex<str>,
+    descriptor_index: CpIndex<str>,
+    attributes: Encoded,
+}
+impl FieldVisitor for FieldWriter<'_> {
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        self.attributes.push_attribute(attribute)
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        let mut w = Writer::new(vec![]);
+        w.write_u16(self.access_flags.bits())?;
+        w.write_u16(self.name_index.get())?;
+        w.write_u16(self.descriptor_index.get())?;
+        self.attributes.write_to(&mut w, "attributes")?;
+
+        let fields = &mut self.class.fields;
+        fields.bytes.extend(w.into_inner());
+        fields.count += 1;
+        Ok(())
+    }
+}
+
+struct MethodWriter<'w> {
+    class: &'w mut ClassWriter,
+    access_flags: MethodAccessFlags,
+    name_index: CpIndex<str>,
+    descriptor_index: CpIndex<str>,
+    attributes: Encoded,
+    /// The code being visited, between `visit_code` and `visit_maxs`.
+    code: Option<CodeBuffer>,
+}
+
+#[derive(Default)]
+struct CodeBuffer {
+    code: Vec<u8>,
+    exception_table: Vec<ExceptionTableEntry>,
+    attributes: Encoded,
+}
+
+impl MethodVisitor for MethodWriter<'_> {
+    fn visit_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        self.attributes.push_attribute(attribute)
+    }
+
+    fn visit_code(&mut self) -> Result<bool> {
+        self.code = Some(CodeBuffer::default());
+        Ok(true)
+    }
+
+    fn visit_instruction(&mut self, _pc: u32, instruction: &Instruction) -> Result<()> {
+        instruction.encode(&mut self.code.get_or_insert_with(Default::default).code)
+    }
+
+    fn visit_exception_handler(&mut self, handler: &ExceptionTableEntry) -> Result<()> {
+        let code = self.code.get_or_insert_with(Default::default);
+        code.exception_table.push(handler.clone());
+        Ok(())
+    }
+
+    fn visit_code_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        let code = self.code.get_or_insert_with(Default::default);
+        code.attributes.push_attribute(attribute)
+    }
+
+    fn visit_maxs(&mut self, max_stack: u16, max_locals: u16) -> Result<()> {
+        let code = self.code.take().unwrap_or_default();
+        // The code_length must be less than 65536.
+        if code.code.len() > u16::MAX as usize {
+            return Err(ClassFileError::ValueTooLarge("code"));
+        }
+
+        let mut w = Writer::new(vec![]);
+        w.write_u16(max_stack)?;
+        w.write_u16(max_locals)?;
+        w.write_u32(code.code.len() as u32)?;
+        w.write_bytes(&code.code)?;
+        w.write_length(code.exception_table.len(), "exception_table")?;
+        for handler in &code.exception_table {
+            w.write_u16(handler.start_pc)?;
+            w.write_u16(handler.end_pc)?;
+            w.write_u16(handler.handler_pc)?;
+            w.write_u16(handler.catch_type)?;
+        }
+        code.attributes.write_to(&mut w, "attributes")?;
+
+        let attribute_name_index = self.class.constant_pool.find_or_insert_utf8("Code")?;
+        self.attributes.push_attribute(&Attribute {
+            attribute_name_index: attribute_name_index.get(),
+            info: &w.into_inner(),
+        })
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        let mut w = Writer::new(vec![]);
+        w.write_u16(self.access_flags.bits())?;
+        w.write_u16(self.name_index.get())?;
+        w.write_u16(self.descriptor_index.get())?;
+        self.attributes.write_to(&mut w, "attributes")?;
+
+        let methods = &mut self.class.methods;
+        methods.bytes.extend(w.into_inner());
+        methods.count += 1;
+        Ok(())
+    }
+}
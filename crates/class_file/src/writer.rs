@@ -0,0 +1,205 @@
+mod attributes;
+
+use std::io::Write;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    class_file::{FieldInfo, MethodInfo},
+    constant_pool::{CpInfo, RefInfo},
+    mutf8, ClassFile, ClassFileError, ConstantPool, Result,
+};
+
+type Endian = BigEndian;
+
+/// Serializes class file structures. An unmodified class file read by the [`crate::Parser`] is
+/// written back byte for byte.
+pub struct Writer<W> {
+    w: W,
+}
+impl<W: Write> Writer<W> {
+    pub fn new(w: W) -> Self {
+        Self { w }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    pub fn write_class_file(&mut self, class_file: &ClassFile) -> Result<()> {
+        self.write_u32(0xCAFEBABE)?;
+        self.write_u16(class_file.minor_version)?;
+        self.write_u16(class_file.major_version)?;
+        self.write_constant_pool(&class_file.constant_pool)?;
+        self.write_u16(class_file.access_flags.bits())?;
+        self.write_u16(class_file.this_class.get())?;
+        self.write_u16(class_file.super_class.get())?;
+        self.write_length(class_file.interfaces.len(), "interfaces")?;
+        for interface in &class_file.interfaces {
+            self.write_u16(interface.get())?;
+        }
+
+        self.write_length(class_file.fields.len(), "fields")?;
+        for field in &class_file.fields {
+            self.write_field_info(field)?;
+        }
+
+        self.write_length(class_file.methods.len(), "methods")?;
+        for method in &class_file.methods {
+            self.write_method_info(method)?;
+        }
+
+        self.write_attributes(&class_file.attributes)
+    }
+
+    pub fn write_constant_pool(&mut self, constant_pool: &ConstantPool) -> Result<()> {
+        // The constant_pool_count is one larger than the number of entries, and the unusable
+        // slots following Long and Double entries are part of the count.
+        self.write_length(constant_pool.into_iter().count() + 1, "constant_pool")?;
+        for cp_info in constant_pool {
+            self.write_cp_info(cp_info)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn write_cp_info(&mut self, cp_info: &CpInfo) -> Result<()> {
+        match cp_info {
+            CpInfo::Utf8(s) => self.write_utf8(&mutf8::encode(s))?,
+            CpInfo::RawUtf8(bytes) => self.write_utf8(bytes)?,
+            CpInfo::Integer(i) => {
+                self.write_u8(3)?;
+                self.w.write_i32::<Endian>(*i)?;
+            }
+            CpInfo::Float(f) => {
+                self.write_u8(4)?;
+                self.write_u32(f.to_bits())?;
+            }
+            CpInfo::Long(l) => {
+                self.write_u8(5)?;
+                self.w.write_i64::<Endian>(*l)?;
+            }
+            CpInfo::Double(d) => {
+                self.write_u8(6)?;
+                self.w.write_u64::<Endian>(d.to_bits())?;
+            }
+            CpInfo::Class(c) => {
+                self.write_u8(7)?;
+                self.write_u16(c.name_index.get())?;
+            }
+            CpInfo::String { string_index } => {
+                self.write_u8(8)?;
+                self.write_u16(string_index.get())?;
+            }
+            CpInfo::FieldRef(r) => {
+                self.write_u8(9)?;
+                self.write_ref_info(r)?;
+            }
+            CpInfo::MethodRef(r) => {
+                self.write_u8(10)?;
+                self.write_ref_info(r)?;
+            }
+            CpInfo::InterfaceMethodRef(r) => {
+                self.write_u8(11)?;
+                self.write_ref_info(r)?;
+            }
+            CpInfo::NameAndType(nat) => {
+                self.write_u8(12)?;
+                self.write_u16(nat.name_index.get())?;
+                self.write_u16(nat.descriptor_index.get())?;
+            }
+            CpInfo::MethodHandle(h) => {
+                self.write_u8(15)?;
+                self.write_u8(h.reference_kind as u8)?;
+                self.write_u16(h.reference_index.get())?;
+            }
+            CpInfo::MethodType(t) => {
+                self.write_u8(16)?;
+                self.write_u16(t.descriptor_index.get())?;
+            }
+            CpInfo::InvokeDynamic(i) => {
+                self.write_u8(18)?;
+                self.write_u16(i.bootstrap_method_attr_index)?;
+                self.write_u16(i.name_and_type_index.get())?;
+            }
+            CpInfo::Dynamic(d) => {
+                self.write_u8(17)?;
+                self.write_u16(d.bootstrap_method_attr_index)?;
+                self.write_u16(d.name_and_type_index.get())?;
+            }
+            CpInfo::Module(m) => {
+                self.write_u8(19)?;
+                self.write_u16(m.name_index.get())?;
+            }
+            CpInfo::Package(p) => {
+                self.write_u8(20)?;
+                self.write_u16(p.name_index.get())?;
+            }
+            // The second slot of a Long or Double is not represented in the class file.
+            CpInfo::Unusable => {}
+        }
+
+        Ok(())
+    }
+
+    fn write_utf8(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_u8(1)?;
+        self.write_length(bytes.len(), "CONSTANT_Utf8 bytes")?;
+        Ok(self.w.write_all(bytes)?)
+    }
+
+    fn write_ref_info(&mut self, ref_info: &RefInfo) -> Result<()> {
+        self.write_u16(ref_info.class_index.get())?;
+        self.write_u16(ref_info.name_and_type_index.get())
+    }
+
+    pub fn write_field_info(&mut self, field: &FieldInfo) -> Result<()> {
+        self.write_u16(field.access_flags.bits())?;
+        self.write_u16(field.name_index.get())?;
+        self.write_u16(field.descriptor_index.get())?;
+        self.write_attributes(&field.attributes)
+    }
+
+    pub fn write_method_info(&mut self, method: &MethodInfo) -> Result<()> {
+        self.write_u16(method.access_flags.bits())?;
+        self.write_u16(method.name_index.get())?;
+        self.write_u16(method.descriptor_index.get())?;
+        self.write_attributes(&method.attributes)
+    }
+
+    /// Writes a u16 count, failing if it does not fit.
+    pub(crate) fn write_length(&mut self, length: usize, what: &'static str) -> Result<()> {
+        let length = u16::try_from(length).map_err(|_| ClassFileError::ValueTooLarge(what))?;
+        self.write_u16(length)
+    }
+
+    pub(crate) fn write_u16_vec(&mut self, values: &[u16], what: &'static str) -> Result<()> {
+        self.write_length(values.len(), what)?;
+        for v in values {
+            self.write_u16(*v)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        Ok(self.w.write_all(bytes)?)
+    }
+
+    pub(crate) fn write_u32(&mut self, v: u32) -> Result<()> {
+        Ok(self.w.write_u32::<Endian>(v)?)
+    }
+
+    pub(crate) fn write_u16(&mut self, v: u16) -> Result<()> {
+        Ok(self.w.write_u16::<Endian>(v)?)
+    }
+
+    pub(crate) fn write_u8(&mut self, v: u8) -> Result<()> {
+        Ok(self.w.write_u8(v)?)
+    }
+}
+
+impl ClassFile {
+    pub fn write(&self, w: impl Write) -> Result<()> {
+        Writer::new(w).write_class_file(self)
+    }
+}
@@ -0,0 +1,411 @@
+use std::io::Write;
+
+use crate::{
+    attributes::{
+        Annotation, Attribute, AttributeInfo, Attributes, CodeAttribute, ElementValue,
+        ElementValuePair, ModuleAttribute, StackMapFrame, TargetInfo, TypeAnnotation,
+        VerificationTypeInfo,
+    },
+    ClassFileError, Result,
+};
+
+use super::Writer;
+
+impl<W: Write> Writer<W> {
+    pub fn write_attributes(&mut self, attributes: &Attributes) -> Result<()> {
+        self.write_length(attributes.0.len(), "attributes")?;
+        for attribute in attributes {
+            self.write_attribute(attribute)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_attribute(&mut self, attribute: &Attribute) -> Result<()> {
+        // The attribute_length precedes the body, so the body is written to a buffer first.
+        let mut body = Writer::new(vec![]);
+        body.write_attribute_info(&attribute.info)?;
+        self.write_raw_attribute(attribute.attribute_name_index, &body.into_inner())
+    }
+
+    /// Writes an attribute whose body is already encoded.
+    pub(crate) fn write_raw_attribute(
+        &mut self,
+        attribute_name_index: u16,
+        info: &[u8],
+    ) -> Result<()> {
+        self.write_u16(attribute_name_index)?;
+        self.write_u32(
+            u32::try_from(info.len())
+                .map_err(|_| ClassFileError::ValueTooLarge("attribute_length"))?,
+        )?;
+        Ok(self.w.write_all(info)?)
+    }
+
+    fn write_attribute_info(&mut self, info: &AttributeInfo) -> Result<()> {
+        match info {
+            AttributeInfo::ConstantValue {
+                constantvalue_index,
+            } => self.write_u16(*constantvalue_index),
+            AttributeInfo::Code(code) => self.write_code_attribute(code),
+            AttributeInfo::StackMapTable(frames) => {
+                self.write_length(frames.len(), "StackMapTable")?;
+                for frame in frames {
+                    self.write_stack_map_frame(frame)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Exceptions(exceptions) => self.write_u16_vec(exceptions, "Exceptions"),
+            AttributeInfo::InnerClasses(classes) => {
+                self.write_length(classes.len(), "InnerClasses")?;
+                for c in classes {
+                    self.write_u16(c.inner_class_info_index)?;
+                    self.write_u16(c.outer_class_info_index)?;
+                    self.write_u16(c.inner_name_index)?;
+                    self.write_u16(c.inner_class_access_flags.bits())?;
+                }
+                Ok(())
+            }
+            AttributeInfo::EnclosingMethod(e) => {
+                self.write_u16(e.class_index)?;
+                self.write_u16(e.method_index)
+            }
+            AttributeInfo::Synthetic | AttributeInfo::Deprecated => Ok(()),
+            AttributeInfo::Signature { signature_index } => self.write_u16(*signature_index),
+            AttributeInfo::SourceFile { sourcefile_index } => self.write_u16(*sourcefile_index),
+            AttributeInfo::SourceDebugExtension(bytes) | AttributeInfo::Unknown(bytes) => {
+                Ok(self.w.write_all(bytes)?)
+            }
+            AttributeInfo::LineNumberTable(lines) => {
+                self.write_length(lines.len(), "LineNumberTable")?;
+                for l in lines {
+                    self.write_u16(l.start_pc)?;
+                    self.write_u16(l.line_number)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::LocalVariableTable(locals) => {
+                self.write_length(locals.len(), "LocalVariableTable")?;
+                for l in locals {
+                    self.write_u16(l.start_pc)?;
+                    self.write_u16(l.length)?;
+                    self.write_u16(l.name_index)?;
+                    self.write_u16(l.descriptor_index)?;
+                    self.write_u16(l.index)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::LocalVariableTypeTable(locals) => {
+                self.write_length(locals.len(), "LocalVariableTypeTable")?;
+                for l in locals {
+                    self.write_u16(l.start_pc)?;
+                    self.write_u16(l.length)?;
+                    self.write_u16(l.name_index)?;
+                    self.write_u16(l.signature_index)?;
+                    self.write_u16(l.index)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::RuntimeVisibleAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleAnnotations(annotations) => {
+                self.write_annotations(annotations)
+            }
+            AttributeInfo::RuntimeVisibleParameterAnnotations(parameters)
+            | AttributeInfo::RuntimeInvisibleParameterAnnotations(parameters) => {
+                self.write_u8(
+                    u8::try_from(parameters.len())
+                        .map_err(|_| ClassFileError::ValueTooLarge("num_parameters"))?,
+                )?;
+                for annotations in parameters {
+                    self.write_annotations(annotations)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::RuntimeVisibleTypeAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleTypeAnnotations(annotations) => {
+                self.write_length(annotations.len(), "type annotations")?;
+                for a in annotations {
+                    self.write_type_annotation(a)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::AnnotationDefault(value) => self.write_element_value(value),
+            AttributeInfo::BootstrapMethods(methods) => {
+                self.write_length(methods.len(), "BootstrapMethods")?;
+                for m in methods {
+                    self.write_u16(m.bootstrap_method_ref)?;
+                    self.write_u16_vec(&m.bootstrap_arguments, "bootstrap_arguments")?;
+                }
+                Ok(())
+            }
+            AttributeInfo::MethodParameters(parameters) => {
+                self.write_u8(
+                    u8::try_from(parameters.len())
+                        .map_err(|_| ClassFileError::ValueTooLarge("MethodParameters"))?,
+                )?;
+                for p in parameters {
+                    self.write_u16(p.name_index)?;
+                    self.write_u16(p.access_flags.bits())?;
+                }
+                Ok(())
+            }
+            AttributeInfo::Module(module) => self.write_module_attribute(module),
+            AttributeInfo::ModulePackages(packages) => {
+                self.write_u16_vec(packages, "ModulePackages")
+            }
+            AttributeInfo::ModuleMainClass { main_class_index } => {
+                self.write_u16(*main_class_index)
+            }
+            AttributeInfo::NestHost { host_class_index } => self.write_u16(*host_class_index),
+            AttributeInfo::NestMembers(classes) => self.write_u16_vec(classes, "NestMembers"),
+            AttributeInfo::Record(components) => {
+                self.write_length(components.len(), "Record")?;
+                for c in components {
+                    self.write_u16(c.name_index)?;
+                    self.write_u16(c.descriptor_index)?;
+                    self.write_attributes(&c.attributes)?;
+                }
+                Ok(())
+            }
+            AttributeInfo::PermittedSubclasses(classes) => {
+                self.write_u16_vec(classes, "PermittedSubclasses")
+            }
+        }
+    }
+
+    fn write_code_attribute(&mut self, code: &CodeAttribute) -> Result<()> {
+        self.write_u16(code.max_stack)?;
+        self.write_u16(code.max_locals)?;
+        self.write_u32(
+            u32::try_from(code.code.len()).map_err(|_| ClassFileError::ValueTooLarge("code"))?,
+        )?;
+        self.w.write_all(&code.code)?;
+        self.write_length(code.exception_table.len(), "exception_table")?;
+        for e in &code.exception_table {
+            self.write_u16(e.start_pc)?;
+            self.write_u16(e.end_pc)?;
+            self.write_u16(e.handler_pc)?;
+            self.write_u16(e.catch_type)?;
+        }
+        self.write_attributes(&code.attributes)
+    }
+
+    fn write_stack_map_frame(&mut self, frame: &StackMapFrame) -> Result<()> {
+        match frame {
+            StackMapFrame::Same { offset_delta } => self.write_u8(*offset_delta as u8),
+            StackMapFrame::SameLocals1StackItem {
+                offset_delta,
+                stack,
+            } => {
+                self.write_u8(*offset_delta as u8 + 64)?;
+                self.write_verification_type_info(stack)
+            }
+            StackMapFrame::SameLocals1StackItemExtended {
+                offset_delta,
+                stack,
+            } => {
+                self.write_u8(247)?;
+                self.write_u16(*offset_delta)?;
+                self.write_verification_type_info(stack)
+            }
+            StackMapFrame::Chop { k, offset_delta } => {
+                self.write_u8(251 - k)?;
+                self.write_u16(*offset_delta)
+            }
+            StackMapFrame::SameExtended { offset_delta } => {
+                self.write_u8(251)?;
+                self.write_u16(*offset_delta)
+            }
+            StackMapFrame::Append {
+                offset_delta,
+                locals,
+            } => {
+                self.write_u8(251 + locals.len() as u8)?;
+                self.write_u16(*offset_delta)?;
+                for l in locals {
+                    self.write_verification_type_info(l)?;
+                }
+                Ok(())
+            }
+            StackMapFrame::Full {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                self.write_u8(255)?;
+                self.write_u16(*offset_delta)?;
+                self.write_length(locals.len(), "locals")?;
+                for l in locals {
+                    self.write_verification_type_info(l)?;
+                }
+                self.write_length(stack.len(), "stack")?;
+                for s in stack {
+                    self.write_verification_type_info(s)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_verification_type_info(&mut self, info: &VerificationTypeInfo) -> Result<()> {
+        match info {
+            VerificationTypeInfo::Top => self.write_u8(0),
+            VerificationTypeInfo::Integer => self.write_u8(1),
+            VerificationTypeInfo::Float => self.write_u8(2),
+            VerificationTypeInfo::Double => self.write_u8(3),
+            VerificationTypeInfo::Long => self.write_u8(4),
+            VerificationTypeInfo::Null => self.write_u8(5),
+            VerificationTypeInfo::UninitializedThis => self.write_u8(6),
+            VerificationTypeInfo::Object { cpool_index } => {
+                self.write_u8(7)?;
+                self.write_u16(*cpool_index)
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                self.write_u8(8)?;
+                self.write_u16(*offset)
+            }
+        }
+    }
+
+    fn write_annotations(&mut self, annotations: &[Annotation]) -> Result<()> {
+        self.write_length(annotations.len(), "annotations")?;
+        for a in annotations {
+            self.write_annotation(a)?;
+        }
+        Ok(())
+    }
+
+    fn write_annotation(&mut self, annotation: &Annotation) -> Result<()> {
+        self.write_u16(annotation.type_index)?;
+        self.write_element_value_pairs(&annotation.element_value_pairs)
+    }
+
+    fn write_element_value_pairs(&mut self, pairs: &[ElementValuePair]) -> Result<()> {
+        self.write_length(pairs.len(), "element_value_pairs")?;
+        for p in pairs {
+            self.write_u16(p.element_name_index)?;
+            self.write_element_value(&p.value)?;
+        }
+        Ok(())
+    }
+
+    fn write_element_value(&mut self, value: &ElementValue) -> Result<()> {
+        self.write_u8(value.tag())?;
+        match value {
+            ElementValue::Byte { const_value_index }
+            | ElementValue::Char { const_value_index }
+            | ElementValue::Double { const_value_index }
+            | ElementValue::Float { const_value_index }
+            | ElementValue::Int { const_value_index }
+            | ElementValue::Long { const_value_index }
+            | ElementValue::Short { const_value_index }
+            | ElementValue::Boolean { const_value_index }
+            | ElementValue::String { const_value_index } => self.write_u16(*const_value_index),
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => {
+                self.write_u16(*type_name_index)?;
+                self.write_u16(*const_name_index)
+            }
+            ElementValue::Class { class_info_index } => self.write_u16(*class_info_index),
+            ElementValue::Annotation(a) => self.write_annotation(a),
+            ElementValue::Array(values) => {
+                self.write_length(values.len(), "element values")?;
+                for v in values {
+                    self.write_element_value(v)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_type_annotation(&mut self, annotation: &TypeAnnotation) -> Result<()> {
+        self.write_u8(annotation.target_type)?;
+        match &annotation.target_info {
+            TargetInfo::TypeParameter {
+                type_parameter_index,
+            } => self.write_u8(*type_parameter_index)?,
+            TargetInfo::Supertype { supertype_index } => self.write_u16(*supertype_index)?,
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                self.write_u8(*type_parameter_index)?;
+                self.write_u8(*bound_index)?;
+            }
+            TargetInfo::Empty => {}
+            TargetInfo::FormalParameter {
+                formal_parameter_index,
+            } => self.write_u8(*formal_parameter_index)?,
+            TargetInfo::Throws { throws_type_index } => self.write_u16(*throws_type_index)?,
+            TargetInfo::Localvar(entries) => {
+                self.write_length(entries.len(), "localvar_target")?;
+                for e in entries {
+                    self.write_u16(e.start_pc)?;
+                    self.write_u16(e.length)?;
+                    self.write_u16(e.index)?;
+                }
+            }
+            TargetInfo::Catch {
+                exception_table_index,
+            } => self.write_u16(*exception_table_index)?,
+            TargetInfo::Offset { offset } => self.write_u16(*offset)?,
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index,
+            } => {
+                self.write_u16(*offset)?;
+                self.write_u8(*type_argument_index)?;
+            }
+        }
+
+        self.write_u8(
+            u8::try_from(annotation.target_path.len())
+                .map_err(|_| ClassFileError::ValueTooLarge("type_path"))?,
+        )?;
+        for p in &annotation.target_path {
+            self.write_u8(p.type_path_kind)?;
+            self.write_u8(p.type_argument_index)?;
+        }
+        self.write_u16(annotation.type_index)?;
+        self.write_element_value_pairs(&annotation.element_value_pairs)
+    }
+
+    fn write_module_attribute(&mut self, module: &ModuleAttribute) -> Result<()> {
+        self.write_u16(module.module_name_index)?;
+        self.write_u16(module.module_flags.bits())?;
+        self.write_u16(module.module_version_index)?;
+
+        self.write_length(module.requires.len(), "requires")?;
+        for r in &module.requires {
+            self.write_u16(r.requires_index)?;
+            self.write_u16(r.requires_flags.bits())?;
+            self.write_u16(r.requires_version_index)?;
+        }
+
+        self.write_length(module.exports.len(), "exports")?;
+        for e in &module.exports {
+            self.write_u16(e.exports_index)?;
+            self.write_u16(e.exports_flags.bits())?;
+            self.write_u16_vec(&e.exports_to_index, "exports_to")?;
+        }
+
+        self.write_length(module.opens.len(), "opens")?;
+        for o in &module.opens {
+            self.write_u16(o.opens_index)?;
+            self.write_u16(o.opens_flags.bits())?;
+            self.write_u16_vec(&o.opens_to_index, "opens_to")?;
+        }
+
+        self.write_u16_vec(&module.uses_index, "uses")?;
+
+        self.write_length(module.provides.len(), "provides")?;
+        for p in &module.provides {
+            self.write_u16(p.provides_index)?;
+            self.write_u16_vec(&p.provides_with_index, "provides_with")?;
+        }
+
+        Ok(())
+    }
+}
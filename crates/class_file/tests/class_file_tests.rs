@@ -1,10 +1,32 @@
-use std::fs::File;
+use std::{collections::BTreeSet, fs::File};
 
-use just_class_file::{AccessFlags, ClassFile, Parser};
+use just_class_file::{
+    annotation::AnnotationValue,
+    attributes::{
+        Attribute, AttributeInfo, Attributes, CodeAttribute, StackMapFrame, TargetInfo,
+        TypePathKind, VerificationTypeInfo,
+    },
+    bootstrap::{LoadableConstant, MethodHandle},
+    borrowed,
+    builder::ClassBuilder,
+    cfg::{ControlFlowGraph, ExceptionEdge, Loop},
+    constant_pool::{CpIndex, CpInfo, MethodHandleInfo, ReferenceKind},
+    dataflow::{Analysis, Definition, Direction, FrameState, Liveness, ReachingDefinitions},
+    descriptor::BaseType,
+    format_check::{Diagnostic, Location},
+    verifier::ResolvedClass,
+    visitor::{ClassReader, ClassVisitor, ClassWriter, MethodHeader, MethodVisitor, ReaderFlags},
+    ClassAccessFlags, ClassFile, ClassFileError, ClassFileVersion, FieldAccessFlags, FieldType,
+    Instruction, MethodAccessFlags, MethodDescriptor, MethodInfo, Parser,
+};
 
 fn with_class_file(f: impl FnOnce(ClassFile)) {
+    with_named_class_file("MyClass", f)
+}
+
+fn with_named_class_file(name: &str, f: impl FnOnce(ClassFile)) {
     f(
-        Parser::new(File::open("tests/classes/my/MyClass.class").unwrap())
+        Parser::new(File::open(format!("tests/classes/my/{}.class", name)).unwrap())
             .parse()
             .unwrap(),
     );
@@ -22,6 +44,73 @@ fn test_class_name() {
     with_class_file(|class_file| assert_eq!("my/MyClass", class_file.class_name().unwrap()));
 }
 
+#[test]
+fn test_version() {
+    // The test classes are compiled by whichever javac is installed, so the version is read from
+    // the bytes following the magic identifier.
+    let bytes = std::fs::read("tests/classes/my/MyClass.class").unwrap();
+    let minor = u16::from_be_bytes([bytes[4], bytes[5]]);
+    let major = u16::from_be_bytes([bytes[6], bytes[7]]);
+
+    with_class_file(|class_file| {
+        let version = class_file.version();
+
+        assert_eq!(ClassFileVersion::new(major, minor), version);
+        assert_eq!(Some(major - 44), version.java_release());
+        assert!(version >= ClassFileVersion::for_java_release(8));
+        assert!(!version.is_preview());
+    });
+}
+
+#[test]
+fn test_unsupported_version() {
+    let mut bytes = std::fs::read("tests/classes/my/MyClass.class").unwrap();
+    bytes[6..8].copy_from_slice(&66u16.to_be_bytes());
+
+    assert!(matches!(
+        ClassFile::parse(std::io::Cursor::new(&bytes)),
+        Err(ClassFileError::UnsupportedVersion(ClassFileVersion {
+            major: 66,
+            minor: 0
+        }))
+    ));
+    assert!(Parser::new(std::io::Cursor::new(&bytes))
+        .max_version(None)
+        .parse()
+        .is_ok());
+    assert!(Parser::new(std::io::Cursor::new(&bytes))
+        .max_version(Some(ClassFileVersion::for_java_release(11)))
+        .parse()
+        .is_err());
+
+    bytes[6..8].copy_from_slice(&44u16.to_be_bytes());
+    assert!(matches!(
+        ClassFile::parse(std::io::Cursor::new(&bytes)),
+        Err(ClassFileError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn test_invalid_constant_pool_count() {
+    let header = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x34];
+    // No entries, and a Long in the only slot.
+    for constant_pool in [
+        vec![0x00, 0x00],
+        vec![0x00, 0x02, 0x05, 0, 0, 0, 0, 0, 0, 0, 1],
+    ] {
+        let bytes = [&header[..], &constant_pool].concat();
+
+        assert!(matches!(
+            ClassFile::parse(std::io::Cursor::new(&bytes)),
+            Err(ClassFileError::InvalidConstantPoolCount(_))
+        ));
+        assert!(matches!(
+            borrowed::ClassFile::parse(&bytes),
+            Err(ClassFileError::InvalidConstantPoolCount(_))
+        ));
+    }
+}
+
 #[test]
 fn test_field_name() {
     with_class_file(|class_file| {
@@ -46,12 +135,44 @@ fn test_int_field_type() {
 fn test_field_access_flags() {
     with_class_file(|class_file| {
         assert_eq!(
-            AccessFlags::FINAL | AccessFlags::PRIVATE,
+            FieldAccessFlags::FINAL | FieldAccessFlags::PRIVATE,
             class_file.fields[0].access_flags
         )
     });
 }
 
+#[test]
+fn test_class_access_flags() {
+    with_named_class_file("MyAttributes", |class_file| {
+        assert_eq!(
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            class_file.access_flags
+        );
+        assert!(class_file.access_flags.check().is_ok());
+    });
+}
+
+#[test]
+fn test_unknown_access_flags_are_kept() {
+    let mut bytes = std::fs::read("tests/classes/my/MyClass.class").unwrap();
+    let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+
+    // The access_flags follow the constant pool, so they are found by writing it out.
+    let mut constant_pool = vec![];
+    just_class_file::Writer::new(&mut constant_pool)
+        .write_constant_pool(&class_file.constant_pool)
+        .unwrap();
+    let position = 8 + constant_pool.len();
+    bytes[position..position + 2].copy_from_slice(&0x0120u16.to_be_bytes());
+
+    let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+    assert_eq!(0x0120, class_file.access_flags.bits());
+
+    let mut written = vec![];
+    class_file.write(&mut written).unwrap();
+    assert_eq!(bytes, written);
+}
+
 #[test]
 fn test_constructor_name() {
     with_class_file(|class_file| {
@@ -96,9 +217,1664 @@ fn test_method_descriptor() {
     });
 }
 
+#[test]
+fn test_method_type() {
+    with_class_file(|class_file| {
+        assert_eq!(
+            MethodDescriptor {
+                params: vec![FieldType::Base(BaseType::Int)],
+                return_type: Some(FieldType::Base(BaseType::Float)),
+            },
+            class_file.method_type(&class_file.methods[1]).unwrap()
+        )
+    });
+}
+
+#[test]
+fn test_field_type() {
+    with_class_file(|class_file| {
+        assert_eq!(
+            FieldType::Base(BaseType::Int),
+            class_file.field_type(&class_file.fields[0]).unwrap()
+        )
+    });
+}
+
 #[test]
 fn test_method_access_flags() {
     with_class_file(|class_file| {
-        assert_eq!(AccessFlags::PUBLIC, class_file.methods[1].access_flags)
+        assert_eq!(
+            MethodAccessFlags::PUBLIC,
+            class_file.methods[1].access_flags
+        )
+    });
+}
+
+#[test]
+fn test_code_attribute() {
+    with_class_file(|class_file| {
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+
+        assert_eq!(2, code.max_stack);
+        assert_eq!(2, code.max_locals);
+        assert_eq!(11, code.code.len());
+        assert!(code.exception_table.is_empty());
+        assert!(matches!(
+            code.attributes.0[0].info,
+            AttributeInfo::LineNumberTable(_)
+        ));
+    });
+}
+
+#[test]
+fn test_constant_value_attribute() {
+    with_class_file(|class_file| {
+        let AttributeInfo::ConstantValue {
+            constantvalue_index,
+        } = class_file.fields[0].attributes.0[0].info
+        else {
+            panic!("Expected a ConstantValue attribute");
+        };
+
+        assert_eq!(
+            CpInfo::Integer(11),
+            class_file.constant_pool[constantvalue_index]
+        );
+    });
+}
+
+#[test]
+fn test_source_file_attribute() {
+    with_class_file(|class_file| {
+        let AttributeInfo::SourceFile { sourcefile_index } = class_file.attributes.0[0].info else {
+            panic!("Expected a SourceFile attribute");
+        };
+
+        assert_eq!(
+            CpInfo::Utf8("MyClass.java".into()),
+            class_file.constant_pool[sourcefile_index]
+        );
+    });
+}
+
+#[test]
+fn test_stack_map_table_attribute() {
+    with_named_class_file("MyAttributes", |class_file| {
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+        let frames = code.attributes.stack_map_table().unwrap();
+
+        assert_eq!(2, frames.len());
+        assert!(matches!(frames[0], StackMapFrame::Full { .. }));
+        assert!(matches!(frames[1], StackMapFrame::Chop { k: 3, .. }));
+    });
+}
+
+#[test]
+fn test_class_attributes() {
+    with_named_class_file("MyAttributes", |class_file| {
+        let find = |name| {
+            class_file
+                .attributes
+                .find_by_name(name, &class_file.constant_pool)
+                .map(|a| &a.info)
+        };
+
+        assert!(matches!(
+            find("Signature"),
+            Some(AttributeInfo::Signature { .. })
+        ));
+        assert!(matches!(
+            find("RuntimeVisibleAnnotations"),
+            Some(AttributeInfo::RuntimeVisibleAnnotations(a)) if a.len() == 1
+        ));
+        assert!(matches!(
+            find("NestMembers"),
+            Some(AttributeInfo::NestMembers(m)) if m.len() == 1
+        ));
+        assert!(matches!(
+            find("InnerClasses"),
+            Some(AttributeInfo::InnerClasses(c)) if c.len() == 2
+        ));
+        assert_eq!(1, class_file.attributes.bootstrap_methods().unwrap().len());
     });
 }
+
+#[test]
+fn test_signatures() {
+    with_named_class_file("MyAttributes", |class_file| {
+        assert_eq!(
+            "<T extends java.lang.Comparable<T>> extends java.lang.Object",
+            class_file.class_signature().unwrap().unwrap().to_string()
+        );
+        assert_eq!(
+            "List<? extends T>",
+            format!(
+                "{:#}",
+                class_file
+                    .field_signature(&class_file.fields[1])
+                    .unwrap()
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            "java.util.function.Supplier<java.lang.String>()",
+            class_file
+                .method_signature(&class_file.methods[2])
+                .unwrap()
+                .unwrap()
+                .to_string()
+        );
+        assert!(class_file
+            .method_signature(&class_file.methods[1])
+            .unwrap()
+            .is_none());
+    });
+}
+
+#[test]
+fn test_annotations() {
+    with_named_class_file("MyAnnotations", |class_file| {
+        assert!(class_file.has_annotation("Ljava/lang/Deprecated;"));
+        assert!(!class_file.has_annotation("Lmy/MyAnnotations$Internal;"));
+
+        let info = class_file
+            .find_annotation(
+                class_file.attributes.annotations(),
+                "Lmy/MyAnnotations$Info;",
+            )
+            .unwrap();
+        assert_eq!(
+            Some(AnnotationValue::String("class")),
+            class_file.annotation_element(info, "name").unwrap()
+        );
+        assert_eq!(
+            Some(AnnotationValue::Array(vec![
+                AnnotationValue::String("a"),
+                AnnotationValue::String("b")
+            ])),
+            class_file.annotation_element(info, "tags").unwrap()
+        );
+        assert_eq!(
+            Some(AnnotationValue::Enum {
+                type_name: "Lmy/MyAnnotations$Level;",
+                const_name: "HIGH"
+            }),
+            class_file.annotation_element(info, "level").unwrap()
+        );
+        assert_eq!(
+            Some(AnnotationValue::Class("Ljava/lang/String;")),
+            class_file.annotation_element(info, "type").unwrap()
+        );
+        assert_eq!(
+            None,
+            class_file.annotation_element(info, "priority").unwrap()
+        );
+
+        // @Internal has class retention, so it is stored as an invisible annotation.
+        let field = &class_file.fields[0];
+        assert!(class_file.field_has_annotation(field, "Lmy/MyAnnotations$Internal;"));
+
+        let type_annotation = field.attributes.type_annotations().next().unwrap();
+        assert_eq!(0x13, type_annotation.target_type);
+        assert_eq!(TargetInfo::Empty, type_annotation.target_info);
+        assert_eq!(
+            vec![Some(TypePathKind::TypeArgument)],
+            type_annotation
+                .target_path
+                .iter()
+                .map(|entry| entry.kind())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, type_annotation.target_path[0].type_argument_index);
+
+        let method = &class_file.methods[1];
+        assert!(class_file.method_has_annotation(method, "Lmy/MyAnnotations$Info;"));
+        assert!(
+            !class_file.method_has_annotation(&class_file.methods[0], "Lmy/MyAnnotations$Info;")
+        );
+        let info = class_file
+            .find_annotation(method.attributes.annotations(), "Lmy/MyAnnotations$Info;")
+            .unwrap();
+        assert_eq!(
+            Some(AnnotationValue::Int(2)),
+            class_file.annotation_element(info, "priority").unwrap()
+        );
+
+        // @NonNull only targets type uses, so the second parameter has a type annotation instead.
+        assert!(class_file
+            .find_annotation(
+                method.attributes.parameter_annotations(0),
+                "Lmy/MyAnnotations$Internal;"
+            )
+            .is_some());
+        assert_eq!(0, method.attributes.parameter_annotations(1).count());
+        assert_eq!(
+            TargetInfo::FormalParameter {
+                formal_parameter_index: 1
+            },
+            method
+                .attributes
+                .type_annotations()
+                .next()
+                .unwrap()
+                .target_info
+        );
+    });
+}
+
+#[test]
+fn test_annotation_default() {
+    with_named_class_file("MyAnnotations$Info", |class_file| {
+        let default = |name: &str| {
+            let method = class_file
+                .methods
+                .iter()
+                .find(|method| class_file.method_name(method).unwrap() == name)
+                .unwrap();
+            method
+                .attributes
+                .annotation_default()
+                .map(|value| class_file.element_value(value).unwrap())
+        };
+
+        assert_eq!(None, default("name"));
+        assert_eq!(Some(AnnotationValue::Array(vec![])), default("tags"));
+        assert_eq!(
+            Some(AnnotationValue::Enum {
+                type_name: "Lmy/MyAnnotations$Level;",
+                const_name: "LOW"
+            }),
+            default("level")
+        );
+        assert_eq!(
+            Some(AnnotationValue::Class("Ljava/lang/Object;")),
+            default("type")
+        );
+        assert_eq!(Some(AnnotationValue::Int(1)), default("priority"));
+    });
+}
+
+#[test]
+fn test_bootstrap_method() {
+    with_named_class_file("MyAttributes", |class_file| {
+        let index = class_file
+            .methods
+            .iter()
+            .filter_map(|method| method.attributes.code_attribute())
+            .flat_map(|code| code.instructions().map(Result::unwrap))
+            .find_map(|(_, instruction)| match instruction {
+                Instruction::Invokedynamic(index) => Some(index),
+                _ => None,
+            })
+            .unwrap();
+
+        let call_site = class_file.call_site(CpIndex::new(index)).unwrap();
+        assert_eq!("get", call_site.name);
+        assert_eq!("()Ljava/util/function/Supplier;", call_site.descriptor);
+
+        let bootstrap_method = class_file
+            .bootstrap_method(call_site.bootstrap_method_attr_index)
+            .unwrap();
+        assert_eq!(
+            MethodHandle {
+                reference_kind: ReferenceKind::InvokeStatic,
+                owner: "java/lang/invoke/LambdaMetafactory",
+                name: "metafactory",
+                descriptor: "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/CallSite;",
+                is_interface: false,
+            },
+            bootstrap_method.method_handle
+        );
+        assert_eq!(
+            vec![
+                LoadableConstant::MethodType("()Ljava/lang/Object;"),
+                LoadableConstant::MethodHandle(MethodHandle {
+                    reference_kind: ReferenceKind::InvokeStatic,
+                    owner: "my/MyAttributes",
+                    name: "lambda$supplier$0",
+                    descriptor: "()Ljava/lang/String;",
+                    is_interface: false,
+                }),
+                LoadableConstant::MethodType("()Ljava/lang/String;"),
+            ],
+            bootstrap_method.arguments
+        );
+
+        assert!(matches!(
+            class_file.bootstrap_method(1),
+            Err(ClassFileError::InvalidBootstrapMethodIndex(1))
+        ));
+    });
+}
+
+#[test]
+fn test_debug_info() {
+    with_named_class_file("MyAttributes", |class_file| {
+        assert_eq!(Some("MyAttributes.java"), class_file.source_file().unwrap());
+
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+        assert_eq!(Some(14), code.line_for_pc(0));
+        assert_eq!(Some(15), code.line_for_pc(2));
+        assert_eq!(Some(16), code.line_for_pc(28));
+        assert_eq!(Some(15), code.line_for_pc(29));
+        assert_eq!(None, code.line_for_pc(code.code.len() as u32));
+        // The enhanced for loop starts both before its body and where it moves to the next value.
+        assert_eq!(vec![2, 29], code.pcs_for_line(15));
+        assert!(code.pcs_for_line(1).is_empty());
+
+        let names = |pc| {
+            class_file
+                .locals_live_at(code, pc)
+                .unwrap()
+                .iter()
+                .map(|local| (local.index, local.name, local.descriptor))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![(0, "this", "Lmy/MyAttributes;"), (1, "values", "[I")],
+            names(0)
+        );
+        assert_eq!(
+            vec![
+                (0, "this", "Lmy/MyAttributes;"),
+                (1, "values", "[I"),
+                (2, "sum", "I"),
+                (6, "value", "I")
+            ],
+            names(24)
+        );
+        assert_eq!(3, names(29).len());
+
+        let this = &class_file.locals_live_at(code, 0).unwrap()[0];
+        assert_eq!(Some("Lmy/MyAttributes<TT;>;"), this.signature);
+        assert_eq!((0, 37), (this.start_pc, this.length));
+    });
+}
+
+#[test]
+fn test_control_flow_graph() {
+    with_named_class_file("MyControlFlow", |class_file| {
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+        let cfg = ControlFlowGraph::new(code).unwrap();
+
+        assert_eq!(
+            vec![(0, 11), (11, 18), (18, 29), (29, 34), (34, 40), (40, 42)],
+            cfg.blocks
+                .iter()
+                .map(|block| (block.start_pc, block.end_pc))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![vec![1], vec![5, 2], vec![4, 3], vec![4], vec![1], vec![]],
+            cfg.blocks
+                .iter()
+                .map(|block| block.successors.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![0, 4], cfg.blocks[1].predecessors);
+        assert!(cfg
+            .blocks
+            .iter()
+            .all(|block| block.exception_handlers.is_empty()));
+        assert_eq!(Some(2), cfg.block_at(26));
+        assert_eq!(None, cfg.block_at(42));
+
+        let dominators = cfg.dominators();
+        assert_eq!(
+            vec![None, Some(0), Some(1), Some(2), Some(2), Some(1)],
+            (0..cfg.blocks.len())
+                .map(|block| dominators.immediate_dominator(block))
+                .collect::<Vec<_>>()
+        );
+        assert!(dominators.dominates(1, 4));
+        assert!(!dominators.dominates(3, 4));
+        assert_eq!(vec![2, 5], dominators.children(1));
+
+        assert_eq!(
+            vec![Loop {
+                header: 1,
+                latches: vec![4],
+                blocks: vec![1, 2, 3, 4],
+            }],
+            cfg.loops()
+        );
+
+        let dot = cfg.dot().to_string();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("  b1 [label=\"11: iload\\l13: iload\\l15: if_icmpge\\l\"];\n"));
+        assert!(dot.contains("  b4 -> b1;\n"));
+    });
+}
+
+#[test]
+fn test_control_flow_graph_exceptions() {
+    with_named_class_file("MyControlFlow", |class_file| {
+        let describe = class_file.methods[2].attributes.code_attribute().unwrap();
+        let cfg = ControlFlowGraph::new(describe).unwrap();
+        assert_eq!(vec![3, 1, 2], cfg.blocks[0].successors);
+
+        let parse = class_file.methods[3].attributes.code_attribute().unwrap();
+        let cfg = ControlFlowGraph::new(parse).unwrap();
+
+        // The try block, the finally block after it, the catch block split where the finally
+        // handler's range ends, and the finally handler split the same way.
+        assert_eq!(
+            vec![0, 5, 17, 20, 32, 34],
+            cfg.blocks
+                .iter()
+                .map(|block| block.start_pc)
+                .collect::<Vec<_>>()
+        );
+        let catch_type = parse.exception_table[0].catch_type;
+        assert_eq!(
+            vec![
+                ExceptionEdge {
+                    handler: 2,
+                    catch_type
+                },
+                ExceptionEdge {
+                    handler: 4,
+                    catch_type: 0
+                }
+            ],
+            cfg.blocks[0].exception_handlers
+        );
+        assert_eq!(vec![1], cfg.blocks[0].successors);
+        assert!(cfg.blocks[1].successors.is_empty());
+        assert_eq!(vec![0, 2, 4], cfg.blocks[4].exception_predecessors);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], {
+            let mut blocks = cfg.reverse_postorder();
+            blocks.sort();
+            blocks
+        });
+
+        let dominators = cfg.dominators();
+        assert_eq!(Some(0), dominators.immediate_dominator(4));
+        assert_eq!(Some(4), dominators.immediate_dominator(5));
+        assert!(cfg.loops().is_empty());
+        assert!(cfg
+            .dot()
+            .to_string()
+            .contains("  b2 -> b4 [style=dashed, label=\"any\"];\n"));
+    });
+}
+
+#[test]
+fn test_liveness() {
+    with_named_class_file("MyControlFlow", |class_file| {
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+        let liveness = ControlFlowGraph::new(code).unwrap().analyze(&Liveness);
+        let live = |pc| {
+            liveness
+                .before(pc)
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec![1], live(0));
+        assert_eq!(vec![2, 3, 4, 5], live(11));
+        assert_eq!(vec![2, 3, 4, 5, 6], live(24));
+        assert_eq!(vec![2], live(40));
+        assert_eq!(&BTreeSet::from([2, 3, 4, 5]), liveness.after(33).unwrap());
+
+        // `s` is dead once parsed, but `this` stays live in the handlers of the try block.
+        let code = class_file.methods[3].attributes.code_attribute().unwrap();
+        let liveness = ControlFlowGraph::new(code).unwrap().analyze(&Liveness);
+        let live = |pc| {
+            liveness
+                .before(pc)
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![0, 1], live(0));
+        assert_eq!(vec![0], live(4));
+        assert_eq!(vec![0, 2], live(5));
+        assert_eq!(vec![0], live(17));
+        assert_eq!(vec![0], live(32));
+    });
+}
+
+#[test]
+fn test_reaching_definitions() {
+    with_named_class_file("MyControlFlow", |class_file| {
+        let analysis = |method: &MethodInfo| {
+            let is_static = method.access_flags.contains(MethodAccessFlags::STATIC);
+            ReachingDefinitions::new(is_static, &class_file.method_type(method).unwrap())
+        };
+        let method = &class_file.methods[1];
+        let code = method.attributes.code_attribute().unwrap();
+        let definitions = ControlFlowGraph::new(code)
+            .unwrap()
+            .analyze(&analysis(method));
+        let reaching = |pc, index| {
+            definitions
+                .before(pc)
+                .unwrap()
+                .iter()
+                .filter(|definition| definition.index == index)
+                .map(|definition| definition.pc)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec![None], reaching(0, 1));
+        assert!(reaching(0, 2).is_empty());
+        assert_eq!(vec![Some(1), Some(33)], reaching(40, 2));
+        assert_eq!(vec![Some(9), Some(34)], reaching(11, 5));
+        assert_eq!(vec![Some(22)], reaching(11, 6));
+        assert_eq!(vec![Some(34)], reaching(37, 5));
+
+        // The store that ends the try block cannot be followed by an exception in it, unlike the
+        // store at the start of the catch block, which the finally handler covers.
+        let method = &class_file.methods[3];
+        let code = method.attributes.code_attribute().unwrap();
+        let definitions = ControlFlowGraph::new(code)
+            .unwrap()
+            .analyze(&analysis(method));
+        let reaching = |pc, index| {
+            definitions
+                .before(pc)
+                .unwrap()
+                .iter()
+                .filter(|definition| definition.index == index)
+                .map(|definition| definition.pc)
+                .collect::<Vec<_>>()
+        };
+        assert!(reaching(17, 2).is_empty());
+        assert_eq!(vec![Some(17)], reaching(32, 2));
+    });
+}
+
+#[test]
+fn test_reaching_definitions_of_longs() {
+    // Stores an int into the upper half of the long in local 0.
+    let mut class = ClassBuilder::new("my/Longs", ClassAccessFlags::PUBLIC).unwrap();
+    for descriptor in ["()V", "(J)V"] {
+        class
+            .method(MethodAccessFlags::STATIC, "longs", descriptor, |code| {
+                if descriptor == "()V" {
+                    code.instruction(Instruction::Lconst0)
+                        .instruction(Instruction::Lstore0);
+                }
+                code.iconst(1)?.istore(1).instruction(Instruction::Return);
+                Ok(())
+            })
+            .unwrap();
+    }
+    let class_file = class.build();
+
+    let reaching_return = |method: &MethodInfo| {
+        let code = method.attributes.code_attribute().unwrap();
+        let descriptor = class_file.method_type(method).unwrap();
+        let results = ControlFlowGraph::new(code)
+            .unwrap()
+            .analyze(&ReachingDefinitions::new(true, &descriptor));
+        let (pc, _) = code.instructions().map(Result::unwrap).last().unwrap();
+        results.before(pc).unwrap().clone()
+    };
+
+    // lconst_0, lstore_0, iconst_1, istore_1
+    assert_eq!(
+        BTreeSet::from([Definition {
+            index: 1,
+            pc: Some(3),
+            wide: false,
+        }]),
+        reaching_return(&class_file.methods[0])
+    );
+    // iconst_1, istore_1
+    assert_eq!(
+        BTreeSet::from([Definition {
+            index: 1,
+            pc: Some(1),
+            wide: false,
+        }]),
+        reaching_return(&class_file.methods[1])
+    );
+}
+
+#[test]
+fn test_dataflow_frame_state() {
+    // Propagates integer constants, `None` being any value.
+    struct Constants;
+    impl Analysis for Constants {
+        type State = FrameState<Option<i32>>;
+
+        const DIRECTION: Direction = Direction::Forward;
+
+        fn boundary_state(&self) -> Self::State {
+            FrameState {
+                locals: vec![None, None],
+                stack: vec![],
+            }
+        }
+
+        fn join(&self, state: &mut Self::State, other: &Self::State) {
+            state.join_with(other, |value, other| {
+                if value != other {
+                    *value = None;
+                }
+            });
+        }
+
+        fn transfer(&self, _pc: u32, instruction: &Instruction, state: &mut Self::State) {
+            match instruction {
+                Instruction::Iconst1 => state.stack.push(Some(1)),
+                Instruction::Iconst2 => state.stack.push(Some(2)),
+                Instruction::Iload0 => state.stack.push(state.locals[0]),
+                Instruction::Iload1 => state.stack.push(state.locals[1]),
+                Instruction::Istore1 => state.locals[1] = state.stack.pop().unwrap(),
+                Instruction::Iadd => {
+                    let (b, a) = (state.stack.pop().unwrap(), state.stack.pop().unwrap());
+                    state.stack.push(a.zip(b).map(|(a, b)| a + b));
+                }
+                Instruction::Ifeq(_) | Instruction::Ireturn => {
+                    state.stack.pop();
+                }
+                Instruction::Goto(_) => {}
+                _ => unreachable!("{:?}", instruction),
+            }
+        }
+    }
+
+    // `x = b ? 1 : other; return x + 2;`
+    let mut class = ClassBuilder::new("my/Constants", ClassAccessFlags::PUBLIC).unwrap();
+    for (name, other) in [("same", 1), ("different", 2)] {
+        class
+            .method(MethodAccessFlags::STATIC, name, "(Z)I", |code| {
+                let (otherwise, join) = (code.new_label(), code.new_label());
+                code.iload(0)
+                    .ifeq(otherwise)
+                    .iconst(1)?
+                    .istore(1)
+                    .goto(join)
+                    .place_label(otherwise)
+                    .iconst(other)?
+                    .istore(1)
+                    .place_label(join)
+                    .iload(1)
+                    .iconst(2)?
+                    .instruction(Instruction::Iadd)
+                    .instruction(Instruction::Ireturn);
+                Ok(())
+            })
+            .unwrap();
+    }
+    let class_file = class.build();
+
+    let returned = |method: &MethodInfo| {
+        let code = method.attributes.code_attribute().unwrap();
+        let results = ControlFlowGraph::new(code).unwrap().analyze(&Constants);
+        let (pc, _) = code
+            .instructions()
+            .map(Result::unwrap)
+            .find(|(_, instruction)| *instruction == Instruction::Ireturn)
+            .unwrap();
+        results.before(pc).unwrap().stack.clone()
+    };
+    assert_eq!(vec![Some(3)], returned(&class_file.methods[0]));
+    assert_eq!(vec![None], returned(&class_file.methods[1]));
+}
+
+#[test]
+fn test_exceptions_attribute() {
+    with_named_class_file("MyAttributes", |class_file| {
+        let exceptions = class_file.methods[1]
+            .attributes
+            .find_map(|info| match info {
+                AttributeInfo::Exceptions(exceptions) => Some(exceptions),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(1, exceptions.len());
+    });
+}
+
+#[test]
+fn test_instructions() {
+    with_class_file(|class_file| {
+        let code = class_file.methods[1].attributes.code_attribute().unwrap();
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Aload0),
+                (1, Instruction::Invokestatic(13)),
+                (4, Instruction::Pop),
+                (5, Instruction::Bipush(11)),
+                (7, Instruction::Iload1),
+                (8, Instruction::Iadd),
+                (9, Instruction::I2f),
+                (10, Instruction::Freturn),
+            ],
+            code.instructions().collect::<Result<Vec<_>, _>>().unwrap()
+        );
+    });
+}
+
+#[test]
+fn test_disassemble() {
+    with_class_file(|class_file| {
+        let output = class_file.disassemble().to_string();
+
+        assert!(output.starts_with("class my/MyClass\n"));
+        assert!(output.contains(
+            "   #1 = Methodref          #2.#3          // java/lang/Object.\"<init>\":()V\n"
+        ));
+        assert!(output.contains(
+            "  1: invokestatic  #13                // Method java/util/Objects.requireNonNull:(Ljava/lang/Object;)Ljava/lang/Object;\n"
+        ));
+        assert!(output.contains("    ConstantValue: int 11\n"));
+        assert!(output.contains("        line 7: 0\n"));
+        assert!(output.ends_with("SourceFile: \"MyClass.java\"\n"));
+    });
+}
+
+#[test]
+fn test_disassemble_cyclic_method_handle() {
+    with_class_file(|mut class_file| {
+        let constant_pool = &mut class_file.constant_pool;
+        let index = constant_pool.len() as u16 + 1;
+        constant_pool
+            .find_or_insert(CpInfo::MethodHandle(MethodHandleInfo {
+                reference_kind: ReferenceKind::InvokeStatic,
+                reference_index: CpIndex::new(index),
+            }))
+            .unwrap();
+
+        let output = class_file.disassemble().to_string();
+
+        assert!(output.contains(&format!(
+            "#{0} = MethodHandle       6:#{0}          // REF_invokeStatic <invalid #{0}>\n",
+            index
+        )));
+    });
+}
+
+#[test]
+fn test_wide_constants() {
+    with_named_class_file("MyConstants", |class_file| {
+        let constant = |field: usize| {
+            let AttributeInfo::ConstantValue {
+                constantvalue_index,
+            } = class_file.fields[field].attributes.0[0].info
+            else {
+                panic!("Expected a ConstantValue attribute");
+            };
+            (
+                class_file.constant_pool[constantvalue_index].clone(),
+                class_file.constant_pool[constantvalue_index + 1].clone(),
+            )
+        };
+
+        assert_eq!((CpInfo::Long(1 << 40), CpInfo::Unusable), constant(0));
+        assert_eq!((CpInfo::Double(0.1), CpInfo::Unusable), constant(1));
+    });
+}
+
+#[test]
+fn test_float_constants() {
+    with_named_class_file("MyConstants", |class_file| {
+        let bits = |field: usize| {
+            let AttributeInfo::ConstantValue {
+                constantvalue_index,
+            } = class_file.fields[field].attributes.0[0].info
+            else {
+                panic!("Expected a ConstantValue attribute");
+            };
+            match class_file.constant_pool[constantvalue_index] {
+                CpInfo::Float(f) => f.to_bits() as u64,
+                CpInfo::Double(d) => d.to_bits(),
+                ref c => panic!("Expected a floating point constant, found {:?}", c),
+            }
+        };
+
+        assert_eq!(f32::INFINITY.to_bits() as u64, bits(2));
+        assert_eq!(f64::NEG_INFINITY.to_bits(), bits(3));
+        assert_eq!(0x7fc00000, bits(4));
+        assert_eq!(1, bits(5));
+        assert_eq!((-0f64).to_bits(), bits(6));
+    });
+}
+
+#[test]
+fn test_nan_payload_round_trip() {
+    let mut bytes = std::fs::read("tests/classes/my/MyConstants.class").unwrap();
+
+    // Replace the canonical NaN emitted by javac with a signaling NaN carrying a payload.
+    let canonical_nan = [4, 0x7f, 0xc0, 0x00, 0x00];
+    let position = bytes
+        .windows(canonical_nan.len())
+        .position(|w| w == canonical_nan)
+        .unwrap();
+    bytes[position + 1..position + 5].copy_from_slice(&[0xff, 0x80, 0x12, 0x34]);
+
+    let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+    let mut written = vec![];
+    class_file.write(&mut written).unwrap();
+
+    assert_eq!(bytes, written);
+}
+
+#[test]
+fn test_modified_utf8_strings() {
+    let string_constant = |class_file: &ClassFile, field: usize| {
+        let AttributeInfo::ConstantValue {
+            constantvalue_index,
+        } = class_file.fields[field].attributes.0[0].info
+        else {
+            panic!("Expected a ConstantValue attribute");
+        };
+        let CpInfo::String { string_index } = class_file.constant_pool[constantvalue_index] else {
+            panic!("Expected a String constant");
+        };
+        class_file.constant_pool[string_index].clone()
+    };
+
+    with_named_class_file("MyStrings", |class_file| {
+        assert_eq!(CpInfo::Utf8("a\0b".into()), string_constant(&class_file, 0));
+        assert_eq!(
+            CpInfo::Utf8("\u{1F600}".into()),
+            string_constant(&class_file, 1)
+        );
+        assert_eq!(
+            CpInfo::Utf8("\u{FFFD}".into()),
+            string_constant(&class_file, 2)
+        );
+    });
+
+    let bytes = std::fs::read("tests/classes/my/MyStrings.class").unwrap();
+    let class_file = Parser::new(std::io::Cursor::new(&bytes))
+        .keep_raw_utf8(true)
+        .parse()
+        .unwrap();
+    assert_eq!(
+        CpInfo::RawUtf8(vec![0xED, 0xA0, 0x80]),
+        string_constant(&class_file, 2)
+    );
+
+    let mut written = vec![];
+    class_file.write(&mut written).unwrap();
+    assert_eq!(bytes, written);
+}
+
+#[test]
+fn test_write_round_trip() {
+    for name in ["MyClass", "MyAttributes", "MyConstants"] {
+        let bytes = std::fs::read(format!("tests/classes/my/{}.class", name)).unwrap();
+        let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+
+        let mut written = vec![];
+        class_file.write(&mut written).unwrap();
+
+        assert_eq!(bytes, written, "{} was not written identically", name);
+    }
+}
+
+#[test]
+fn test_borrowed_class_file() {
+    let bytes = std::fs::read("tests/classes/my/MyClass.class").unwrap();
+    let owned = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+
+    let class_file = borrowed::ClassFile::parse(&bytes).unwrap();
+
+    assert_eq!("my/MyClass", class_file.class_name().unwrap());
+    assert_eq!(
+        Some("java/lang/Object"),
+        class_file.super_class().unwrap().as_deref()
+    );
+    let method = &class_file.methods[1];
+    assert_eq!("add", class_file.method_name(method).unwrap());
+    assert_eq!("(I)F", class_file.method_descriptor(method).unwrap());
+
+    let code = method.code(&class_file.constant_pool).unwrap().unwrap();
+    let owned_code = owned.methods[1].attributes.code_attribute().unwrap();
+    assert_eq!(owned_code.code.as_slice(), code.code);
+    assert_eq!(
+        owned_code
+            .instructions()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+        code.instructions().map(Result::unwrap).collect::<Vec<_>>()
+    );
+    // The code array is borrowed from the class file.
+    assert!(bytes.as_ptr_range().contains(&code.code.as_ptr()));
+}
+
+#[test]
+fn test_borrowed_constant_pool() {
+    let bytes = std::fs::read("tests/classes/my/MyConstants.class").unwrap();
+    let owned = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+
+    let class_file = borrowed::ClassFile::parse(&bytes).unwrap();
+
+    let cp_infos = (1..=class_file.constant_pool.len() as u16)
+        .map(|index| class_file.constant_pool.get(index).unwrap())
+        .collect::<Vec<_>>();
+    // Compared by their debug representation, as the NaN constant is not equal to itself.
+    assert_eq!(
+        format!("{:?}", owned.constant_pool.into_iter().collect::<Vec<_>>()),
+        format!("{:?}", cp_infos.iter().collect::<Vec<_>>())
+    );
+    assert!(matches!(
+        class_file.constant_pool.get(0),
+        Err(ClassFileError::InvalidConstantPoolIndex(0))
+    ));
+    assert!(matches!(
+        class_file.constant_pool.utf8(class_file.this_class),
+        Err(ClassFileError::UnexpectedConstantPoolEntry(
+            "Utf8",
+            CpInfo::Class(_)
+        ))
+    ));
+}
+
+#[test]
+fn test_borrowed_to_class_file() {
+    for name in ["MyClass", "MyAttributes", "MyConstants", "MyControlFlow"] {
+        let bytes = std::fs::read(format!("tests/classes/my/{}.class", name)).unwrap();
+        let class_file = borrowed::ClassFile::parse(&bytes).unwrap();
+
+        let mut written = vec![];
+        class_file
+            .to_class_file()
+            .unwrap()
+            .write(&mut written)
+            .unwrap();
+
+        assert_eq!(bytes, written, "{} was not written identically", name);
+    }
+}
+
+#[test]
+fn test_constant_pool_get() {
+    with_class_file(|class_file| {
+        let constant_pool = &class_file.constant_pool;
+
+        assert_eq!(None, constant_pool.get(0));
+        assert_eq!(None, constant_pool.get(constant_pool.len() as u16 + 1));
+        assert!(matches!(
+            constant_pool.get(class_file.this_class.get()),
+            Some(CpInfo::Class(_))
+        ));
+        assert_eq!(
+            "my/MyClass",
+            constant_pool.class_name(class_file.this_class).unwrap()
+        );
+        assert!(matches!(
+            constant_pool.utf8(CpIndex::new(class_file.this_class.get())),
+            Err(ClassFileError::UnexpectedConstantPoolEntry(
+                "Utf8",
+                CpInfo::Class(_)
+            ))
+        ));
+        assert!(matches!(
+            constant_pool.utf8(CpIndex::new(0)),
+            Err(ClassFileError::InvalidConstantPoolIndex(0))
+        ));
+
+        let attributes = Attributes(vec![Attribute {
+            attribute_name_index: constant_pool.len() as u16 + 1,
+            info: AttributeInfo::Synthetic,
+        }]);
+        assert!(attributes
+            .find_by_name("Synthetic", constant_pool)
+            .is_none());
+    });
+}
+
+#[test]
+fn test_constant_pool_find_or_insert() {
+    with_class_file(|mut class_file| {
+        let constant_pool = &mut class_file.constant_pool;
+        let len = constant_pool.len();
+
+        let method_ref = constant_pool
+            .find_or_insert_method_ref("java/lang/Object", "<init>", "()V")
+            .unwrap();
+        assert_eq!(len, constant_pool.len());
+        let nat = constant_pool.entry(method_ref).unwrap().name_and_type_index;
+        assert_eq!(
+            nat,
+            constant_pool
+                .find_or_insert_name_and_type("<init>", "()V")
+                .unwrap()
+        );
+
+        let long = constant_pool.find_or_insert(CpInfo::Long(1 << 40)).unwrap();
+        assert_eq!(len as u16 + 1, long);
+        assert_eq!(Some(&CpInfo::Unusable), constant_pool.get(long + 1));
+        let zero = constant_pool.find_or_insert(CpInfo::Float(0.0)).unwrap();
+        let negative_zero = constant_pool.find_or_insert(CpInfo::Float(-0.0)).unwrap();
+        let nan = constant_pool
+            .find_or_insert(CpInfo::Float(f32::NAN))
+            .unwrap();
+        assert_ne!(zero, negative_zero);
+        assert_eq!(
+            nan,
+            constant_pool
+                .find_or_insert(CpInfo::Float(f32::NAN))
+                .unwrap()
+        );
+        assert_eq!(len + 5, constant_pool.len());
+    });
+}
+
+#[test]
+fn test_format_check() {
+    for name in ["MyClass", "MyAttributes", "MyConstants", "MyStrings"] {
+        with_named_class_file(name, |class_file| {
+            assert_eq!(Vec::<Diagnostic>::new(), class_file.format_check());
+        });
+    }
+}
+
+#[test]
+fn test_format_check_reports_every_problem() {
+    with_class_file(|mut class_file| {
+        // An interface must be abstract and extend Object.
+        class_file.access_flags |= ClassAccessFlags::INTERFACE;
+        // The class name is not a field descriptor.
+        let this_class = match &class_file.constant_pool[class_file.this_class] {
+            CpInfo::Class(class_info) => class_info.name_index,
+            cp_info => panic!("{:?}", cp_info),
+        };
+        class_file.fields[0].descriptor_index = this_class;
+        let method = class_file.methods[1].clone();
+        class_file.methods.push(method);
+
+        let locations = class_file
+            .format_check()
+            .into_iter()
+            .map(|diagnostic| diagnostic.location)
+            .collect::<Vec<_>>();
+
+        assert!(locations.contains(&Location::ClassFile));
+        assert!(locations.contains(&Location::Field(0)));
+        // The constructor may not be in an interface, but `add` is a valid default method.
+        assert!(locations.contains(&Location::Method(0)));
+        assert!(!locations.contains(&Location::Method(1)));
+        assert!(locations.contains(&Location::Method(2)));
+    });
+}
+
+#[test]
+fn test_format_check_bad_constant_pool_reference() {
+    with_class_file(|mut class_file| {
+        // Typed indices keep this from compiling without converting it explicitly.
+        class_file.super_class = CpIndex::new(class_file.fields[0].name_index.get());
+
+        let diagnostics = class_file.format_check();
+
+        assert_eq!(1, diagnostics.len(), "{:?}", diagnostics);
+        assert_eq!(Location::ClassFile, diagnostics[0].location);
+        assert_eq!(
+            format!(
+                "class file: #{} should be a Class entry, found Utf8",
+                class_file.super_class
+            ),
+            diagnostics[0].to_string()
+        );
+    });
+}
+
+/// The part of the class hierarchy that the test classes refer to.
+fn hierarchy(class_name: &str) -> Option<ResolvedClass> {
+    if class_name == "java/lang/Runnable" {
+        return Some(ResolvedClass {
+            super_class: Some("java/lang/Object".to_owned()),
+            is_interface: true,
+        });
+    }
+
+    let super_class = match class_name {
+        "java/lang/NumberFormatException" => "java/lang/IllegalArgumentException",
+        "java/lang/IllegalArgumentException" => "java/lang/RuntimeException",
+        "java/lang/RuntimeException" => "java/lang/Exception",
+        "java/lang/Exception" => "java/lang/Throwable",
+        "java/lang/Throwable" | "java/lang/Number" => "java/lang/Object",
+        "java/lang/Integer" => "java/lang/Number",
+        _ => return None,
+    };
+    Some(ResolvedClass {
+        super_class: Some(super_class.to_owned()),
+        is_interface: false,
+    })
+}
+
+fn code_mut(method: &mut MethodInfo) -> &mut CodeAttribute {
+    method
+        .attributes
+        .0
+        .iter_mut()
+        .find_map(|attribute| match &mut attribute.info {
+            AttributeInfo::Code(code) => Some(code),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_verify() {
+    for name in ["MyClass", "MyAttributes", "MyConstants", "MyControlFlow"] {
+        with_named_class_file(name, |class_file| {
+            assert_eq!(Ok(()), class_file.verify(&hierarchy));
+        });
+    }
+}
+
+#[test]
+fn test_verify_bad_operand_type() {
+    with_named_class_file("MyControlFlow", |mut class_file| {
+        let method = &mut class_file.methods[1];
+        let code = code_mut(method);
+        let pc = code
+            .instructions()
+            .map(Result::unwrap)
+            .find(|(_, instruction)| *instruction == Instruction::Iadd)
+            .unwrap()
+            .0;
+        code.code[pc as usize] = Instruction::Fadd.opcode();
+
+        let error = class_file
+            .verify_method(&class_file.methods[1], &hierarchy)
+            .unwrap_err();
+
+        assert_eq!("my/MyControlFlow.sum([I)I", error.method);
+        assert_eq!(Some(pc), error.pc);
+        assert_eq!(
+            "Bad type on operand stack: integer is not assignable to float",
+            error.message
+        );
+    });
+}
+
+#[test]
+fn test_verify_missing_stack_map_frame() {
+    with_named_class_file("MyControlFlow", |mut class_file| {
+        code_mut(&mut class_file.methods[2])
+            .attributes
+            .0
+            .retain(|attribute| !matches!(attribute.info, AttributeInfo::StackMapTable(_)));
+
+        let error = class_file.verify(&hierarchy).unwrap_err();
+
+        assert_eq!(
+            "my/MyControlFlow.describe(I)Ljava/lang/String;",
+            error.method
+        );
+        assert!(error
+            .message
+            .starts_with("Expecting a stackmap frame at branch target"));
+    });
+}
+
+#[test]
+fn test_verify_uninitialized_this() {
+    with_named_class_file("MyControlFlow", |mut class_file| {
+        // Skip the call to the superclass constructor.
+        let code = code_mut(&mut class_file.methods[0]);
+        assert_eq!(Instruction::Aload0.opcode(), code.code[0]);
+        assert_eq!(Instruction::Invokespecial(0).opcode(), code.code[1]);
+        code.code[0..4].fill(Instruction::Nop.opcode());
+
+        let error = class_file
+            .verify_method(&class_file.methods[0], &hierarchy)
+            .unwrap_err();
+
+        assert_eq!(Some(9), error.pc);
+        assert_eq!(
+            "Constructor must call super() or this() before return",
+            error.message
+        );
+    });
+}
+
+#[test]
+fn test_verify_array_assignability() {
+    // Verifies a method that returns its int array argument as a `return_type`.
+    let verify = |return_type: &str| {
+        let mut class = ClassBuilder::new("my/Arrays", ClassAccessFlags::PUBLIC).unwrap();
+        class
+            .method(
+                MethodAccessFlags::STATIC,
+                "array",
+                &format!("([I){}", return_type),
+                |code| {
+                    code.aload(0).instruction(Instruction::Areturn);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        let mut class_file = class.build();
+        let code = code_mut(&mut class_file.methods[0]);
+        (code.max_stack, code.max_locals) = (1, 1);
+        class_file.verify(&hierarchy)
+    };
+
+    for return_type in [
+        "Ljava/lang/Object;",
+        "Ljava/lang/Cloneable;",
+        "Ljava/io/Serializable;",
+        "[I",
+    ] {
+        assert_eq!(Ok(()), verify(return_type), "{}", return_type);
+    }
+
+    let error = verify("Ljava/lang/Runnable;").unwrap_err();
+    assert_eq!(Some(1), error.pc);
+}
+
+/// Makes the class file look like one from before Java 6, which has no stack map frames.
+fn downgrade_to_java_5(class_file: &mut ClassFile) {
+    class_file.major_version = 49;
+    for method in &mut class_file.methods {
+        if method.attributes.code_attribute().is_some() {
+            code_mut(method)
+                .attributes
+                .0
+                .retain(|attribute| !matches!(attribute.info, AttributeInfo::StackMapTable(_)));
+        }
+    }
+}
+
+#[test]
+fn test_verify_by_type_inference() {
+    with_named_class_file("MyControlFlow", |mut class_file| {
+        downgrade_to_java_5(&mut class_file);
+
+        assert_eq!(Ok(()), class_file.verify(&hierarchy));
+
+        // Add a float to the int sum in the loop.
+        let code = code_mut(&mut class_file.methods[1]);
+        let pc = code
+            .instructions()
+            .map(Result::unwrap)
+            .find(|(_, instruction)| *instruction == Instruction::Iadd)
+            .unwrap()
+            .0;
+        code.code[pc as usize] = Instruction::Fadd.opcode();
+
+        let error = class_file.verify(&hierarchy).unwrap_err();
+        assert_eq!(Some(pc), error.pc);
+    });
+}
+
+#[test]
+fn test_verify_subroutine() {
+    // Replaces the code of `add(I)F`.
+    let subroutine = |code: &[u8]| {
+        let mut class_file =
+            ClassFile::parse(File::open("tests/classes/my/MyClass.class").unwrap()).unwrap();
+        downgrade_to_java_5(&mut class_file);
+        let attribute = code_mut(&mut class_file.methods[1]);
+        attribute.max_locals = 3;
+        attribute.code = code.to_vec();
+
+        class_file.verify_method(&class_file.methods[1], &hierarchy)
+    };
+
+    let code = [
+        0xa8, 0x00, 0x06, // 0: jsr 6
+        0x1b, // 3: iload_1
+        0x86, // 4: i2f
+        0xae, // 5: freturn
+        0x4d, // 6: astore_2
+        0x84, 0x01, 0x01, // 7: iinc 1, 1
+        0xa9, 0x02, // 10: ret 2
+    ];
+    assert_eq!(Ok(()), subroutine(&code));
+
+    // The return address overwrites the int argument.
+    let code = [
+        0xa8, 0x00, 0x06, // 0: jsr 6
+        0x1b, // 3: iload_1
+        0x86, // 4: i2f
+        0xae, // 5: freturn
+        0x4c, // 6: astore_1
+        0x00, 0x00, 0x00, // 7: nop
+        0xa9, 0x01, // 10: ret 1
+    ];
+    let error = subroutine(&code).unwrap_err();
+    assert_eq!(Some(3), error.pc);
+    assert_eq!(
+        "Bad local variable type: returnAddress(6) is not assignable to integer",
+        error.message
+    );
+}
+
+#[test]
+fn test_compute_frames() {
+    for name in ["MyClass", "MyAttributes", "MyConstants", "MyControlFlow"] {
+        with_named_class_file(name, |class_file| {
+            for method in &class_file.methods {
+                let Some(code) = method.attributes.code_attribute() else {
+                    continue;
+                };
+                let computed = class_file.compute_frames(method, &hierarchy).unwrap();
+
+                assert_eq!(code.max_stack, computed.max_stack);
+                assert_eq!(code.max_locals, computed.max_locals);
+            }
+        });
+    }
+}
+
+#[test]
+fn test_stack_map_table() {
+    with_named_class_file("MyControlFlow", |class_file| {
+        let computed = class_file
+            .compute_frames(&class_file.methods[1], &hierarchy)
+            .unwrap();
+        let mut constant_pool = class_file.constant_pool.clone();
+
+        let stack_map_table = computed.stack_map_table(&mut constant_pool).unwrap();
+
+        // Unlike javac, which drops local variables once they go out of scope, the frames keep
+        // every local variable that has a type on all paths.
+        let javac = class_file.methods[1]
+            .attributes
+            .code_attribute()
+            .unwrap()
+            .attributes
+            .stack_map_table()
+            .unwrap();
+        assert_eq!(javac[0], stack_map_table[0]);
+        assert_eq!(
+            &[
+                StackMapFrame::Append {
+                    offset_delta: 22,
+                    locals: vec![VerificationTypeInfo::Integer]
+                },
+                StackMapFrame::Chop {
+                    k: 1,
+                    offset_delta: 5
+                }
+            ],
+            &stack_map_table[1..]
+        );
+        assert_eq!(class_file.constant_pool, constant_pool);
+    });
+}
+
+#[test]
+fn test_update_frames() {
+    with_named_class_file("MyControlFlow", |mut class_file| {
+        for method in &mut class_file.methods {
+            let code = code_mut(method);
+            code.max_stack = 0;
+            code.max_locals = 0;
+            code.attributes
+                .0
+                .retain(|attribute| !matches!(attribute.info, AttributeInfo::StackMapTable(_)));
+        }
+
+        for i in 0..class_file.methods.len() {
+            class_file.update_frames(i, &hierarchy).unwrap();
+        }
+
+        assert_eq!(Ok(()), class_file.verify(&hierarchy));
+    });
+}
+
+#[test]
+fn test_compute_frames_rejects_subroutines() {
+    let mut class_file =
+        ClassFile::parse(File::open("tests/classes/my/MyClass.class").unwrap()).unwrap();
+    code_mut(&mut class_file.methods[1]).code = vec![
+        0xa8, 0x00, 0x04, // 0: jsr 4
+        0xae, // 3: freturn
+        0x4d, // 4: astore_2
+        0xa9, 0x02, // 5: ret 2
+    ];
+
+    let error = class_file
+        .compute_frames(&class_file.methods[1], &hierarchy)
+        .unwrap_err();
+
+    assert_eq!(Some(0), error.pc);
+    assert_eq!("jsr can't be described by stack map frames", error.message);
+}
+
+#[test]
+fn test_visitor_round_trip() {
+    for name in ["MyClass", "MyAttributes", "MyConstants", "MyControlFlow"] {
+        let bytes = std::fs::read(format!("tests/classes/my/{}.class", name)).unwrap();
+        let mut writer = ClassWriter::new();
+
+        ClassReader::new(&bytes)
+            .unwrap()
+            .accept(&mut writer, ReaderFlags::empty())
+            .unwrap();
+
+        assert_eq!(
+            bytes,
+            writer.to_bytes().unwrap(),
+            "{} was not written identically",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_visitor_skip_debug() {
+    let bytes = std::fs::read("tests/classes/my/MyControlFlow.class").unwrap();
+    let mut writer = ClassWriter::new();
+
+    ClassReader::new(&bytes)
+        .unwrap()
+        .accept(&mut writer, ReaderFlags::SKIP_DEBUG)
+        .unwrap();
+
+    let class_file = ClassFile::parse(std::io::Cursor::new(writer.to_bytes().unwrap())).unwrap();
+    assert!(class_file.attributes.0.is_empty());
+    for method in &class_file.methods {
+        let code = method.attributes.code_attribute().unwrap();
+        assert!(code
+            .attributes
+            .iter()
+            .all(|attribute| matches!(attribute.info, AttributeInfo::StackMapTable(_))));
+    }
+    assert_eq!(Ok(()), class_file.verify(&hierarchy));
+}
+
+#[test]
+fn test_visitor_skip_code() {
+    /// Collects the names of the methods without looking at their code.
+    #[derive(Default)]
+    struct MethodNames {
+        names: Vec<String>,
+        visited_code: bool,
+    }
+    impl ClassVisitor for MethodNames {
+        fn visit_method(
+            &mut self,
+            header: &MethodHeader,
+        ) -> just_class_file::Result<Option<Box<dyn MethodVisitor + '_>>> {
+            self.names.push(header.name.to_string());
+            Ok(Some(Box::new(VisitedCode(&mut self.visited_code))))
+        }
+    }
+    struct VisitedCode<'a>(&'a mut bool);
+    impl MethodVisitor for VisitedCode<'_> {
+        fn visit_code(&mut self) -> just_class_file::Result<bool> {
+            *self.0 = true;
+            Ok(true)
+        }
+    }
+
+    let bytes = std::fs::read("tests/classes/my/MyControlFlow.class").unwrap();
+    let mut visitor = MethodNames::default();
+
+    ClassReader::new(&bytes)
+        .unwrap()
+        .accept(&mut visitor, ReaderFlags::SKIP_CODE)
+        .unwrap();
+
+    assert_eq!(
+        vec!["<init>", "sum", "describe", "parse", "create"],
+        visitor.names
+    );
+    assert!(!visitor.visited_code);
+}
+
+#[test]
+fn test_visitor_adapter() {
+    /// Renames `add` to `subtract`, subtracting instead of adding, and drops the constructor.
+    struct Subtract<'a>(&'a mut ClassWriter);
+    impl ClassVisitor for Subtract<'_> {
+        fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+            Some(self.0)
+        }
+
+        fn visit_method(
+            &mut self,
+            header: &MethodHeader,
+        ) -> just_class_file::Result<Option<Box<dyn MethodVisitor + '_>>> {
+            match header.name.as_ref() {
+                "<init>" => Ok(None),
+                "add" => {
+                    let header = MethodHeader {
+                        name: "subtract".into(),
+                        ..header.clone()
+                    };
+                    let next = self.0.visit_method(&header)?.unwrap();
+                    Ok(Some(Box::new(SubtractInstruction(next))))
+                }
+                _ => self.0.visit_method(header),
+            }
+        }
+    }
+    struct SubtractInstruction<'a>(Box<dyn MethodVisitor + 'a>);
+    impl MethodVisitor for SubtractInstruction<'_> {
+        fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+            Some(self.0.as_mut())
+        }
+
+        fn visit_instruction(
+            &mut self,
+            pc: u32,
+            instruction: &Instruction,
+        ) -> just_class_file::Result<()> {
+            match instruction {
+                Instruction::Iadd => self.0.visit_instruction(pc, &Instruction::Isub),
+                _ => self.0.visit_instruction(pc, instruction),
+            }
+        }
+    }
+
+    let bytes = std::fs::read("tests/classes/my/MyClass.class").unwrap();
+    let mut writer = ClassWriter::new();
+
+    ClassReader::new(&bytes)
+        .unwrap()
+        .accept(&mut Subtract(&mut writer), ReaderFlags::empty())
+        .unwrap();
+
+    let class_file = ClassFile::parse(std::io::Cursor::new(writer.to_bytes().unwrap())).unwrap();
+    assert_eq!(1, class_file.methods.len());
+    let method = &class_file.methods[0];
+    assert_eq!("subtract", class_file.method_name(method).unwrap());
+    assert_eq!("(I)F", class_file.method_descriptor(method).unwrap());
+    let instructions = method
+        .attributes
+        .code_attribute()
+        .unwrap()
+        .instructions()
+        .map(|instruction| instruction.unwrap().1)
+        .collect::<Vec<_>>();
+    assert!(instructions.contains(&Instruction::Isub));
+    assert!(!instructions.contains(&Instruction::Iadd));
+    assert_eq!(Ok(()), class_file.verify(&hierarchy));
+}
+
+#[test]
+fn test_class_builder() {
+    let mut class = ClassBuilder::new(
+        "my/Generated",
+        ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+    )
+    .unwrap();
+    class
+        .field(FieldAccessFlags::PRIVATE, "count", "I")
+        .unwrap()
+        .method(MethodAccessFlags::PUBLIC, "<init>", "()V", |code| {
+            code.aload(0)
+                .invokespecial("java/lang/Object", "<init>", "()V")?
+                .instruction(Instruction::Return);
+            Ok(())
+        })
+        .unwrap()
+        .method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            "sum",
+            "(I)I",
+            |code| {
+                let (condition, body) = (code.new_label(), code.new_label());
+                code.iconst(0)?
+                    .istore(1)
+                    .goto(condition)
+                    .place_label(body)
+                    .iload(1)
+                    .iload(0)
+                    .instruction(Instruction::Iadd)
+                    .istore(1)
+                    .iinc(0, -1)
+                    .place_label(condition)
+                    .iload(0)
+                    .ifgt(body)
+                    .iload(1)
+                    .instruction(Instruction::Ireturn);
+                Ok(())
+            },
+        )
+        .unwrap()
+        .method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            "parse",
+            "(Ljava/lang/String;)I",
+            |code| {
+                let (start, end, handler) = (code.new_label(), code.new_label(), code.new_label());
+                code.place_label(start)
+                    .aload(0)
+                    .invokestatic("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I")?
+                    .place_label(end)
+                    .instruction(Instruction::Ireturn)
+                    .place_label(handler)
+                    .astore(1)
+                    .getstatic("java/lang/System", "out", "Ljava/io/PrintStream;")?
+                    .ldc_string("Not a number")?
+                    .invokevirtual("java/io/PrintStream", "println", "(Ljava/lang/String;)V")?
+                    .iconst(-1)?
+                    .instruction(Instruction::Ireturn)
+                    .try_catch(start, end, handler, Some("java/lang/NumberFormatException"))?;
+                Ok(())
+            },
+        )
+        .unwrap();
+    let mut class_file = class.build();
+    for i in 0..class_file.methods.len() {
+        class_file.update_frames(i, &hierarchy).unwrap();
+    }
+    assert_eq!(Ok(()), class_file.verify(&hierarchy));
+
+    let mut bytes = vec![];
+    class_file.write(&mut bytes).unwrap();
+    let class_file = ClassFile::parse(std::io::Cursor::new(bytes)).unwrap();
+    assert_eq!("my/Generated", class_file.class_name().unwrap());
+    assert_eq!(
+        vec!["<init>", "sum", "parse"],
+        class_file
+            .methods
+            .iter()
+            .map(|method| class_file.method_name(method).unwrap())
+            .collect::<Vec<_>>()
+    );
+    let sum = class_file.methods[1].attributes.code_attribute().unwrap();
+    assert_eq!((2, 2), (sum.max_stack, sum.max_locals));
+    let parse = class_file.methods[2].attributes.code_attribute().unwrap();
+    assert_eq!(1, parse.exception_table.len());
+}
@@ -0,0 +1,44 @@
+package my;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+@Deprecated
+@MyAnnotations.Info(name = "class", tags = {"a", "b"}, level = MyAnnotations.Level.HIGH, type = String.class)
+public class MyAnnotations {
+    @Retention(RetentionPolicy.RUNTIME)
+    public @interface Info {
+        String name();
+
+        String[] tags() default {};
+
+        Level level() default Level.LOW;
+
+        Class<?> type() default Object.class;
+
+        int priority() default 1;
+    }
+
+    @Retention(RetentionPolicy.CLASS)
+    public @interface Internal {
+    }
+
+    @Target(ElementType.TYPE_USE)
+    @Retention(RetentionPolicy.RUNTIME)
+    public @interface NonNull {
+    }
+
+    public enum Level {
+        LOW, HIGH
+    }
+
+    @Internal
+    private List<@NonNull String> names;
+
+    @Info(name = "method", priority = 2)
+    public void run(@Internal int count, @NonNull String name) {
+    }
+}
@@ -0,0 +1,27 @@
+package my;
+
+import java.io.IOException;
+import java.util.List;
+import java.util.function.Supplier;
+
+@Deprecated
+public class MyAttributes<T extends Comparable<T>> {
+    public static final String CONSTANT = "constant";
+
+    private List<? extends T> items;
+
+    public int sum(int[] values) throws IOException {
+        int sum = 0;
+        for (int value : values) {
+            sum += value;
+        }
+        return sum;
+    }
+
+    public Supplier<String> supplier() {
+        return () -> CONSTANT;
+    }
+
+    public class Inner {
+    }
+}
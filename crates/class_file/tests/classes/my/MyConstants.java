@@ -0,0 +1,11 @@
+package my;
+
+public class MyConstants {
+    public static final long LONG = 1L << 40;
+    public static final double DOUBLE = 0.1;
+    public static final float POSITIVE_INFINITY = Float.POSITIVE_INFINITY;
+    public static final double NEGATIVE_INFINITY = Double.NEGATIVE_INFINITY;
+    public static final float NAN = Float.NaN;
+    public static final float SUBNORMAL = Float.MIN_VALUE;
+    public static final double NEGATIVE_ZERO = -0.0;
+}
@@ -0,0 +1,44 @@
+package my;
+
+public class MyControlFlow {
+    private int count;
+
+    public MyControlFlow(int count) {
+        this.count = count;
+    }
+
+    public int sum(int[] values) {
+        int sum = 0;
+        for (int value : values) {
+            if (value > 0) {
+                sum += value;
+            }
+        }
+        return sum;
+    }
+
+    public String describe(int n) {
+        switch (n) {
+            case 0:
+                return "zero";
+            case 1:
+                return "one";
+            default:
+                return "many";
+        }
+    }
+
+    public int parse(String s) {
+        try {
+            return Integer.parseInt(s);
+        } catch (NumberFormatException e) {
+            return -1;
+        } finally {
+            count++;
+        }
+    }
+
+    public static Object create(boolean b) {
+        return new MyControlFlow(b ? 1 : 0);
+    }
+}
@@ -0,0 +1,7 @@
+package my;
+
+public class MyStrings {
+    public static final String NUL = "a\0b";
+    public static final String SUPPLEMENTARY = "\uD83D\uDE00";
+    public static final String UNPAIRED_SURROGATE = "\uD800";
+}
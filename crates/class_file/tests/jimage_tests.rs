@@ -0,0 +1,349 @@
+use std::{collections::HashMap, env, fs::File, io::Read, path::PathBuf};
+
+use just_class_file::{
+    borrowed,
+    cfg::ControlFlowGraph,
+    module::{Exports, ModuleDescriptor, Provides, Requires},
+    verifier::ResolvedClass,
+    visitor::{ClassReader, ClassWriter, ReaderFlags},
+    AttributeInfo, ClassFile, ExportsFlags, Parser, RequiresFlags,
+};
+use just_jimage::Archive;
+use memmap::Mmap;
+
+fn modules_path() -> PathBuf {
+    env::var("JAVA_HOME")
+        .map(|s| PathBuf::from(s).join("lib/modules"))
+        .unwrap()
+}
+
+fn with_archive(f: impl FnOnce(Archive<Mmap>)) {
+    let file = File::open(modules_path()).unwrap();
+    let mmap = unsafe { Mmap::map(&file).unwrap() };
+
+    f(Archive::parse(mmap).unwrap());
+}
+
+fn for_each_class(archive: &Archive<Mmap>, mut f: impl FnMut(&str, Vec<u8>)) {
+    for resource in archive.resources() {
+        if resource.extension() != "class" {
+            continue;
+        }
+
+        let name = resource.full_name();
+        let mut bytes = vec![];
+        archive
+            .by_name(&name)
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        f(&name, bytes);
+    }
+}
+
+#[test]
+fn test_parse_java_base() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            if !name.starts_with("/java.base/") {
+                return;
+            }
+
+            if let Err(e) = ClassFile::parse(std::io::Cursor::new(&bytes)) {
+                panic!("{} failed to parse: {}", name, e);
+            }
+        });
+    });
+}
+
+#[test]
+fn test_resolve_bootstrap_methods() {
+    with_archive(|archive| {
+        let mut lambdas = 0;
+        for_each_class(&archive, |name, bytes| {
+            if !name.starts_with("/java.base/") {
+                return;
+            }
+
+            let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            let count = class_file
+                .attributes
+                .bootstrap_methods()
+                .map_or(0, |methods| methods.len());
+            for index in 0..count as u16 {
+                let bootstrap_method = class_file
+                    .bootstrap_method(index)
+                    .unwrap_or_else(|e| panic!("{} bootstrap method {}: {}", name, index, e));
+                if bootstrap_method.method_handle.owner == "java/lang/invoke/LambdaMetafactory" {
+                    lambdas += 1;
+                }
+            }
+        });
+        assert!(lambdas > 0);
+    });
+}
+
+#[test]
+fn test_module_descriptors() {
+    fn module_descriptor(archive: &Archive<Mmap>, module: &str) -> ModuleDescriptor {
+        let mut bytes = vec![];
+        archive
+            .by_name(&format!("/{}/module-info.class", module))
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        ClassFile::parse(std::io::Cursor::new(&bytes))
+            .unwrap()
+            .module_descriptor()
+            .unwrap()
+            .unwrap()
+    }
+
+    with_archive(|archive| {
+        for resource in archive.resources() {
+            if resource.base() == "module-info" && resource.parent().is_empty() {
+                let descriptor = module_descriptor(&archive, resource.module());
+                assert_eq!(resource.module(), descriptor.name);
+            }
+        }
+
+        let java_base = module_descriptor(&archive, "java.base");
+        assert!(java_base.version.is_some());
+        assert!(java_base.requires.is_empty());
+        assert!(java_base.exports.contains(&Exports {
+            package: "java/lang".to_owned(),
+            flags: ExportsFlags::empty(),
+            targets: vec![],
+        }));
+        assert!(java_base.exports.contains(&Exports {
+            package: "com/sun/security/ntlm".to_owned(),
+            flags: ExportsFlags::empty(),
+            targets: vec!["java.security.sasl".to_owned()],
+        }));
+        assert!(java_base
+            .uses
+            .contains(&"java/nio/file/spi/FileSystemProvider".to_owned()));
+        assert!(java_base.provides.contains(&Provides {
+            service: "java/nio/file/spi/FileSystemProvider".to_owned(),
+            implementations: vec!["jdk/internal/jrtfs/JrtFileSystemProvider".to_owned()],
+        }));
+        assert!(java_base.packages.contains(&"java/lang".to_owned()));
+        assert!(java_base.packages.len() > java_base.exports.len());
+        assert!(!java_base.is_open());
+
+        let java_sql = module_descriptor(&archive, "java.sql");
+        assert_eq!(
+            vec![
+                ("java.base", RequiresFlags::MANDATED),
+                ("java.logging", RequiresFlags::TRANSITIVE),
+                ("java.transaction.xa", RequiresFlags::TRANSITIVE),
+                ("java.xml", RequiresFlags::TRANSITIVE),
+            ],
+            java_sql
+                .requires
+                .iter()
+                .map(|Requires { module, flags, .. }| (module.as_str(), *flags))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["java/sql/Driver".to_owned()], java_sql.uses);
+        assert_eq!(None, java_sql.main_class);
+    });
+}
+
+#[test]
+fn test_borrowed_parse_matches_owned() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            let owned = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            let borrowed = borrowed::ClassFile::parse(&bytes)
+                .unwrap_or_else(|e| panic!("{} failed to parse: {}", name, e));
+
+            let (mut expected, mut written) = (vec![], vec![]);
+            owned.write(&mut expected).unwrap();
+            borrowed
+                .to_class_file()
+                .unwrap()
+                .write(&mut written)
+                .unwrap();
+
+            assert!(expected == written, "{} was not decoded identically", name);
+        });
+    });
+}
+
+#[test]
+fn test_write_round_trip() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            let class_file = Parser::new(std::io::Cursor::new(&bytes))
+                .keep_raw_utf8(true)
+                .parse()
+                .unwrap_or_else(|e| panic!("{} failed to parse: {}", name, e));
+
+            let mut written = vec![];
+            class_file.write(&mut written).unwrap();
+
+            assert!(bytes == written, "{} was not written identically", name);
+        });
+    });
+}
+
+#[test]
+fn test_visitor_round_trip() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            let mut writer = ClassWriter::new();
+            ClassReader::new(&bytes)
+                .and_then(|reader| reader.accept(&mut writer, ReaderFlags::empty()))
+                .unwrap_or_else(|e| panic!("{} failed to be visited: {}", name, e));
+
+            let written = writer.to_bytes().unwrap();
+            assert!(bytes == written, "{} was not written identically", name);
+        });
+    });
+}
+
+#[test]
+fn test_java_base_access_flags_are_legal() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            if !name.starts_with("/java.base/") {
+                return;
+            }
+
+            let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            let check = |what: &str, result: just_class_file::Result<()>| {
+                if let Err(e) = result {
+                    panic!("{} {}: {}", name, what, e);
+                }
+            };
+
+            check("class", class_file.access_flags.check());
+            for field in &class_file.fields {
+                check(
+                    class_file.field_name(field).unwrap(),
+                    field.access_flags.check(class_file.access_flags),
+                );
+            }
+            for method in &class_file.methods {
+                let method_name = class_file.method_name(method).unwrap();
+                if method_name != "<clinit>" {
+                    check(
+                        method_name,
+                        method
+                            .access_flags
+                            .check(class_file.access_flags, class_file.version()),
+                    );
+                }
+            }
+        });
+    });
+}
+
+#[test]
+fn test_java_base_is_well_formed() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            if !name.starts_with("/java.base/") {
+                return;
+            }
+
+            let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            let diagnostics = class_file.format_check();
+            assert!(diagnostics.is_empty(), "{}: {:?}", name, diagnostics);
+        });
+    });
+}
+
+#[test]
+fn test_java_base_control_flow_graphs() {
+    with_archive(|archive| {
+        for_each_class(&archive, |name, bytes| {
+            if !name.starts_with("/java.base/") {
+                return;
+            }
+
+            let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            for method in &class_file.methods {
+                let Some(code) = method.attributes.code_attribute() else {
+                    continue;
+                };
+                let cfg = ControlFlowGraph::new(code).unwrap_or_else(|e| {
+                    panic!(
+                        "{}.{}: {}",
+                        name,
+                        class_file.method_name(method).unwrap(),
+                        e
+                    )
+                });
+
+                // The verifier rejects unreachable code, so the entry dominates every block.
+                let dominators = cfg.dominators();
+                for (i, block) in cfg.blocks.iter().enumerate() {
+                    assert!(dominators.dominates(0, i), "{}: block {}", name, i);
+                    for successor in &block.successors {
+                        assert!(cfg.blocks[*successor].predecessors.contains(&i));
+                    }
+                }
+                for l in cfg.loops() {
+                    assert!(l
+                        .blocks
+                        .iter()
+                        .all(|block| dominators.dominates(l.header, *block)));
+                }
+            }
+        });
+    });
+}
+
+#[test]
+fn test_verify_java_base() {
+    with_archive(|archive| {
+        let mut classes = HashMap::new();
+        let mut java_base = vec![];
+        for_each_class(&archive, |name, bytes| {
+            let class_file = ClassFile::parse(std::io::Cursor::new(&bytes)).unwrap();
+            classes.insert(
+                class_file.class_name().unwrap().to_owned(),
+                ResolvedClass::from_class_file(&class_file).unwrap(),
+            );
+            if name.starts_with("/java.base/") {
+                java_base.push(class_file);
+            }
+        });
+
+        let hierarchy = |class_name: &str| classes.get(class_name).cloned();
+        for class_file in &mut java_base {
+            if let Err(e) = class_file.verify(&hierarchy) {
+                panic!("{}", e);
+            }
+
+            // Recomputed stack map frames must describe the code as well as javac's.
+            for i in 0..class_file.methods.len() {
+                if class_file.methods[i].attributes.code_attribute().is_some() {
+                    class_file.update_frames(i, &hierarchy).unwrap();
+                }
+            }
+            if let Err(e) = class_file.verify(&hierarchy) {
+                panic!("{} with computed frames", e);
+            }
+
+            // Without stack map frames the types must be inferred.
+            class_file.major_version = 49;
+            for method in &mut class_file.methods {
+                for attribute in &mut method.attributes.0 {
+                    if let AttributeInfo::Code(code) = &mut attribute.info {
+                        code.attributes.0.retain(|attribute| {
+                            !matches!(attribute.info, AttributeInfo::StackMapTable(_))
+                        });
+                    }
+                }
+            }
+            if let Err(e) = class_file.verify(&hierarchy) {
+                panic!("{} by type inference", e);
+            }
+        }
+    });
+}
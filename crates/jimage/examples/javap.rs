@@ -0,0 +1,35 @@
+use std::{env, fs::File, path::PathBuf};
+
+use just_class_file::ClassFile;
+use just_jimage::Archive;
+use memmap::Mmap;
+
+fn main() {
+    pretty_env_logger::init();
+
+    let path = env::var("JAVA_HOME")
+        .map(|s| PathBuf::from(s).join("lib/modules"))
+        .unwrap();
+    let file = File::open(path).unwrap();
+    let mmap = unsafe { Mmap::map(&file).unwrap() };
+
+    let archive = Archive::parse(&mmap).unwrap();
+
+    let names = env::args().skip(1).collect::<Vec<_>>();
+    if names.is_empty() {
+        eprintln!("Usage: javap <resource>...  e.g. /java.base/java/lang/Object.class");
+        return;
+    }
+
+    for name in names {
+        let Some(resource) = archive.by_name(&name) else {
+            log::warn!("Resource not found: {}", name);
+            continue;
+        };
+
+        match ClassFile::parse(resource) {
+            Ok(class_file) => print!("{}", class_file.disassemble()),
+            Err(e) => log::error!("Failed to parse {}: {}", name, e),
+        }
+    }
+}
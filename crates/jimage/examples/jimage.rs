@@ -47,7 +47,7 @@ fn main() {
 }
 
 fn strip_module(full_name: &str) -> &str {
-    full_name.splitn(3, "/").skip(2).next().unwrap()
+    full_name.splitn(3, "/").nth(2).unwrap()
 }
 
 fn should_skip_module(module: &str) -> bool {
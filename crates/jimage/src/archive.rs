@@ -121,14 +121,14 @@ where
         &self.index
     }
 
-    pub fn resources(&self) -> Resources<R> {
+    pub fn resources(&self) -> Resources<'_, R> {
         Resources {
             archive: self,
             index: 0,
         }
     }
 
-    pub fn by_name(&self, path: &str) -> Option<Resource<R>> {
+    pub fn by_name(&self, path: &str) -> Option<Resource<'_, R>> {
         let hash_code = hash(path, HASH_MULTIPLIER);
         let index = hash_code % self.index.redirect_table.len() as i32;
         let value = self.index.redirect_table[index as usize];
@@ -157,7 +157,7 @@ where
 
     fn verify(resource: &Resource<R>, path: &str) -> bool {
         // Module
-        let path = if resource.module().len() > 0 {
+        let path = if !resource.module().is_empty() {
             if path.chars().nth(0) != Some('/')
                 || !path[1..].starts_with(resource.module())
                 || path.chars().nth(1 + resource.module().len()) != Some('/')
@@ -170,7 +170,7 @@ where
         };
 
         // Package
-        let path = if resource.parent().len() > 0 {
+        let path = if !resource.parent().is_empty() {
             if !path.starts_with(resource.parent())
                 || path.chars().nth(resource.parent().len()) != Some('/')
             {
@@ -189,7 +189,7 @@ where
         };
 
         // Extension
-        let path = if resource.extension().len() > 0 {
+        let path = if !resource.extension().is_empty() {
             if path.chars().nth(0) != Some('.') || !path[1..].starts_with(resource.extension()) {
                 return false;
             }
@@ -198,15 +198,15 @@ where
             path
         };
 
-        return path.len() == 0;
+        path.is_empty()
     }
 }
 
 fn hash(data: &str, seed: i32) -> i32 {
-    let hash_code = data.bytes().into_iter().fold(seed as u32, |useed, byte| {
+    let hash_code = data.bytes().fold(seed as u32, |useed, byte| {
         (useed.wrapping_mul(HASH_MULTIPLIER as u32)) ^ byte as u32
     });
-    return (hash_code & 0x7fff_ffff) as i32;
+    (hash_code & 0x7fff_ffff) as i32
 }
 
 pub struct Resources<'a, R> {
@@ -291,14 +291,14 @@ where
         let mut s = String::with_capacity(10);
 
         if let Some(module) = self.try_string(AttributeKind::Module) {
-            s.push_str("/");
+            s.push('/');
             s.push_str(module);
-            s.push_str("/");
+            s.push('/');
         }
 
         if let Some(parent) = self.try_string(AttributeKind::Parent) {
             s.push_str(parent);
-            s.push_str("/");
+            s.push('/');
         }
 
         if let Some(base) = self.try_string(AttributeKind::Base) {
@@ -306,7 +306,7 @@ where
         }
 
         if let Some(extension) = self.try_string(AttributeKind::Extension) {
-            s.push_str(".");
+            s.push('.');
             s.push_str(extension);
         }
 
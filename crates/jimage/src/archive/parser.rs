@@ -48,7 +48,7 @@ where
     }
 
     fn parse_header(&mut self) -> Result<Header, JImageError> {
-        let _ = self.parse_magic_identifier()?;
+        self.parse_magic_identifier()?;
         let version = self.parse_version()?;
         let flags = self.read_u32()?;
         let resource_count = self.read_u32()?;
@@ -74,10 +74,10 @@ where
         self.r.read_u32_into::<E>(&mut attribute_offsets)?;
 
         let mut attribute_data = vec![0u8; header.attributes_size as usize];
-        self.r.read(&mut attribute_data)?;
+        self.r.read_exact(&mut attribute_data)?;
 
         let mut strings_data = vec![0u8; header.strings_size as usize];
-        self.r.read(&mut strings_data)?;
+        self.r.read_exact(&mut strings_data)?;
 
         Ok(Index {
             redirect_table,
@@ -105,8 +105,7 @@ where
             return Ok(None);
         }
 
-        let kind =
-            AttributeKind::try_from(kind).map_err(|e| JImageError::InvalidAttributeKind(e))?;
+        let kind = AttributeKind::try_from(kind).map_err(JImageError::InvalidAttributeKind)?;
 
         let value = (0..=length)
             .map(|_| self.read_u8())
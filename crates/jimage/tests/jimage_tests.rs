@@ -32,6 +32,20 @@ fn test_parse_archive_header() {
     });
 }
 
+#[test]
+fn test_truncated_archive() {
+    let file = File::open(modules_path()).unwrap();
+    let mmap = unsafe { Mmap::map(&file).unwrap() };
+    // The strings of the index are cut in half.
+    let length = {
+        let archive = Archive::parse(&mmap[..]).unwrap();
+        let header = archive.header();
+        header.index_size() - header.strings_size as usize / 2
+    };
+
+    assert!(Archive::parse(&mmap[..length]).is_err());
+}
+
 #[test]
 fn test_read_resource() {
     with_archive(|archive| {
@@ -1,4 +1,4 @@
 #!/bin/sh
 
-javac $(find $(dirname $0) -name '*.java')
+javac -g $(find $(dirname $0) -name '*.java')
 